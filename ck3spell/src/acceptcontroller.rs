@@ -0,0 +1,60 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::KbKey;
+
+use crate::appcore::AcceptScope;
+use crate::commands::{ACCEPT_CANCEL, ACCEPT_CONFIRM, ACCEPT_WORD_EXACT, ACCEPT_WORD_LIKE};
+use crate::AppState;
+
+pub struct AcceptController;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for AcceptController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        if data.accepting && !ctx.has_focus() {
+            ctx.request_focus();
+        }
+        if !data.accepting && ctx.has_focus() {
+            ctx.resign_focus();
+        }
+        if let Event::KeyDown(key_event) = event {
+            match &key_event.key {
+                // Ctrl with a number chooses where the word is remembered
+                KbKey::Character(k) if key_event.mods.ctrl() => {
+                    let scope = match k.as_str() {
+                        "1" => Some(AcceptScope::Session),
+                        "2" => Some(AcceptScope::Personal),
+                        "3" => Some(AcceptScope::Project),
+                        _ => None,
+                    };
+                    if let Some(scope) = scope {
+                        data.accept_scope = scope;
+                        return;
+                    }
+                }
+                KbKey::Enter => {
+                    if data.pending_accept.is_some() {
+                        ctx.submit_command(ACCEPT_CONFIRM);
+                    } else if data.accept_model.trim().is_empty() {
+                        ctx.submit_command(ACCEPT_WORD_EXACT);
+                    } else {
+                        ctx.submit_command(ACCEPT_WORD_LIKE);
+                    }
+                    return; // Do not pass the ENTER down to the textbox
+                }
+                KbKey::Escape => {
+                    ctx.submit_command(ACCEPT_CANCEL);
+                    return;
+                }
+                _ => (),
+            }
+        }
+        child.event(ctx, event, data, env);
+    }
+}
@@ -1,18 +1,29 @@
-use anyhow::Context;
 use druid::commands::QUIT_APP;
 use druid::widget::prelude::*;
 use druid::widget::Controller;
-use druid::{Command, KbKey, Target};
+use druid::{Command, KbKey, Target, TimerToken};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::commands::{
-    ACCEPT_WORD, APPLY_EDIT, APPLY_SUGGESTION, CLOSE_GOOD_FILES, CURSOR_CHANGED, CURSOR_NEXT,
-    CURSOR_PREV, DICTIONARY_UPDATED, EDIT_LINE, FILE_CHANGED, GOTO_LINE, SAVE_AND_CLOSE,
+    ACCEPT_CANCEL, ACCEPT_CONFIRM, ACCEPT_WORD, ACCEPT_WORD_EXACT, ACCEPT_WORD_LIKE, APPLY_EDIT,
+    APPLY_KNOWN_FIXES, APPLY_SUGGESTION, CLOSE_FIXES, CLOSE_GOOD_FILES, CLOSE_IGNORES,
+    CLOSE_ISSUES, CLOSE_MISSPELLINGS, CURSOR_CHANGED, CURSOR_NEXT, CURSOR_PREV, DICTIONARY_UPDATED,
+    EDIT_LINE, FILE_CHANGED, FILE_READ, FIX_WHITESPACE, GOTO_FIX, GOTO_ISSUE, GOTO_LINE,
+    GOTO_MISSPELLING, IGNORE_AT_KEY, IGNORE_WORD, MANAGE_IGNORES, MISSPELLINGS_COUNTED,
+    MODEL_FOUND, REMOVE_IGNORE, SAVE_AND_CLOSE, SHOW_ISSUES, SHOW_MISSPELLINGS, SUGGESTIONS_FOUND,
 };
 use crate::AppState;
 
-pub struct AppController;
+/// How long to wait before loading a dictionary, so that the window can
+/// show that it's loading first.
+const LOAD_DELAY: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+pub struct AppController {
+    load_timer: Option<TimerToken>,
+}
 
 impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
     fn event(
@@ -23,89 +34,141 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
         data: &mut AppState,
         env: &Env,
     ) {
-        if data.editing_linenr == 0 && !ctx.has_focus() {
+        if data.editing_linenr == 0 && !data.accepting && !ctx.has_focus() {
             ctx.request_focus();
         }
+        if !data.loading_dictionary.is_empty() && self.load_timer.is_none() {
+            self.load_timer = Some(ctx.request_timer(LOAD_DELAY));
+        }
+        if let Event::Timer(token) = event {
+            if Some(*token) == self.load_timer {
+                self.load_timer = None;
+                data.load_file_dictionary();
+                // Highlight the lines again, now with the dictionary.
+                ctx.submit_command(FILE_CHANGED);
+                ctx.set_handled();
+                return;
+            }
+        }
         if let Event::Command(command) = event {
             if let Some(word) = command.get(APPLY_SUGGESTION) {
-                let mut word: &str = word;
-                if let Some(suffix) = data.cursor_word_fixed_suffix() {
-                    if let Some(stripped) = word.strip_suffix(&suffix) {
-                        word = stripped;
-                    }
+                data.apply_suggestion(word, env);
+            } else if command.is(APPLY_EDIT) {
+                data.apply_edit(env);
+            } else if let Some(&linenr) = command.get(GOTO_LINE) {
+                data.goto_line(linenr);
+                ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
+            } else if let Some(found) = command.get(GOTO_ISSUE) {
+                let file_idx = data.file_idx;
+                data.goto_issue(found);
+                if data.file_idx != file_idx {
+                    ctx.submit_command(FILE_CHANGED);
                 }
-                let wordnr = data.cursor.wordnr;
-                if wordnr > 0 {
-                    data.change_line(data.cursor.linenr, |lineinfo| {
-                        if let Some(range) = lineinfo.bad_words_range.get(wordnr - 1) {
-                            let mut linetext = (*lineinfo.line.line).clone();
-                            linetext.replace_range(range.clone(), word);
-                            lineinfo.line.line = Rc::new(linetext);
-                            lineinfo.highlight(env);
-                        }
-                    });
-                    if data.cursor_word().is_none() {
-                        data.cursor_next();
-                    } else {
-                        data.update_suggestions();
-                    }
+                ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
+            } else if command.is(ACCEPT_WORD) {
+                data.start_accept();
+            } else if command.is(ACCEPT_WORD_EXACT) {
+                if let Some(words) = data.accept_cursor_word(false) {
+                    let words = Rc::new(words);
+                    ctx.submit_command(Command::new(DICTIONARY_UPDATED, words, Target::Auto));
                 }
-            } else if command.is(APPLY_EDIT) && data.editing_linenr > 0 {
-                let new_text = data.editing_text.clone();
-                data.change_line(data.editing_linenr, |lineinfo| {
-                    lineinfo.line.line = Rc::new(new_text.to_string());
-                    lineinfo.highlight(env);
-                });
-                data.editing_linenr = 0;
-                data.editing_text = Arc::new(String::new());
-                if data.cursor_word().is_none() {
-                    data.cursor_next();
-                } else {
-                    data.update_suggestions();
+            } else if let Some((id, found)) = command.get(SUGGESTIONS_FOUND) {
+                data.suggestions_found(*id, found);
+            } else if let Some((id, model)) = command.get(MODEL_FOUND) {
+                data.model_found(*id, model.as_deref());
+            } else if let Some((id, counts)) = command.get(MISSPELLINGS_COUNTED) {
+                data.misspellings_counted(*id, counts);
+            } else if let Some(read) = command.get(FILE_READ) {
+                if let Some((pathname, parsed)) = read.take() {
+                    data.file_read(&pathname, parsed);
                 }
-            } else if let Some(&linenr) = command.get(GOTO_LINE) {
-                let mut cursor = data.cursor;
-                cursor.linenr = linenr;
-                if !data.file.lines[cursor.linenr - 1]
-                    .bad_words_range
-                    .is_empty()
-                {
-                    cursor.wordnr = 1;
-                } else {
-                    cursor.wordnr = 0;
+            } else if command.is(IGNORE_WORD) {
+                // Accept the word for this session only, without adding
+                // it to the user dictionary.
+                if let Some(word) = data.ignore_cursor_word() {
+                    let words = Rc::new(vec![word]);
+                    ctx.submit_command(Command::new(DICTIONARY_UPDATED, words, Target::Auto));
+                }
+            } else if command.is(IGNORE_AT_KEY) {
+                // Ignore the word in this loc key only, and remember
+                // that in the project.
+                if let Some(word) = data.ignore_at_key() {
+                    let words = Rc::new(vec![word]);
+                    ctx.submit_command(Command::new(DICTIONARY_UPDATED, words, Target::Auto));
+                }
+            } else if command.is(MANAGE_IGNORES) {
+                data.managing_ignores = true;
+                data.showing_issues = false;
+                data.showing_misspellings = false;
+                data.showing_fixes = false;
+            } else if let Some(ignore) = command.get(REMOVE_IGNORE) {
+                data.remove_ignore(ignore);
+                // The word may be flagged again on any line of the file.
+                ctx.submit_command(FILE_CHANGED);
+            } else if command.is(CLOSE_IGNORES) {
+                data.managing_ignores = false;
+            } else if command.is(SHOW_ISSUES) {
+                data.showing_issues = true;
+                data.managing_ignores = false;
+                data.showing_misspellings = false;
+                data.showing_fixes = false;
+            } else if command.is(CLOSE_ISSUES) {
+                data.showing_issues = false;
+            } else if command.is(SHOW_MISSPELLINGS) {
+                data.showing_misspellings = true;
+                data.managing_ignores = false;
+                data.showing_issues = false;
+                data.showing_fixes = false;
+                data.update_misspellings();
+            } else if let Some(word) = command.get(GOTO_MISSPELLING) {
+                let file_idx = data.file_idx;
+                data.goto_misspelling(word);
+                if data.file_idx != file_idx {
+                    ctx.submit_command(FILE_CHANGED);
                 }
-                data.update_cursor(cursor);
-                data.update_suggestions();
                 ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
-            } else if command.is(ACCEPT_WORD) {
-                if let Some(cursor_word) = data.cursor_word() {
-                    if let Err(err) = data
-                        .file
-                        .speller
-                        .borrow_mut()
-                        .add_word_to_user_dict(cursor_word)
-                    {
-                        eprintln!("{:#}", err);
-                    }
-                    ctx.submit_command(DICTIONARY_UPDATED);
+            } else if command.is(CLOSE_MISSPELLINGS) {
+                data.showing_misspellings = false;
+            } else if command.is(APPLY_KNOWN_FIXES) {
+                data.apply_known_fixes(env);
+                data.showing_fixes = true;
+                data.managing_ignores = false;
+                data.showing_issues = false;
+                data.showing_misspellings = false;
+                ctx.submit_command(FILE_CHANGED);
+                ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
+            } else if let Some(fix) = command.get(GOTO_FIX) {
+                let file_idx = data.file_idx;
+                data.goto_fix(fix);
+                if data.file_idx != file_idx {
+                    ctx.submit_command(FILE_CHANGED);
+                }
+                ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
+            } else if command.is(CLOSE_FIXES) {
+                data.showing_fixes = false;
+            } else if command.is(ACCEPT_WORD_LIKE) {
+                if let Some(words) = data.accept_cursor_word(true) {
+                    let words = Rc::new(words);
+                    ctx.submit_command(Command::new(DICTIONARY_UPDATED, words, Target::Auto));
+                }
+            } else if command.is(ACCEPT_CONFIRM) {
+                if let Some(words) = data.confirm_accept() {
+                    let words = Rc::new(words);
+                    ctx.submit_command(Command::new(DICTIONARY_UPDATED, words, Target::Auto));
                 }
+            } else if command.is(ACCEPT_CANCEL) {
+                data.accepting = false;
+                data.pending_accept = None;
             } else if command.is(EDIT_LINE) {
-                data.editing_linenr = data.cursor.linenr;
-                data.editing_text = Arc::new(
-                    data.file.lines[data.cursor.linenr - 1]
-                        .line
-                        .line
-                        .to_string(),
-                );
+                data.start_edit();
+            } else if command.is(FIX_WHITESPACE) {
+                data.fix_cursor_whitespace(env);
+                ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
             } else if command.is(SAVE_AND_CLOSE) {
-                if let Err(err) = data.save_file().with_context(|| "Could not save file") {
-                    eprintln!("{:#}", err);
-                }
-                if data.files.len() == 1 {
-                    ctx.submit_command(QUIT_APP);
-                } else {
-                    data.drop_file();
+                if data.save_and_close() {
                     ctx.submit_command(FILE_CHANGED);
+                } else {
+                    ctx.submit_command(QUIT_APP);
                 }
             } else if command.is(CLOSE_GOOD_FILES) {
                 if data.file.is_clean() {
@@ -118,14 +181,18 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
             } else if command.is(CURSOR_NEXT) {
                 data.cursor_next();
                 ctx.submit_command(Command::new(CURSOR_CHANGED, data.cursor, Target::Auto));
+            } else if let Some(words) = command.get(DICTIONARY_UPDATED) {
+                // Not handled here; the lines still have to be highlighted again.
+                data.file.memo.forget(words);
             }
         } else if let Event::KeyDown(key_event) = event {
             match &key_event.key {
                 // Special: accept no hotkeys while editing a line
-                _ if data.editing_linenr > 0 => (),
+                _ if data.editing_linenr > 0 || data.accepting => (),
                 KbKey::Character(a) if a == "a" => ctx.submit_command(ACCEPT_WORD),
                 KbKey::Character(e) if e == "e" => ctx.submit_command(EDIT_LINE),
                 KbKey::Character(c) if c == "c" => ctx.submit_command(SAVE_AND_CLOSE),
+                KbKey::Character(w) if w == "w" => ctx.submit_command(FIX_WHITESPACE),
                 KbKey::Character(k) => {
                     // Number keys select suggestions
                     if let Ok(d) = k.parse::<usize>() {
@@ -144,6 +211,19 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
                 _ => (),
             }
         }
+        let fast_suggestions = data.fast_suggestions;
+        let issue_filter = data.issue_filter;
+        let lines = Arc::clone(&data.file.lines);
         child.event(ctx, event, data, env);
+        if data.fast_suggestions != fast_suggestions {
+            // Look for the cursor word's suggestions again, the new way.
+            data.update_suggestions();
+        }
+        if !Arc::ptr_eq(&data.file.lines, &lines) {
+            // The lines highlight themselves when they are shown.
+            data.lines_changed();
+        } else if data.issue_filter != issue_filter {
+            data.update_issue_list();
+        }
     }
 }
@@ -0,0 +1,211 @@
+//! The app state logic that doesn't need the window: moving the cursor,
+//! applying suggestions, and accepting words. It works on plain data, so
+//! that `AppState` only has to wrap it and it can be tested on its own.
+
+use anyhow::Result;
+use speller::Speller;
+use std::ops::Range;
+
+/// Current highlighted issue, as 1-based line and issue number.
+/// If the issue number is 0 then no issue is highlighted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub linenr: usize,
+    pub issuenr: usize,
+}
+
+impl Default for Cursor {
+    fn default() -> Cursor {
+        Cursor {
+            linenr: 1,
+            issuenr: 0,
+        }
+    }
+}
+
+/// Where an accepted word is remembered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AcceptScope {
+    /// Only until ck3spell exits.
+    Session,
+    /// In the user's local dictionary, if one was given.
+    Personal,
+    /// In the project file, if one was given.
+    Project,
+}
+
+/// Returns where the cursor goes from `cursor` when moving forward: the
+/// next issue on its line, or else the first issue on a later line. If
+/// there is none, it goes to the last line without an issue.
+/// `stops(linenr)` gives the numbers of the issues on a line that the
+/// cursor can go to, in order.
+pub fn cursor_next<I>(cursor: Cursor, nlines: usize, stops: impl Fn(usize) -> I) -> Cursor
+where
+    I: Iterator<Item = usize>,
+{
+    if let Some(issuenr) = stops(cursor.linenr).find(|&nr| nr > cursor.issuenr) {
+        return Cursor { issuenr, ..cursor };
+    }
+    for linenr in cursor.linenr + 1..=nlines {
+        if let Some(issuenr) = stops(linenr).next() {
+            return Cursor { linenr, issuenr };
+        }
+    }
+    Cursor {
+        linenr: nlines,
+        issuenr: 0,
+    }
+}
+
+/// Returns where the cursor goes from `cursor` when moving back, the
+/// same way as `cursor_next`. If there is no issue before it, it goes to
+/// the first line.
+pub fn cursor_prev<I>(cursor: Cursor, stops: impl Fn(usize) -> I) -> Cursor
+where
+    I: DoubleEndedIterator<Item = usize>,
+{
+    if let Some(issuenr) = stops(cursor.linenr).rev().find(|&nr| nr < cursor.issuenr) {
+        return Cursor { issuenr, ..cursor };
+    }
+    for linenr in (1..cursor.linenr).rev() {
+        if let Some(issuenr) = stops(linenr).next_back() {
+            return Cursor { linenr, issuenr };
+        }
+    }
+    Cursor {
+        linenr: 1,
+        issuenr: 0,
+    }
+}
+
+/// Returns the custom ending that was checked together with the text
+/// `wordpart` to make `word`, if there was one. Such an ending is fixed
+/// in the text and can't be changed by suggestions.
+pub fn fixed_suffix<'a>(word: &'a str, wordpart: &str) -> Option<&'a str> {
+    word.strip_prefix(wordpart)
+        .filter(|suffix| !suffix.is_empty())
+}
+
+/// Returns `line` with `range` replaced by `suggestion`. The suggestion
+/// is for the word with its fixed ending, if it has one, and the ending
+/// is already in the line after `range`, so it is left off.
+pub fn apply_suggestion(
+    line: &str,
+    range: Range<usize>,
+    suggestion: &str,
+    fixed_suffix: Option<&str>,
+) -> String {
+    let suggestion = fixed_suffix
+        .and_then(|suffix| suggestion.strip_suffix(suffix))
+        .unwrap_or(suggestion);
+    let mut line = line.to_string();
+    line.replace_range(range, suggestion);
+    line
+}
+
+/// Accept `word` into `speller`, with the endings of `model` if given.
+/// Words accepted into the `Personal` scope are also added to the user
+/// dictionary. Returns false if the word could not be accepted. Keeping
+/// the word in the project file is up to the caller.
+pub fn accept_word(
+    speller: &mut dyn Speller,
+    scope: AcceptScope,
+    word: &str,
+    model: Option<&str>,
+) -> Result<bool> {
+    // A line break or tab would garble the project file.
+    if scope == AcceptScope::Project && word.contains(['\n', '\r', '\t']) {
+        return Ok(false);
+    }
+    Ok(match (scope, model) {
+        (AcceptScope::Personal, None) => speller.add_word_to_user_dict(word)?,
+        (AcceptScope::Personal, Some(model)) => speller.add_word_like_to_user_dict(word, model)?,
+        (_, None) => speller.add_word(word),
+        (_, Some(model)) => speller.add_word_like(word, model),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use speller::SpellerHunspellDict;
+
+    /// The issue numbers that the cursor can go to, per line.
+    const STOPS: [&[usize]; 5] = [&[], &[1, 3], &[], &[2], &[]];
+
+    fn stops(linenr: usize) -> impl DoubleEndedIterator<Item = usize> {
+        STOPS[linenr - 1].iter().copied()
+    }
+
+    fn cursor(linenr: usize, issuenr: usize) -> Cursor {
+        Cursor { linenr, issuenr }
+    }
+
+    #[test]
+    fn test_cursor_next() {
+        let next = |c| cursor_next(c, STOPS.len(), stops);
+        assert_eq!(cursor(2, 1), next(Cursor::default()));
+        assert_eq!(cursor(2, 3), next(cursor(2, 1)));
+        assert_eq!(cursor(4, 2), next(cursor(2, 3)));
+        // Past the last issue, the cursor stays on the last line.
+        assert_eq!(cursor(5, 0), next(cursor(4, 2)));
+        assert_eq!(cursor(5, 0), next(cursor(5, 0)));
+        // From an issue that the cursor doesn't stop at.
+        assert_eq!(cursor(2, 3), next(cursor(2, 2)));
+    }
+
+    #[test]
+    fn test_cursor_prev() {
+        let prev = |c| cursor_prev(c, stops);
+        assert_eq!(cursor(4, 2), prev(cursor(5, 0)));
+        assert_eq!(cursor(2, 3), prev(cursor(4, 2)));
+        assert_eq!(cursor(2, 1), prev(cursor(2, 3)));
+        assert_eq!(cursor(1, 0), prev(cursor(2, 1)));
+        assert_eq!(cursor(1, 0), prev(cursor(1, 0)));
+    }
+
+    #[test]
+    fn test_apply_suggestion() {
+        let line = " key:0 \"Helo world\"";
+        assert_eq!(
+            " key:0 \"Hello world\"",
+            apply_suggestion(line, 8..12, "Hello", None)
+        );
+
+        // "chevalier" + the custom ending "e" from [GetFemEnding]
+        let line = " key:0 \"La chevaliér[GetFemEnding]\"";
+        let word = "chevaliére";
+        let suffix = fixed_suffix(word, &line[11..21]);
+        assert_eq!(Some("e"), suffix);
+        assert_eq!(
+            " key:0 \"La chevalièr[GetFemEnding]\"",
+            apply_suggestion(line, 11..21, "chevalière", suffix)
+        );
+        assert_eq!(None, fixed_suffix("word", "word"));
+    }
+
+    #[test]
+    fn test_accept_word() {
+        let mut speller = SpellerHunspellDict::from_text(
+            "1\nVictorian/S\n",
+            "SET UTF-8\nSFX S Y 1\nSFX S 0 s .\n",
+        )
+        .unwrap();
+        assert!(!speller.spellcheck("Valyrians"));
+        let accepted = accept_word(
+            &mut speller,
+            AcceptScope::Session,
+            "Valyrian",
+            Some("Victorian"),
+        );
+        assert!(accepted.unwrap());
+        assert!(speller.spellcheck("Valyrians"));
+
+        assert!(accept_word(&mut speller, AcceptScope::Project, "olde", None).unwrap());
+        assert!(speller.spellcheck("olde"));
+        assert!(!accept_word(&mut speller, AcceptScope::Project, "two\twords", None).unwrap());
+        // Without a user dictionary the word is only kept in the speller.
+        assert!(accept_word(&mut speller, AcceptScope::Personal, "thee", None).unwrap());
+        assert!(speller.spellcheck("thee"));
+    }
+}
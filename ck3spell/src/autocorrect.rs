@@ -0,0 +1,92 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The autocorrect list that is used if none is given, when it exists in
+/// the current directory.
+pub const AUTOCORRECT_LIST_FILE: &str = ".ck3spell-autocorrect";
+
+/// Replacements for misspellings that are always fixed the same way,
+/// from a file that can be committed together with the mod.
+/// Each line of the file is a misspelling and its replacement, separated
+/// by whitespace, like the REP lines of a hunspell affix file. An
+/// underscore in the replacement stands for a space, as in `alot a_lot`.
+/// Lines starting with `#` are comments.
+#[derive(Debug, Default)]
+pub struct AutoCorrect {
+    rules: HashMap<String, String>,
+}
+
+impl AutoCorrect {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read autocorrect list {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Could not load autocorrect list {}", path.display()))
+    }
+
+    pub(crate) fn parse(contents: &str) -> Result<Self> {
+        let mut list = AutoCorrect::default();
+        for (nr, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [word, fix] => {
+                    list.rules.insert(word.to_string(), fix.replace('_', " "));
+                }
+                _ => bail!("line {}: expected a word and its replacement", nr + 1),
+            }
+        }
+        Ok(list)
+    }
+
+    /// Returns what `word` should be replaced with, if there is a rule
+    /// for it. A rule for a lowercase word also fixes it when it starts
+    /// with a capital, and then the replacement gets one too.
+    pub fn fix(&self, word: &str) -> Option<String> {
+        if let Some(fix) = self.rules.get(word) {
+            return Some(fix.clone());
+        }
+        let mut chars = word.chars();
+        let first = chars.next()?;
+        let rest = chars.as_str();
+        if !first.is_uppercase() || rest.to_lowercase() != rest {
+            return None;
+        }
+        let fix = self.rules.get(&word.to_lowercase())?;
+        let mut chars = fix.chars();
+        let first = chars.next()?;
+        Some(first.to_uppercase().chain(chars).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let contents = "# Common typos\n\
+                        teh the\n\
+                        \n\
+                        recieve\treceive\n\
+                        alot a_lot\n";
+        let list = AutoCorrect::parse(contents).unwrap();
+        assert_eq!(Some("the".to_string()), list.fix("teh"));
+        assert_eq!(Some("receive".to_string()), list.fix("recieve"));
+        assert_eq!(Some("a lot".to_string()), list.fix("alot"));
+        assert_eq!(None, list.fix("the"));
+
+        assert!(AutoCorrect::parse("teh\n").is_err());
+        assert!(AutoCorrect::parse("teh the them\n").is_err());
+    }
+
+    #[test]
+    fn test_capitalized() {
+        let list = AutoCorrect::parse("teh the\n").unwrap();
+        assert_eq!(Some("The".to_string()), list.fix("Teh"));
+        assert_eq!(None, list.fix("TEH"));
+    }
+}
@@ -1,19 +1,43 @@
-use druid::{Rect, Selector};
+use anyhow::Result;
+use druid::{Rect, Selector, SingleUse};
+use speller::Suggestions;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::Cursor;
+use crate::appcore::Cursor;
+use crate::diagnostics::{LineIssue, Misspelling};
+use crate::project::Ignore;
+use crate::{AppliedFix, ParsedFile};
 
 pub const QUERY_LINE_LAYOUT_REGION: Selector<usize> = Selector::new("query_line_layout_region");
 pub const REPLY_LINE_LAYOUT_REGION: Selector<Rect> = Selector::new("reply_line_layout_region");
 
 pub const CURSOR_CHANGED: Selector<Cursor> = Selector::new("cursor_changed");
 
-pub const DICTIONARY_UPDATED: Selector = Selector::new("dictionary_updated");
+/// Carries the words that were added to the dictionary.
+pub const DICTIONARY_UPDATED: Selector<Rc<Vec<String>>> = Selector::new("dictionary_updated");
 
 pub const FILE_CHANGED: Selector = Selector::new("file_changed");
 
 pub const APPLY_SUGGESTION: Selector<Rc<String>> = Selector::new("apply_suggestion");
 
+/// Carries the id of a suggestions search and what it found.
+pub const SUGGESTIONS_FOUND: Selector<(u64, Suggestions)> = Selector::new("suggestions_found");
+
+/// Carries the id of a suggestions search and the model word it found
+/// for accepting the word with endings, if any.
+pub const MODEL_FOUND: Selector<(u64, Option<String>)> = Selector::new("model_found");
+
+/// Carries the id of a count of the misspelled words and the counts.
+pub const MISSPELLINGS_COUNTED: Selector<(u64, Arc<Vec<Misspelling>>)> =
+    Selector::new("misspellings_counted");
+
+/// Carries a file that was read and parsed after the window opened, or
+/// the error from reading it.
+pub const FILE_READ: Selector<SingleUse<(PathBuf, Result<ParsedFile>)>> =
+    Selector::new("file_read");
+
 pub const APPLY_EDIT: Selector = Selector::new("apply_edit");
 
 pub const GOTO_LINE: Selector<usize> = Selector::new("goto_line");
@@ -24,8 +48,39 @@ pub const CURSOR_NEXT: Selector = Selector::new("cursor_next");
 pub const CURSOR_PREV: Selector = Selector::new("cursor_prev");
 pub const EDIT_LINE: Selector = Selector::new("edit_line");
 pub const SAVE_AND_CLOSE: Selector = Selector::new("save_and_close");
+pub const FIX_WHITESPACE: Selector = Selector::new("fix_whitespace");
+
+// Buttons in the accept word panel
+pub const ACCEPT_WORD_EXACT: Selector = Selector::new("accept_word_exact");
+pub const ACCEPT_WORD_LIKE: Selector = Selector::new("accept_word_like");
+pub const ACCEPT_CANCEL: Selector = Selector::new("accept_cancel");
+/// Accept the word even though the dictionary marks it as one to avoid.
+pub const ACCEPT_CONFIRM: Selector = Selector::new("accept_confirm");
 
 // Non-hotkey buttons
+pub const IGNORE_WORD: Selector = Selector::new("ignore_word");
 pub const CLOSE_GOOD_FILES: Selector = Selector::new("close_good_files");
+pub const IGNORE_AT_KEY: Selector = Selector::new("ignore_at_key");
+pub const MANAGE_IGNORES: Selector = Selector::new("manage_ignores");
+pub const SHOW_ISSUES: Selector = Selector::new("show_issues");
+pub const SHOW_MISSPELLINGS: Selector = Selector::new("show_misspellings");
+pub const APPLY_KNOWN_FIXES: Selector = Selector::new("apply_known_fixes");
+
+// Buttons in the ignores panel
+pub const REMOVE_IGNORE: Selector<Ignore> = Selector::new("remove_ignore");
+pub const CLOSE_IGNORES: Selector = Selector::new("close_ignores");
+
+// Buttons in the issues panel
+pub const GOTO_ISSUE: Selector<LineIssue> = Selector::new("goto_issue");
+pub const CLOSE_ISSUES: Selector = Selector::new("close_issues");
+
+// Buttons in the misspellings panel
+/// Carries the misspelled word whose next occurrence to go to.
+pub const GOTO_MISSPELLING: Selector<String> = Selector::new("goto_misspelling");
+pub const CLOSE_MISSPELLINGS: Selector = Selector::new("close_misspellings");
+
+// Buttons in the applied fixes panel
+pub const GOTO_FIX: Selector<AppliedFix> = Selector::new("goto_fix");
+pub const CLOSE_FIXES: Selector = Selector::new("close_fixes");
 
 pub const EDIT_TEXT_AT: Selector<usize> = Selector::new("edit_text_at");
@@ -0,0 +1,55 @@
+use druid::{ExtEventSink, Target};
+use std::cell::Cell;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::commands::MISSPELLINGS_COUNTED;
+use crate::diagnostics::{count_misspellings, Issue};
+
+/// Counts the misspelled words of all the files on a worker thread, so
+/// that editing a line doesn't wait for the counts of a big mod. The
+/// counts arrive with a MISSPELLINGS_COUNTED command. When the lines
+/// change faster than they can be counted, only the latest are counted.
+pub struct MisspellingCounter {
+    queries: Sender<(u64, Vec<Issue>)>,
+    last_id: Cell<u64>,
+}
+
+impl MisspellingCounter {
+    pub fn new(sink: ExtEventSink) -> Self {
+        let (queries, receiver) = channel::<(u64, Vec<Issue>)>();
+        thread::spawn(move || {
+            while let Ok(query) = receiver.recv() {
+                let (id, issues) = receiver.try_iter().last().unwrap_or(query);
+                let counts = Arc::new(count_misspellings(issues.iter()));
+                // This only fails if the window is gone.
+                let _ = sink.submit_command(MISSPELLINGS_COUNTED, (id, counts), Target::Auto);
+            }
+        });
+        MisspellingCounter {
+            queries,
+            last_id: Cell::new(0),
+        }
+    }
+
+    /// A counter without a worker thread, whose counts never arrive, for
+    /// using the app state without a window.
+    pub fn idle() -> Self {
+        let (queries, _) = channel::<(u64, Vec<Issue>)>();
+        MisspellingCounter {
+            queries,
+            last_id: Cell::new(0),
+        }
+    }
+
+    /// Start counting the misspelled words among `issues`. Returns the id
+    /// that the counts will come with.
+    pub fn count(&self, issues: Vec<Issue>) -> u64 {
+        let id = self.last_id.get() + 1;
+        self.last_id.set(id);
+        // The worker only stops when this counter is dropped.
+        let _ = self.queries.send((id, issues));
+        id
+    }
+}
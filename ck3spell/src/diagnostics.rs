@@ -0,0 +1,244 @@
+use druid::Data;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::homoglyph;
+
+/// How much an issue matters. Only errors and warnings keep a file from
+/// counting as clean.
+#[derive(Clone, Copy, Data, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Hint,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Hint => "hint",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Which check found an issue.
+#[derive(Clone, Copy, Data, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// A word that is not in the dictionary.
+    Spelling,
+    /// A word with letters from different scripts that look the same,
+    /// such as a Cyrillic "о" in a Latin word.
+    MixedScript,
+    /// An ellipsis or dash that isn't written the project's way.
+    Typography,
+    /// Markup that is not closed, or closes nothing.
+    Markup,
+    /// A code block that looks malformed.
+    Code,
+    /// Whitespace that should be removed.
+    Whitespace,
+    /// Zero-width and directional characters, which break the text in
+    /// the game.
+    Invisible,
+    /// The text is longer than the maximum length.
+    Length,
+    /// A word too long to be a real word, such as a pasted blob of
+    /// base64, which isn't spellchecked.
+    LongWord,
+}
+
+impl Category {
+    pub fn severity(self) -> Severity {
+        match self {
+            Category::Spelling | Category::MixedScript | Category::Markup | Category::Code => {
+                Severity::Error
+            }
+            Category::Typography | Category::Invisible => Severity::Warning,
+            Category::Whitespace | Category::Length | Category::LongWord => Severity::Hint,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Spelling => "spelling",
+            Category::MixedScript => "mixed scripts",
+            Category::Typography => "typography",
+            Category::Markup => "markup",
+            Category::Code => "code",
+            Category::Whitespace => "whitespace",
+            Category::Invisible => "invisible character",
+            Category::Length => "too long",
+            Category::LongWord => "not a word",
+        }
+    }
+
+    /// Whether suggestions can be given for issues of this category.
+    pub fn is_word(self) -> bool {
+        matches!(
+            self,
+            Category::Spelling | Category::MixedScript | Category::Typography
+        )
+    }
+}
+
+/// Which issues the cursor goes through with Up and Down.
+#[derive(Clone, Copy, Data, Debug, PartialEq, Eq)]
+pub enum IssueFilter {
+    /// The words that suggestions can be given for.
+    Words,
+    /// All issues, including the hints.
+    All,
+    Only(Category),
+}
+
+impl IssueFilter {
+    pub fn accepts(self, category: Category) -> bool {
+        match self {
+            IssueFilter::Words => category.is_word(),
+            IssueFilter::All => true,
+            IssueFilter::Only(only) => category == only,
+        }
+    }
+}
+
+/// Something wrong with a piece of a line.
+#[derive(Clone, Data, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub category: Category,
+    /// The part of the line to highlight.
+    #[data(eq)]
+    pub range: Range<usize>,
+    /// The text that the issue is about. Usually the text in `range`,
+    /// but a misspelled word may have a custom ending added to it.
+    pub text: String,
+}
+
+impl Issue {
+    pub fn new(category: Category, range: Range<usize>, text: &str) -> Self {
+        Issue {
+            category,
+            range,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.category.severity()
+    }
+
+    /// A short description for the issues panel.
+    pub fn describe(&self) -> String {
+        match self.category {
+            Category::Whitespace | Category::Length => self.category.name().to_string(),
+            Category::Invisible => {
+                let codes: Vec<String> = self
+                    .text
+                    .chars()
+                    .map(|c| format!("U+{:04X}", c as u32))
+                    .collect();
+                format!("{}: {}", self.category.name(), codes.join(" "))
+            }
+            Category::LongWord => format!(
+                "{}: {} characters",
+                self.category.name(),
+                self.text.chars().count()
+            ),
+            Category::MixedScript => format!(
+                "{}: {} ({})",
+                self.category.name(),
+                self.text,
+                homoglyph::describe(&self.text)
+            ),
+            _ => format!("{}: {}", self.category.name(), self.text),
+        }
+    }
+}
+
+/// An issue together with the file and line it's on, for the issues panel.
+#[derive(Clone, Data, Debug)]
+pub struct LineIssue {
+    pub pathname: Rc<PathBuf>,
+    pub line_nr: usize,
+    pub issue: Issue,
+}
+
+/// A misspelled word and how often it occurs, for the misspellings panel.
+#[derive(Clone, Data, Debug, PartialEq, Eq)]
+pub struct Misspelling {
+    pub word: String,
+    pub count: usize,
+}
+
+/// Count how often each of the misspelled words of `issues` occurs.
+/// The most common ones come first, and words that are equally common
+/// are in alphabetical order.
+pub fn count_misspellings<'a>(issues: impl Iterator<Item = &'a Issue>) -> Vec<Misspelling> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for issue in issues.filter(|issue| issue.category == Category::Spelling) {
+        *counts.entry(&issue.text).or_default() += 1;
+    }
+    let mut list: Vec<Misspelling> = counts
+        .into_iter()
+        .map(|(word, count)| Misspelling {
+            word: word.to_string(),
+            count,
+        })
+        .collect();
+    list.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    list
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let issue = Issue::new(Category::Spelling, 4..8, "wrod");
+        assert_eq!("spelling: wrod", issue.describe());
+        assert_eq!(Severity::Error, issue.severity());
+        let issue = Issue::new(Category::Invisible, 2..5, "\u{200b}");
+        assert_eq!("invisible character: U+200B", issue.describe());
+        assert_eq!(Severity::Warning, issue.severity());
+        let issue = Issue::new(Category::MixedScript, 0..6, "C\u{43e}lor");
+        assert_eq!(
+            "mixed scripts: C\u{43e}lor (Cyrillic \u{43e})",
+            issue.describe()
+        );
+        let issue = Issue::new(Category::LongWord, 0..120, &"x".repeat(120));
+        assert_eq!("not a word: 120 characters", issue.describe());
+        assert_eq!(Severity::Hint, issue.severity());
+        let issue = Issue::new(Category::Whitespace, 0..2, "  ");
+        assert_eq!("whitespace", issue.describe());
+        assert!(Severity::Hint > Severity::Warning);
+    }
+
+    #[test]
+    fn test_filter() {
+        assert!(IssueFilter::Words.accepts(Category::Typography));
+        assert!(!IssueFilter::Words.accepts(Category::Markup));
+        assert!(IssueFilter::All.accepts(Category::Length));
+        assert!(IssueFilter::Only(Category::Code).accepts(Category::Code));
+        assert!(!IssueFilter::Only(Category::Code).accepts(Category::Spelling));
+    }
+
+    #[test]
+    fn test_count_misspellings() {
+        let issues = [
+            Issue::new(Category::Spelling, 0..4, "wrld"),
+            Issue::new(Category::Spelling, 5..9, "helo"),
+            Issue::new(Category::Markup, 10..12, "#b"),
+            Issue::new(Category::Spelling, 0..4, "wrld"),
+            Issue::new(Category::Spelling, 0..3, "teh"),
+        ];
+        let list = count_misspellings(issues.iter());
+        let words: Vec<(&str, usize)> = list.iter().map(|m| (m.word.as_str(), m.count)).collect();
+        assert_eq!(vec![("wrld", 2), ("helo", 1), ("teh", 1)], words);
+    }
+}
@@ -24,7 +24,7 @@ impl<W: Widget<AppState>> Controller<AppState, W> for EditorController {
         }
         if let Event::Command(command) = event {
             if command.is(EDIT_LINE) {
-                if let Some(range) = data.file.lines[data.editing_linenr - 1].marked_word() {
+                if let Some(range) = data.file.lines[data.editing_linenr - 1].marked_range() {
                     ctx.submit_command(Command::new(EDIT_TEXT_AT, range.start, Target::Auto));
                 }
             }
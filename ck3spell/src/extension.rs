@@ -0,0 +1,257 @@
+//! Dictionaries packaged as LibreOffice (.oxt) or Mozilla (.xpi) extensions.
+//!
+//! Both kinds of extension are zip files that contain the .dic and .aff
+//! files somewhere inside. LibreOffice also unpacks installed extensions
+//! into directories that keep the .oxt name, so those are searched too.
+//! Dictionaries found in zip files are extracted to a cache directory.
+
+use anyhow::{anyhow, Context, Result};
+use fnv::FnvHasher;
+use home::home_dir;
+use std::env::var_os;
+use std::ffi::OsStr;
+use std::fs::{create_dir_all, read_dir, remove_file, rename, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{copy, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+use zip::ZipArchive;
+
+/// Mozilla dictionaries use `en-US` where hunspell uses `en_US`.
+fn dictionary_names(locale: &str) -> [(String, String); 2] {
+    let dashed = locale.replace('_', "-");
+    [
+        (format!("{}.dic", locale), format!("{}.aff", locale)),
+        (format!("{}.dic", dashed), format!("{}.aff", dashed)),
+    ]
+}
+
+fn is_extension(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(OsStr::to_str),
+        Some("oxt" | "xpi" | "OXT" | "XPI")
+    )
+}
+
+/// The entries of `dir`, sorted to make the result predictable if
+/// several extensions match.
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The extensions that may hold dictionaries for a search path
+/// directory. Those are the extensions in `dir` itself, and if `dir` is
+/// the `uno_packages` directory of a LibreOffice profile, the extensions
+/// that LibreOffice installed in its `cache/uno_packages/*/` directories.
+fn extensions(dir: &Path) -> Vec<PathBuf> {
+    let installed = sorted_entries(&dir.join("cache").join("uno_packages"))
+        .into_iter()
+        .filter(|path| path.is_dir());
+    [dir.to_path_buf()]
+        .into_iter()
+        .chain(installed)
+        .flat_map(|dir| sorted_entries(&dir))
+        .filter(|path| is_extension(path))
+        .collect()
+}
+
+/// Look for a dictionary for `locale` in the extensions in `dir`.
+/// Return a tuple of paths to the dictionary file and the affix file.
+pub fn dictionary_in_extensions(dir: &Path, locale: &str) -> Option<(PathBuf, PathBuf)> {
+    find_in_extensions(dir, locale, user_cache_dir().as_deref())
+}
+
+fn find_in_extensions(
+    dir: &Path,
+    locale: &str,
+    cache: Option<&Path>,
+) -> Option<(PathBuf, PathBuf)> {
+    extensions(dir)
+        .iter()
+        .find_map(|path| find_in_extension(path, locale, cache))
+}
+
+fn find_in_extension(
+    path: &Path,
+    locale: &str,
+    cache: Option<&Path>,
+) -> Option<(PathBuf, PathBuf)> {
+    if path.is_dir() {
+        return find_in_unpacked(path, locale);
+    }
+    let result = cache
+        .ok_or_else(|| anyhow!("No cache directory to extract {} to", path.display()))
+        .and_then(|cache| extract_dictionary(path, locale, cache));
+    match result {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            None
+        }
+    }
+}
+
+/// Look for the dictionary in an unpacked extension. Extensions keep
+/// their dictionaries at the top or in a directory just below it, such
+/// as `dictionaries/`.
+fn find_in_unpacked(dir: &Path, locale: &str) -> Option<(PathBuf, PathBuf)> {
+    let subdirs = sorted_entries(dir).into_iter().filter(|path| path.is_dir());
+    for dir in [dir.to_path_buf()].into_iter().chain(subdirs) {
+        for (dic, aff) in dictionary_names(locale) {
+            let pdic = dir.join(dic);
+            let paff = dir.join(aff);
+            if pdic.exists() && paff.exists() {
+                return Some((pdic, paff));
+            }
+        }
+    }
+    None
+}
+
+/// The user's own directory for cached files, where extracted
+/// dictionaries are kept so that other users can't change them.
+fn user_cache_dir() -> Option<PathBuf> {
+    let from_env = |var| {
+        var_os(var)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    let base = if cfg!(windows) {
+        from_env("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library").join("Caches"))
+    } else {
+        from_env("XDG_CACHE_HOME").or_else(|| home_dir().map(|home| home.join(".cache")))
+    };
+    Some(base?.join("ck3spell").join("dicts"))
+}
+
+/// The directory that dictionaries from `archive` are extracted to.
+/// Its name depends on where the archive is and when it was changed,
+/// so that different extensions with the same name don't share it and
+/// a changed extension gets a fresh copy.
+fn extract_dir(cache: &Path, archive: &Path) -> Result<PathBuf> {
+    let modified = archive
+        .metadata()
+        .and_then(|meta| meta.modified())
+        .with_context(|| format!("Could not read {}", archive.display()))?;
+    let mut hasher = FnvHasher::default();
+    archive
+        .canonicalize()
+        .unwrap_or_else(|_| archive.to_path_buf())
+        .hash(&mut hasher);
+    modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    Ok(cache.join(format!("{}-{:016x}", name, hasher.finish())))
+}
+
+/// The entries of `zip` that hold the dictionary and affix file for
+/// `locale`, each with the name to extract it as.
+fn dictionary_entries<R: Read + Seek>(
+    zip: &ZipArchive<R>,
+    locale: &str,
+) -> Option<[(String, String); 2]> {
+    dictionary_names(locale).into_iter().find_map(|(dic, aff)| {
+        let find = |name: &str| {
+            zip.file_names()
+                .find(|entry| entry.rsplit('/').next() == Some(name))
+                .map(str::to_string)
+        };
+        Some([(find(&dic)?, dic), (find(&aff)?, aff)])
+    })
+}
+
+/// Extract the dictionary for `locale` from the zip file `archive` into
+/// `cache`, unless it was extracted there before.
+fn extract_dictionary(
+    archive: &Path,
+    locale: &str,
+    cache: &Path,
+) -> Result<Option<(PathBuf, PathBuf)>> {
+    let file =
+        File::open(archive).with_context(|| format!("Could not open {}", archive.display()))?;
+    let mut zip = ZipArchive::new(file)
+        .with_context(|| format!("Could not read extension {}", archive.display()))?;
+    let Some(entries) = dictionary_entries(&zip, locale) else {
+        return Ok(None);
+    };
+
+    let dir = extract_dir(cache, archive)?;
+    create_dir_all(&dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let [pdic, paff] = entries.map(|(entry, name)| (entry, dir.join(name)));
+    for (entry, target) in [&pdic, &paff] {
+        // Files are only ever put there whole, by the rename below.
+        if target.exists() {
+            continue;
+        }
+        // Write to a new file that nobody else can have made, so that
+        // a planted symlink can't redirect the write, and only then
+        // give it its name.
+        let temp = target.with_extension(format!("tmp{}", process::id()));
+        let _ = remove_file(&temp);
+        let mut out = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("Could not create {}", temp.display()))?;
+        let mut contents = zip.by_name(entry)?;
+        copy(&mut contents, &mut out)
+            .with_context(|| format!("Could not extract {}", target.display()))?;
+        rename(&temp, target).with_context(|| format!("Could not create {}", target.display()))?;
+    }
+    Ok(Some((pdic.1, paff.1)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXTENSIONS: &str = "tests/files/extensions";
+
+    #[test]
+    fn test_dictionary_entries() {
+        let xpi = Path::new(EXTENSIONS).join("one/dict.xpi");
+        let zip = ZipArchive::new(File::open(xpi).unwrap()).unwrap();
+        let entry = |entry: &str, name: &str| (entry.to_string(), name.to_string());
+        assert_eq!(
+            Some([
+                entry("dictionaries/xx-YY.dic", "xx-YY.dic"),
+                entry("dictionaries/xx-YY.aff", "xx-YY.aff"),
+            ]),
+            dictionary_entries(&zip, "xx_YY")
+        );
+        assert_eq!(None, dictionary_entries(&zip, "zz_ZZ"));
+    }
+
+    #[test]
+    fn test_extract_dir() {
+        let dir = Path::new(EXTENSIONS);
+        let cache = Path::new("cache");
+        let one = extract_dir(cache, &dir.join("one/dict.xpi")).unwrap();
+        assert!(one.starts_with(cache));
+        // An extension with the same name elsewhere gets its own copy.
+        let two = extract_dir(cache, &dir.join("two/dict.xpi")).unwrap();
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn test_extension_layout() {
+        let dir = Path::new(EXTENSIONS).join("profile");
+        let (dic, _) = find_in_extensions(&dir, "xx_YY", None).unwrap();
+        let installed = dir.join("cache/uno_packages/lu1234.tmp_/dict-xx.oxt/dictionaries");
+        assert_eq!(installed.join("xx_YY.dic"), dic);
+        // Directories outside the known layout are not searched.
+        assert_eq!(None, find_in_extensions(&dir, "zz_ZZ", None));
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::{Display, Formatter};
+
+/// The alphabets whose letters are mixed up in words, because some of
+/// their letters look the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+        match c as u32 {
+            0x41..=0x24f | 0x1e00..=0x1eff => Some(Script::Latin),
+            0x400..=0x52f => Some(Script::Cyrillic),
+            0x370..=0x3ff | 0x1f00..=0x1fff => Some(Script::Greek),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Script {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            Script::Latin => "Latin",
+            Script::Cyrillic => "Cyrillic",
+            Script::Greek => "Greek",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Groups of letters from different scripts that look the same, written
+/// as escapes because they can't be told apart otherwise.
+const LOOKALIKES: [&str; 27] = [
+    "A\u{410}\u{391}",
+    "B\u{412}\u{392}",
+    "C\u{421}",
+    "E\u{415}\u{395}",
+    "H\u{41d}\u{397}",
+    "I\u{406}\u{399}",
+    "J\u{408}",
+    "K\u{41a}\u{39a}",
+    "M\u{41c}\u{39c}",
+    "N\u{39d}",
+    "O\u{41e}\u{39f}",
+    "P\u{420}\u{3a1}",
+    "S\u{405}",
+    "T\u{422}\u{3a4}",
+    "X\u{425}\u{3a7}",
+    "Y\u{3a5}",
+    "Z\u{396}",
+    "a\u{430}",
+    "c\u{441}",
+    "e\u{435}",
+    "i\u{456}",
+    "j\u{458}",
+    "o\u{43e}\u{3bf}",
+    "p\u{440}\u{3c1}",
+    "s\u{455}",
+    "x\u{445}\u{3c7}",
+    "y\u{443}",
+];
+
+/// The scripts of the letters in `word`, with how many letters each
+/// has, the most common first. Ties go to Latin, then Cyrillic.
+fn scripts(word: &str) -> Vec<(Script, usize)> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in word.chars().filter_map(Script::of) {
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    counts.sort_by_key(|&(script, count)| (usize::MAX - count, script as u8));
+    counts
+}
+
+/// Returns true if `word` has letters from more than one script, such as
+/// a Cyrillic "о" in a Latin word. These words look right but are not
+/// found in the dictionary, and can't be found by searching the text.
+pub fn is_mixed(word: &str) -> bool {
+    scripts(word).len() > 1
+}
+
+/// Returns `word` with all its letters in one script, by replacing the
+/// letters of the other scripts with the ones that look the same. The
+/// script with the most letters in the word is tried first. Returns
+/// None if the word isn't mixed, or if there's no script that all its
+/// letters have a lookalike in.
+pub fn unmix(word: &str) -> Option<String> {
+    if !is_mixed(word) {
+        return None;
+    }
+    scripts(word)
+        .into_iter()
+        .find_map(|(script, _)| word.chars().map(|c| lookalike(c, script)).collect())
+}
+
+/// Returns the letter of `script` that looks like `c`, which may be `c`
+/// itself.
+fn lookalike(c: char, script: Script) -> Option<char> {
+    match Script::of(c) {
+        None => Some(c),
+        Some(s) if s == script => Some(c),
+        Some(_) => LOOKALIKES
+            .iter()
+            .find(|group| group.contains(c))?
+            .chars()
+            .find(|&l| Script::of(l) == Some(script)),
+    }
+}
+
+/// Describe the letters of `word` that are not in its main script, such
+/// as "Cyrillic о".
+pub fn describe(word: &str) -> String {
+    let Some(&(main, _)) = scripts(word).first() else {
+        return String::new();
+    };
+    let mut odd: Vec<String> = Vec::new();
+    for c in word.chars() {
+        if let Some(script) = Script::of(c).filter(|&s| s != main) {
+            let desc = format!("{} {}", script, c);
+            if !odd.contains(&desc) {
+                odd.push(desc);
+            }
+        }
+    }
+    odd.join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_mixed() {
+        assert!(is_mixed("C\u{43e}lor")); // Cyrillic о
+        assert!(is_mixed("\u{43f}pивет")); // Latin p
+        assert!(!is_mixed("Color"));
+        assert!(!is_mixed("привет"));
+        assert!(!is_mixed("Élève"));
+        assert!(!is_mixed("word123"));
+    }
+
+    #[test]
+    fn test_unmix() {
+        assert_eq!(Some("Color".to_string()), unmix("C\u{43e}lor"));
+        assert_eq!(Some("привет".to_string()), unmix("\u{43f}pивет"));
+        // The Cyrillic "д" has no Latin lookalike, so the word is taken
+        // to be Cyrillic even though most of it is Latin.
+        assert_eq!(Some("срод".to_string()), unmix("cpoд"));
+        assert_eq!(None, unmix("fд"));
+        assert_eq!(None, unmix("Color"));
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!("Cyrillic о", describe("C\u{43e}l\u{43e}r"));
+        assert_eq!("Latin p, Latin t", describe("\u{43f}pивеt"));
+    }
+}
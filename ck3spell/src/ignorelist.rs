@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The ignore list that is used if none is given, when it exists in the
+/// current directory.
+pub const IGNORE_LIST_FILE: &str = ".ck3spell-ignore";
+
+/// Words that are never flagged in a project, from a file that can be
+/// committed together with the mod. Unlike accepted words they don't go
+/// into the dictionary, so they aren't suggested and don't get endings.
+/// Each line of the file is a word, or a regular expression between
+/// slashes that has to match the whole word, such as `/[A-Z][a-z]+ii/`.
+/// Lines starting with `#` are comments.
+#[derive(Debug, Default)]
+pub struct IgnoreList {
+    words: HashSet<String>,
+    patterns: Vec<Regex>,
+}
+
+impl IgnoreList {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read ignore list {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Could not load ignore list {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        let mut list = IgnoreList::default();
+        for (nr, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix('/').and_then(|l| l.strip_suffix('/')) {
+                Some(pattern) => match Regex::new(&format!("^(?:{})$", pattern)) {
+                    Ok(re) => list.patterns.push(re),
+                    Err(err) => bail!("line {}: {}", nr + 1, err),
+                },
+                None => {
+                    list.words.insert(line.to_string());
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// Returns true if `word` should not be flagged.
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) || self.patterns.iter().any(|re| re.is_match(word))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let contents = "# Names from the mod\n\
+                        Aethelred\n\
+                        \n\
+                        /[A-Z][a-z]+ii/\n\
+                        /\n";
+        let list = IgnoreList::parse(contents).unwrap();
+        assert!(list.contains("Aethelred"));
+        assert!(!list.contains("aethelred"));
+        assert!(list.contains("Valerii"));
+        // The expression has to match the whole word.
+        assert!(!list.contains("Valeriius"));
+        assert!(!list.contains("# Names from the mod"));
+        // A lone slash is a word.
+        assert!(list.contains("/"));
+
+        assert!(IgnoreList::parse("/(unclosed/\n").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use druid::{Env, Key};
+
+use crate::syntax::Join;
+
+pub const CHECK_CUSTOM_ENDING: Key<bool> = Key::new("ck3spell.check-custom-ending");
+pub const CHECK_HYPHEN_BEFORE: Key<bool> = Key::new("ck3spell.check-hyphen-before");
+pub const CHECK_HYPHEN_AFTER: Key<bool> = Key::new("ck3spell.check-hyphen-after");
+
+/// How to check a word token, depending on how it's joined to the code next to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinPolicy {
+    /// Check it with each of the endings that the Custom key can give.
+    CustomEndings,
+    /// Check it together with the word parts on the other side of the
+    /// code, as one word. See `joined_word`.
+    CheckJoined,
+    /// Check it as a word by itself.
+    CheckAlone,
+    /// Don't check it, because the code supplies the rest of the word.
+    Skip,
+}
+
+/// Which of the word parts that are joined to code are checked.
+#[derive(Clone, Copy, Debug)]
+pub struct JoinPolicies {
+    custom_ending: bool,
+    hyphen_before: bool,
+    hyphen_after: bool,
+}
+
+impl JoinPolicies {
+    /// Returns policies that check the word parts joined to code in all
+    /// the ways except the ones in `skipped`.
+    pub fn all_except(skipped: &[Join]) -> Self {
+        JoinPolicies {
+            custom_ending: !skipped.contains(&Join::CustomEnding),
+            hyphen_before: !skipped.contains(&Join::HyphenBefore),
+            hyphen_after: !skipped.contains(&Join::HyphenAfter),
+        }
+    }
+
+    pub fn from_env(env: &Env) -> Self {
+        JoinPolicies {
+            custom_ending: env.get(CHECK_CUSTOM_ENDING),
+            hyphen_before: env.get(CHECK_HYPHEN_BEFORE),
+            hyphen_after: env.get(CHECK_HYPHEN_AFTER),
+        }
+    }
+
+    pub fn set_env(&self, env: &mut Env) {
+        env.set(CHECK_CUSTOM_ENDING, self.custom_ending);
+        env.set(CHECK_HYPHEN_BEFORE, self.hyphen_before);
+        env.set(CHECK_HYPHEN_AFTER, self.hyphen_after);
+    }
+
+    /// How to check a word part that is joined to code by `join`.
+    pub fn policy(&self, join: Join) -> JoinPolicy {
+        match join {
+            Join::CustomEnding if self.custom_ending => JoinPolicy::CustomEndings,
+            // `pre-[GetEra]` is checked as "pre-", so that a prefix that
+            // isn't a word by itself can be accepted with its hyphen.
+            Join::HyphenBefore if self.hyphen_before => JoinPolicy::CheckJoined,
+            Join::HyphenAfter if self.hyphen_after => JoinPolicy::CheckJoined,
+            // The rest of the word in `$NAME$s` is the key's text.
+            _ => JoinPolicy::Skip,
+        }
+    }
+}
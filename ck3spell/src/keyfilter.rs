@@ -0,0 +1,48 @@
+use regex::Regex;
+
+/// Which loc keys get their texts checked, from the `--only-keys` and
+/// `--skip-keys` options. A pattern matches if it matches anywhere in
+/// the key, so `_debug_desc$` skips all the keys that end that way.
+#[derive(Debug, Default)]
+pub struct KeyFilter {
+    only: Vec<Regex>,
+    skip: Vec<Regex>,
+}
+
+impl KeyFilter {
+    /// If `only` is empty then all keys that `skip` doesn't match are checked.
+    pub fn new(only: Vec<Regex>, skip: Vec<Regex>) -> Self {
+        KeyFilter { only, skip }
+    }
+
+    /// Returns true if the text of `key` should be checked. The key is
+    /// without its `:0` version number.
+    pub fn accepts(&self, key: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|re| re.is_match(key)))
+            && !self.skip.iter().any(|re| re.is_match(key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn regexes(patterns: &[&str]) -> Vec<Regex> {
+        patterns.iter().map(|p| Regex::new(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(KeyFilter::default().accepts("event.1.desc"));
+
+        let filter = KeyFilter::new(Vec::new(), regexes(&["_debug_desc$"]));
+        assert!(filter.accepts("trait_brave_desc"));
+        assert!(!filter.accepts("trait_brave_debug_desc"));
+
+        let filter = KeyFilter::new(regexes(&["^event\\.", "^trait_"]), regexes(&["debug"]));
+        assert!(filter.accepts("event.1.desc"));
+        assert!(filter.accepts("trait_brave"));
+        assert!(!filter.accepts("my_event.1.desc"));
+        assert!(!filter.accepts("trait_debug"));
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Result};
+use speller::{Speller, Suggestion, SuggestionConfig, Suggestions};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use crate::project::AcceptedWord;
+
+/// A loaded dictionary, shared with the thread that looks up suggestions.
+pub type SharedSpeller = Arc<RwLock<Box<dyn Speller + Send + Sync>>>;
+
+type Loader = Box<dyn FnOnce() -> Result<Box<dyn Speller + Send + Sync>>>;
+
+/// A speller that only loads its dictionary when it's asked to, so that
+/// the dictionaries of languages whose files are never shown don't hold
+/// up the start. Until the dictionary is loaded it accepts every word,
+/// and so does it if the dictionary could not be loaded.
+pub struct LazySpeller {
+    locale: String,
+    loader: Option<Loader>,
+    speller: Option<SharedSpeller>,
+    /// Why the dictionary could not be loaded, if it couldn't.
+    error: Option<String>,
+    /// Kept so that it can be given to the dictionary when it's loaded.
+    suggestion_config: SuggestionConfig,
+    /// A copy of the dictionary's user words, because they can't be lent
+    /// out from behind the lock. It's updated whenever words are added.
+    user_words: Vec<String>,
+}
+
+impl LazySpeller {
+    pub fn new(
+        locale: &str,
+        loader: impl FnOnce() -> Result<Box<dyn Speller + Send + Sync>> + 'static,
+    ) -> Self {
+        LazySpeller {
+            locale: locale.to_string(),
+            loader: Some(Box::new(loader)),
+            speller: None,
+            error: None,
+            suggestion_config: SuggestionConfig::default(),
+            user_words: Vec::new(),
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Returns true if loading the dictionary has been tried, even if
+    /// it failed.
+    pub fn is_loaded(&self) -> bool {
+        self.loader.is_none()
+    }
+
+    /// Returns why the dictionary could not be loaded, if loading it
+    /// was tried and failed. Words are not really checked then.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Load the dictionary, unless that has already been tried, and
+    /// add the words that the project accepts to it.
+    pub fn load(&mut self, accepted: &[AcceptedWord]) -> Result<()> {
+        if let Some(loader) = self.loader.take() {
+            let mut speller =
+                loader().inspect_err(|err| self.error = Some(format!("{:#}", err)))?;
+            speller.set_suggestion_config(self.suggestion_config.clone());
+            self.speller = Some(Arc::new(RwLock::new(speller)));
+            self.add_accepted(accepted);
+        }
+        Ok(())
+    }
+
+    /// Add words that the project accepts. Does nothing if the dictionary
+    /// isn't loaded; `load` adds them then.
+    pub fn add_accepted(&mut self, accepted: &[AcceptedWord]) {
+        let Some(mut speller) = self.write() else {
+            return;
+        };
+        let mut plain = Vec::new();
+        for accepted in accepted {
+            match &accepted.model {
+                // If the model is gone, at least accept the word itself.
+                Some(model) => {
+                    if !speller.add_word_like(&accepted.word, model) {
+                        plain.push(accepted.word.as_str());
+                    }
+                }
+                None => plain.push(accepted.word.as_str()),
+            }
+        }
+        speller.add_words(&plain);
+        drop(speller);
+        self.copy_user_words();
+    }
+
+    /// Returns the dictionary, if it's loaded, to use from another thread.
+    pub fn shared(&self) -> Option<SharedSpeller> {
+        self.speller.clone()
+    }
+
+    // Only this thread writes to the speller, so the lock can't have
+    // been poisoned by a writer that panicked.
+    fn read(&self) -> Option<RwLockReadGuard<'_, Box<dyn Speller + Send + Sync>>> {
+        self.speller.as_ref().map(|s| s.read().unwrap())
+    }
+
+    fn write(&self) -> Option<RwLockWriteGuard<'_, Box<dyn Speller + Send + Sync>>> {
+        self.speller.as_ref().map(|s| s.write().unwrap())
+    }
+
+    fn loaded(&self) -> Result<RwLockWriteGuard<'_, Box<dyn Speller + Send + Sync>>> {
+        self.write()
+            .ok_or_else(|| anyhow!("The dictionary for {} is not loaded", self.locale))
+    }
+
+    fn copy_user_words(&mut self) {
+        if let Some(words) = self
+            .read()
+            .map(|s| s.user_words().map(str::to_string).collect())
+        {
+            self.user_words = words;
+        }
+    }
+}
+
+impl Speller for LazySpeller {
+    fn spellcheck(&self, word: &str) -> bool {
+        self.read().is_none_or(|s| s.spellcheck(word))
+    }
+
+    fn suggestions(&self, word: &str, max: usize) -> Vec<String> {
+        self.read()
+            .map_or_else(Vec::new, |s| s.suggestions(word, max))
+    }
+
+    fn suggestions_with_reason(&self, word: &str, max: usize) -> Suggestions {
+        self.read().map_or(Suggestions::NoCandidates, |s| {
+            s.suggestions_with_reason(word, max)
+        })
+    }
+
+    fn suggestions_with_budget(&self, word: &str, max: usize, budget: Duration) -> Suggestions {
+        self.read().map_or(Suggestions::NoCandidates, |s| {
+            s.suggestions_with_budget(word, max, budget)
+        })
+    }
+
+    fn suggestions_cancellable(
+        &self,
+        word: &str,
+        max: usize,
+        budget: Duration,
+        cancel: &AtomicBool,
+    ) -> Suggestions {
+        self.read().map_or(Suggestions::NoCandidates, |s| {
+            s.suggestions_cancellable(word, max, budget, cancel)
+        })
+    }
+
+    fn suggestions_detailed(&self, word: &str, max: usize) -> Vec<Suggestion> {
+        self.read()
+            .map_or_else(Vec::new, |s| s.suggestions_detailed(word, max))
+    }
+
+    fn set_suggestion_config(&mut self, config: SuggestionConfig) {
+        // Only take the lock when it changes, because a search on the
+        // other thread may still be holding it.
+        if config != self.suggestion_config {
+            if let Some(mut s) = self.write() {
+                s.set_suggestion_config(config.clone());
+            }
+            self.suggestion_config = config;
+        }
+    }
+
+    fn add_word(&mut self, word: &str) -> bool {
+        let added = self.write().is_some_and(|mut s| s.add_word(word));
+        if added {
+            self.copy_user_words();
+        }
+        added
+    }
+
+    fn add_words(&mut self, words: &[&str]) -> usize {
+        let added = self.write().map_or(0, |mut s| s.add_words(words));
+        if added > 0 {
+            self.copy_user_words();
+        }
+        added
+    }
+
+    fn add_word_like(&mut self, word: &str, model: &str) -> bool {
+        let added = self
+            .write()
+            .is_some_and(|mut s| s.add_word_like(word, model));
+        if added {
+            self.copy_user_words();
+        }
+        added
+    }
+
+    fn words_like(&self, word: &str, model: &str) -> Vec<String> {
+        self.read()
+            .map_or_else(Vec::new, |s| s.words_like(word, model))
+    }
+
+    fn suggest_model(&self, word: &str) -> Option<String> {
+        self.read().and_then(|s| s.suggest_model(word))
+    }
+
+    fn suggest_model_cancellable(
+        &self,
+        word: &str,
+        budget: Duration,
+        cancel: &AtomicBool,
+    ) -> Option<String> {
+        self.read()
+            .and_then(|s| s.suggest_model_cancellable(word, budget, cancel))
+    }
+
+    fn is_discouraged(&self, word: &str) -> bool {
+        self.read().is_some_and(|s| s.is_discouraged(word))
+    }
+
+    fn user_words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.user_words.iter().map(String::as_str))
+    }
+
+    fn set_user_dict(&mut self, path: &Path) -> Result<i32> {
+        let count = self.loaded()?.set_user_dict(path)?;
+        self.copy_user_words();
+        Ok(count)
+    }
+
+    fn add_word_to_user_dict(&mut self, word: &str) -> Result<bool> {
+        let added = self.loaded()?.add_word_to_user_dict(word)?;
+        self.copy_user_words();
+        Ok(added)
+    }
+
+    fn add_word_like_to_user_dict(&mut self, word: &str, model: &str) -> Result<bool> {
+        let added = self.loaded()?.add_word_like_to_user_dict(word, model)?;
+        self.copy_user_words();
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use speller::SpellerHunspellDict;
+
+    #[test]
+    fn test_user_words() {
+        let mut speller = LazySpeller::new("en_US", || {
+            let speller = SpellerHunspellDict::from_text("1\nhello\n", "SET UTF-8\n")?;
+            Ok(Box::new(speller))
+        });
+        assert!(!speller.add_word("Valyria"));
+        speller.load(&[]).unwrap();
+        assert!(speller.add_word("Valyria"));
+        speller.add_accepted(&[AcceptedWord {
+            word: "Essos".to_string(),
+            model: None,
+        }]);
+        let mut words: Vec<&str> = speller.user_words().collect();
+        words.sort_unstable();
+        assert_eq!(vec!["Essos", "Valyria"], words);
+    }
+}
@@ -5,6 +5,7 @@ use druid::widget::prelude::*;
 use druid::widget::ListIter;
 use druid::{Command, Point, Rect, Target, WidgetPod};
 use std::cmp::Ordering;
+use std::sync::Arc;
 
 use crate::commands::{QUERY_LINE_LAYOUT_REGION, REPLY_LINE_LAYOUT_REGION};
 use crate::LineInfo;
@@ -24,7 +25,7 @@ impl LineList {
         }
     }
 
-    fn update_child_count(&mut self, data: &impl ListIter<LineInfo>, _env: &Env) -> bool {
+    fn update_child_count(&mut self, data: &impl ListIter<Arc<LineInfo>>, _env: &Env) -> bool {
         let len = self.children.len();
         match len.cmp(&data.data_len()) {
             Ordering::Greater => self.children.truncate(data.data_len()),
@@ -44,12 +45,18 @@ impl LineList {
     }
 }
 
-impl<T: ListIter<LineInfo>> Widget<T> for LineList {
+impl<T: ListIter<Arc<LineInfo>>> Widget<T> for LineList {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         let mut children = self.children.iter_mut();
         data.for_each_mut(|child_data, _| {
             if let Some(child) = children.next() {
-                child.event(ctx, event, child_data, env);
+                // Only replace the line if the event changed it, so that the
+                // other lines stay shared with the previous version.
+                let mut lineinfo = LineInfo::clone(child_data);
+                child.event(ctx, event, &mut lineinfo, env);
+                if !lineinfo.same(child_data) {
+                    *child_data = Arc::new(lineinfo);
+                }
             }
         });
 
@@ -2,10 +2,11 @@ use druid::widget::prelude::*;
 use druid::widget::Scroll;
 use druid::{Command, Point, Rect, Target, WidgetPod};
 
+use crate::appcore::Cursor;
 use crate::commands::{
     CURSOR_CHANGED, DICTIONARY_UPDATED, QUERY_LINE_LAYOUT_REGION, REPLY_LINE_LAYOUT_REGION,
 };
-use crate::{AppState, Cursor};
+use crate::AppState;
 
 pub struct LineScroller<W> {
     scroll: WidgetPod<AppState, Scroll<AppState, W>>,
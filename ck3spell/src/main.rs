@@ -1,47 +1,229 @@
 use anyhow::{anyhow, bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use druid::text::{Attribute, RichText};
 use druid::widget::prelude::*;
-use druid::{AppLauncher, Color, Key, Lens, WindowDesc};
+use druid::{AppLauncher, Color, Key, Lens, SingleUse, Target, WindowDesc};
 use home::home_dir;
 use nu_glob::glob;
+use regex::Regex;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::env::current_exe;
+use std::env::{current_exe, split_paths, var_os};
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Write;
+use std::mem::replace;
+use std::num::NonZeroUsize;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
 use std::sync::Arc;
+use std::thread::{self, available_parallelism};
 
-use speller::{Speller, SpellerHunspellDict};
+#[cfg(feature = "system-hunspell")]
+use speller::SpellerHunspellLib;
+use speller::{Speller, SpellerConfig, SpellerHunspellDict, SuggestionConfig, Suggestions};
 
+mod acceptcontroller;
 mod appcontroller;
+mod appcore;
+mod autocorrect;
 mod commands;
+mod counter;
 mod custom;
+mod diagnostics;
 mod edit;
 mod editorcontroller;
+mod extension;
+mod homoglyph;
+mod ignorelist;
+mod join;
+mod keyfilter;
+mod lazyspeller;
 mod linelist;
 mod linescroller;
+mod memo;
+mod nowrap;
+mod numbers;
+mod progress;
+mod project;
+mod segment;
+mod suggester;
+mod suppress;
 mod syntax;
 mod syntaxhighlighter;
+mod typography;
 mod ui;
 
+use crate::appcore::{AcceptScope, Cursor};
+use crate::autocorrect::{AutoCorrect, AUTOCORRECT_LIST_FILE};
+use crate::commands::FILE_READ;
+use crate::counter::MisspellingCounter;
 use crate::custom::CustomEndings;
-use crate::syntax::{parse_line, TokenType};
+use crate::diagnostics::{Category, Issue, IssueFilter, LineIssue, Misspelling, Severity};
+use crate::extension::dictionary_in_extensions;
+use crate::ignorelist::{IgnoreList, IGNORE_LIST_FILE};
+use crate::join::{JoinPolicies, JoinPolicy};
+use crate::keyfilter::KeyFilter;
+use crate::lazyspeller::LazySpeller;
+use crate::memo::SpellMemo;
+use crate::numbers::NumberSuffixes;
+use crate::progress::Progress;
+use crate::project::{AcceptedWord, Ignore, Project};
+use crate::segment::{Segmentation, Split};
+use crate::suggester::Suggester;
+use crate::suppress::Suppression;
+use crate::syntax::{
+    bad_whitespace, comment_words, find_in_text, fix_whitespace, invisible_chars, joined_word,
+    loc_key_name, malformed_code, parse_line, show_invisible, unbalanced_markup, visible_length,
+    word_part_join, Join, Token, TokenType,
+};
+use crate::typography::Typography;
 use crate::ui::ui_builder;
 
 #[derive(Parser)]
 #[clap(author, version, about)]
 struct Cli {
     /// Files to spell check.
-    #[clap(required(true))]
+    #[clap(required_unless_present_any(["list_languages", "list_dicts"]))]
     pathnames: Vec<PathBuf>,
     /// Dictionary for accepted words.
     #[clap(short, long)]
     local_dict: Option<PathBuf>,
+    /// Extra directory to search for dictionaries. Can be given more than once.
+    /// These are searched before the directories in CK3SPELL_DICT_PATH and
+    /// the default search path.
+    #[clap(long, value_name = "DIR")]
+    dict_path: Vec<PathBuf>,
+    /// Print the supported languages and their dictionary locales, then exit.
+    #[clap(long)]
+    list_languages: bool,
+    /// Print the dictionary search path and which dictionaries were found, then exit.
+    #[clap(long)]
+    list_dicts: bool,
+    /// Show long lines on one row and scroll them sideways, instead of
+    /// wrapping them. This can also be switched in the window.
+    #[clap(long)]
+    no_wrap: bool,
+    /// Don't split unknown words at these places and check the pieces
+    /// separately. By default words are split at all of them.
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "PLACES")]
+    no_split: Vec<Split>,
+    /// Don't check the parts of words that are joined to code in these
+    /// ways. By default they are all checked. A part that is written
+    /// directly against a key reference, as in `$NAME$s`, is never
+    /// checked, because the key gives the rest of the word.
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "JOINS")]
+    skip_joined: Vec<Join>,
+    /// Also spellcheck the text in comments.
+    #[clap(long)]
+    check_comments: bool,
+    /// Don't accept words just because they are Roman numerals, such as
+    /// the "XIV" in "Louis XIV".
+    #[clap(long)]
+    no_roman_numerals: bool,
+    /// Don't treat typographic apostrophes, such as `’`, as the same as
+    /// the plain `'` when looking words up in the dictionary.
+    #[clap(long)]
+    no_normalize_apostrophes: bool,
+    /// Mark ellipses and dashes that are not written the project's way:
+    /// "unicode" for "…" and "—", or "ascii" for "..." and "--".
+    #[clap(long, value_enum, value_name = "STYLE")]
+    typography: Option<Typography>,
+    /// Mark the line numbers of texts that show more than this many
+    /// characters, because long texts wrap badly in tooltips.
+    #[clap(long, value_name = "CHARS")]
+    max_length: Option<u64>,
+    /// Don't spellcheck words longer than this, such as pasted blobs of
+    /// base64; they are listed as hints instead. 0 means no limit.
+    #[clap(long, value_name = "CHARS", default_value_t = 100)]
+    max_word_length: u64,
+    /// Project file for settings that belong to the mod, such as words
+    /// that are ignored in particular loc keys. It is created if it
+    /// doesn't exist yet.
+    #[clap(long, value_name = "FILE")]
+    project: Option<PathBuf>,
+    /// Only check the texts of loc keys that match this regular
+    /// expression. Can be given more than once, to check the keys that
+    /// match any of them.
+    #[clap(long, value_name = "REGEX")]
+    only_keys: Vec<Regex>,
+    /// Don't check the texts of loc keys that match this regular
+    /// expression, such as `_debug_desc$`. Can be given more than once.
+    #[clap(long, value_name = "REGEX")]
+    skip_keys: Vec<Regex>,
+    /// File with words that are never flagged, one per line, or regular
+    /// expressions between slashes. The default is .ck3spell-ignore in
+    /// the current directory, if there is one.
+    #[clap(long, value_name = "FILE")]
+    ignore_list: Option<PathBuf>,
+    /// File with replacements for misspellings that are always fixed the
+    /// same way, such as "teh the". These are offered first among the
+    /// suggestions. The default is .ck3spell-autocorrect in the current
+    /// directory, if there is one.
+    #[clap(long, value_name = "FILE")]
+    autocorrect_list: Option<PathBuf>,
+    /// Apply the known fixes to all misspelled words in the files, save
+    /// the files, print what was changed, and exit without opening the
+    /// window. The known fixes are those in the autocorrect list and the
+    /// corrections remembered in the project file.
+    #[clap(long)]
+    autofix: bool,
+    /// The keyboard to suggest neighboring letters from, for typos such
+    /// as "a" for "z": qwerty, qwertz, azerty, or the rows of the
+    /// keyboard separated by |, as in the KEY line of a hunspell affix
+    /// file. The default is the dictionary's own.
+    #[clap(long, value_parser = keyboard_keys, value_name = "LAYOUT")]
+    keyboard: Option<String>,
+    /// Which spellchecker to use.
+    // Only builds with the system-hunspell feature have a choice.
+    #[clap(long, value_enum, default_value_t, hide = cfg!(not(feature = "system-hunspell")))]
+    engine: Engine,
+}
+
+/// The implementations of the Speller trait that can check the files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum Engine {
+    /// This program's own spellchecker.
+    #[default]
+    Native,
+    /// The hunspell library installed on the system.
+    #[cfg(feature = "system-hunspell")]
+    Hunspell,
+}
+
+/// The rows of common keyboard layouts, in the format of the KEY line of
+/// a hunspell affix file.
+const KEYBOARD_LAYOUTS: [(&str, &str); 3] = [
+    ("qwerty", "qwertyuiop|asdfghjkl|zxcvbnm"),
+    ("qwertz", "qwertzuiopü|asdfghjklöä|yxcvbnm"),
+    ("azerty", "azertyuiop|qsdfghjklmù|wxcvbn"),
+];
+
+/// Keyboard layouts that are often left switched on by mistake when
+/// typing in a language, by the locale of its dictionary. Each has the
+/// characters on the same keys of the two layouts, so that words typed
+/// in the other one, such as "ghbdtn" for "привет", can be fixed.
+const WRONG_LAYOUTS: [(&str, &str, &str); 1] = [(
+    "ru_RU",
+    "qwertyuiop[]asdfghjkl;'zxcvbnm,.`",
+    "йцукенгшщзхъфывапролджэячсмитьбюё",
+)];
+
+/// Parse the argument of `--keyboard`.
+fn keyboard_keys(arg: &str) -> Result<String, String> {
+    if let Some((_, keys)) = KEYBOARD_LAYOUTS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(arg))
+    {
+        return Ok(keys.to_string());
+    }
+    if arg.contains('|') {
+        return Ok(arg.to_string());
+    }
+    Err("expected qwerty, qwertz, azerty, or keyboard rows separated by |".to_string())
 }
 
 const WINDOW_TITLE: &str = "CK3 spellcheck";
@@ -56,11 +238,30 @@ const ESCAPE_COLOR: Key<Color> = Key::new("ck3spell.escape-color");
 const COMMENT_COLOR: Key<Color> = Key::new("ck3spell.comment-color");
 const MARKUP_COLOR: Key<Color> = Key::new("ck3spell.markup-color");
 const ICON_TAG_COLOR: Key<Color> = Key::new("ck3spell.icon-tag-color");
+const BAD_MARKUP_COLOR: Key<Color> = Key::new("ck3spell.bad-markup-color");
+const BAD_CODE_COLOR: Key<Color> = Key::new("ck3spell.bad-code-color");
+const WHITESPACE_COLOR: Key<Color> = Key::new("ck3spell.whitespace-color");
+const INVISIBLE_COLOR: Key<Color> = Key::new("ck3spell.invisible-color");
+const TYPOGRAPHY_COLOR: Key<Color> = Key::new("ck3spell.typography-color");
+const LITERAL_COLOR: Key<Color> = Key::new("ck3spell.literal-color");
+const OCCURRENCE_COLOR: Key<Color> = Key::new("ck3spell.occurrence-color");
 const LINE_COLOR: Key<Color> = Key::new("ck3spell-line-color");
+const WRAP_LINES: Key<bool> = Key::new("ck3spell.wrap-lines");
+const CHECK_COMMENTS: Key<bool> = Key::new("ck3spell.check-comments");
+const ROMAN_NUMERALS: Key<bool> = Key::new("ck3spell.roman-numerals");
+/// Texts that show more characters than this are marked. 0 means no limit.
+const MAX_LENGTH: Key<u64> = Key::new("ck3spell.max-length");
+/// Words longer than this are not spellchecked.
+const MAX_WORD_LENGTH: Key<u64> = Key::new("ck3spell.max-word-length");
+const LONG_LINE_COLOR: Key<Color> = Key::new("ck3spell.long-line-color");
 
 const DICTIONARY_SEARCH_PATH: [&str; 5] =
     ["./dicts", ".", "/usr/share/hunspell", "$EXE/dicts", "$EXE"];
 
+/// Environment variable with extra directories to search for dictionaries,
+/// separated the same way as PATH.
+const DICTIONARY_PATH_ENV: &str = "CK3SPELL_DICT_PATH";
+
 #[derive(Clone, Data, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 enum LineEnd {
@@ -90,91 +291,199 @@ struct Line {
 pub struct LineInfo {
     line: Line,
     rendered: RichText,
-    // For highlighting
-    bad_words_range: Rc<Vec<Range<usize>>>,
-    // For spellchecking and for displaying the word. Usually the same as the highlighted range,
-    // but can differ when custom endings are used.
-    bad_words_text: Rc<Vec<String>>,
-    highlight_word_nr: usize,
+    // Everything wrong with the line, in order of where it starts.
+    // Whitespace and long lines are only hints, so they don't keep the
+    // file from counting as clean.
+    issues: Rc<Vec<Issue>>,
+    highlight_issue_nr: usize,
+    // The text of the word at the cursor, so that other occurrences of it
+    // can be highlighted. Empty if there is no cursor word. It's shared by
+    // all the lines of the file, so that moving the cursor doesn't have
+    // to change every line.
+    cursor_word: Rc<RefCell<Rc<String>>>,
+    // The parsed line, so that it only has to be parsed again when the text changes.
+    tokens: Rc<Vec<Token>>,
+    tokens_line: Option<Rc<String>>,
+    // What a `# ck3spell:` directive on the line above says not to flag here.
+    suppress_above: Rc<Suppression>,
+    // Name of the file, for looking up the project's ignored words.
+    filename: Rc<String>,
+    project: Rc<RefCell<Project>>,     // Should be in Env but can't.
     speller: Rc<RefCell<dyn Speller>>, // Should be in Env but can't.
+    memo: Rc<SpellMemo>,               // Should be in Env but can't.
     custom: Rc<CustomEndings>,         // Should be in Env but can't.
+    numbers: Rc<NumberSuffixes>,       // Should be in Env but can't.
 }
 
 impl LineInfo {
     fn highlight(&mut self, env: &Env) {
         highlight_syntax(self, env);
-        if let Some(range) = self.marked_word() {
+        if let Some(range) = self.marked_range() {
             self.rendered
                 .add_attribute(range, Attribute::underline(true));
         }
     }
 
-    fn marked_word(&self) -> Option<Range<usize>> {
-        if self.highlight_word_nr > 0 {
-            self.bad_words_range
-                .get(self.highlight_word_nr - 1)
-                .cloned()
+    fn marked_range(&self) -> Option<Range<usize>> {
+        if self.highlight_issue_nr > 0 {
+            self.issues
+                .get(self.highlight_issue_nr - 1)
+                .map(|issue| issue.range.clone())
         } else {
             None
         }
     }
+
+    /// The issues that suggestions can be given for.
+    fn words(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.category.is_word())
+    }
+
+    /// Returns the 1-based numbers of the issues that `filter` lets the
+    /// cursor go to.
+    fn issue_nrs(&self, filter: IssueFilter) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.issues
+            .iter()
+            .enumerate()
+            .filter(move |(_, issue)| filter.accepts(issue.category))
+            .map(|(idx, _)| idx + 1)
+    }
+
+    fn ranges(&self, categories: &[Category]) -> Vec<Range<usize>> {
+        self.issues
+            .iter()
+            .filter(|issue| categories.contains(&issue.category))
+            .map(|issue| issue.range.clone())
+            .collect()
+    }
+
+    fn too_long(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.category == Category::Length)
+    }
+
+    fn is_clean(&self) -> bool {
+        self.issues
+            .iter()
+            .all(|issue| issue.severity() == Severity::Hint)
+    }
 }
 
-/// Current highlighted bad word, as 1-based line and word number.
-/// If the word number is 0 then no word is highlighted.
-#[derive(Clone, Copy, Data, Debug)]
-pub struct Cursor {
-    linenr: usize,
-    wordnr: usize,
+// The core types don't know about druid.
+impl Data for Cursor {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
 }
 
-impl Default for Cursor {
-    fn default() -> Cursor {
-        Cursor {
-            linenr: 1,
-            wordnr: 0,
-        }
+impl Data for AcceptScope {
+    fn same(&self, other: &Self) -> bool {
+        self == other
     }
 }
 
+/// An accept that waits until the user confirms it, because the
+/// dictionary marks the word as one to avoid.
+#[derive(Clone, Data, Debug)]
+pub struct PendingAccept {
+    warning: Arc<String>,
+    /// Whether the word is accepted with the endings of the accept model.
+    like: bool,
+}
+
 #[derive(Clone, Data)]
 pub struct Suggestion {
     suggestion_nr: usize, // 1-based
     suggestion: Rc<String>,
 }
 
+/// A fix that was made by applying the known fixes, so that it can be
+/// reviewed.
+#[derive(Clone, Data)]
+pub struct AppliedFix {
+    pathname: Rc<PathBuf>,
+    line_nr: usize,
+    word: String,
+    fix: String,
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct FileState {
     /// File to spell check.
     pathname: Rc<PathBuf>,
     /// Name of file to spell check, for display.
     filename: Rc<String>,
-    lines: Arc<Vec<LineInfo>>,
-    speller: Rc<RefCell<dyn Speller>>,
+    /// Each line has its own `Arc`, so that changing one line doesn't
+    /// copy the others.
+    lines: Arc<Vec<Arc<LineInfo>>>,
+    speller: Rc<RefCell<LazySpeller>>,
+    /// Spellcheck results for the words of this file.
+    memo: Rc<SpellMemo>,
     custom: Rc<CustomEndings>,
+    numbers: Rc<NumberSuffixes>,
+    /// The word at the cursor, shared with the lines.
+    cursor_word: Rc<RefCell<Rc<String>>>,
+    /// The issues of all the lines, for the issues panel. They are
+    /// collected again whenever the lines change.
+    issues: Arc<Vec<LineIssue>>,
 }
 
 impl FileState {
     fn new(
         pathname: &Path,
         contents: &str,
-        speller: Rc<RefCell<dyn Speller>>,
+        project: &Rc<RefCell<Project>>,
+        speller: Rc<RefCell<LazySpeller>>,
         custom: Rc<CustomEndings>,
+        numbers: Rc<NumberSuffixes>,
     ) -> Self {
         let filename = if let Some(name) = pathname.file_name() {
             name.to_string_lossy().to_string()
         } else {
             "".to_string()
         };
-        FileState {
+        let mut file = FileState {
             pathname: Rc::new(pathname.to_path_buf()),
             filename: Rc::new(filename),
-            lines: Arc::new(split_lines(contents, &speller, &custom)),
+            lines: Arc::new(Vec::new()),
             speller,
+            memo: Rc::new(SpellMemo::default()),
             custom,
+            numbers,
+            cursor_word: Rc::new(RefCell::new(Rc::new(String::new()))),
+            issues: Arc::new(Vec::new()),
+        };
+        file.lines = Arc::new(split_lines(contents, &file, project));
+        file.update_issues();
+        file
+    }
+
+    /// Use the tokens that were parsed on a worker thread, so that the
+    /// lines don't have to be parsed again when they are highlighted.
+    fn set_tokens(&mut self, tokens: Vec<Vec<Token>>) {
+        let lines = Arc::make_mut(&mut self.lines);
+        for (lineinfo, tokens) in lines.iter_mut().zip(tokens) {
+            let lineinfo = Arc::make_mut(lineinfo);
+            lineinfo.tokens = Rc::new(tokens);
+            lineinfo.tokens_line = Some(Rc::clone(&lineinfo.line.line));
         }
     }
 
+    fn update_issues(&mut self) {
+        let mut issues = Vec::new();
+        for lineinfo in self.lines.iter() {
+            for issue in lineinfo.issues.iter() {
+                issues.push(LineIssue {
+                    pathname: Rc::clone(&self.pathname),
+                    line_nr: lineinfo.line.line_nr,
+                    issue: issue.clone(),
+                });
+            }
+        }
+        self.issues = Arc::new(issues);
+    }
+
     fn save(&self) -> Result<()> {
         let mut file = File::create(&*self.pathname)
             .with_context(|| format!("Could not write to {}", self.pathname.display()))?;
@@ -187,9 +496,13 @@ impl FileState {
     }
 
     fn is_clean(&self) -> bool {
-        self.lines
-            .iter()
-            .all(|lineinfo| lineinfo.bad_words_range.is_empty())
+        // Until the dictionary is loaded, or if it couldn't be, every
+        // word looks good.
+        let speller = self.speller.borrow();
+        if !speller.is_loaded() || speller.error().is_some() {
+            return false;
+        }
+        self.lines.iter().all(|lineinfo| lineinfo.is_clean())
     }
 }
 
@@ -201,158 +514,893 @@ pub struct AppState {
     file_idx: usize, // 0-based
     cursor: Cursor,
     suggestions: Arc<Vec<Suggestion>>,
+    /// Why there are no suggestions for the cursor word, if there aren't.
+    no_suggestions: Arc<String>,
+    suggester: Rc<Suggester>,
+    /// The search whose suggestions should be shown when they arrive,
+    /// or 0 if there is none.
+    suggestions_id: u64,
+    /// The search whose model word should be kept when it arrives, or 0
+    /// if there is none.
+    model_id: u64,
+    /// The model word found for the cursor word, or empty if none was.
+    suggested_model: Arc<String>,
     editing_linenr: usize, // 1-based
     editing_text: Arc<String>,
+    /// Whether the accept word panel is shown.
+    accepting: bool,
+    /// Word whose endings the cursor word should get when accepted.
+    accept_model: Arc<String>,
+    accept_scope: AcceptScope,
+    pending_accept: Option<PendingAccept>,
+    /// Whether long lines are wrapped or shown on one row.
+    wrap_lines: bool,
+    /// Whether to skip the slow searches for suggestions, which is
+    /// useful with huge dictionaries.
+    fast_suggestions: bool,
+    /// How the project writes ellipses and dashes, if it cares.
+    typography: Option<Typography>,
+    /// Which issues Up and Down go through.
+    cursor_filter: IssueFilter,
+    project: Rc<RefCell<Project>>,
+    /// The project's ignored words, for showing in the ignores panel.
+    ignores: Arc<Vec<Ignore>>,
+    /// Whether the ignores panel is shown.
+    managing_ignores: bool,
+    /// Whether the issues panel is shown.
+    showing_issues: bool,
+    /// The least severe issues that the issues panel lists.
+    issue_filter: Severity,
+    /// The issues of all the files that pass the filter.
+    issue_list: Arc<Vec<LineIssue>>,
+    /// Whether the misspellings panel is shown.
+    showing_misspellings: bool,
+    /// The misspelled words of the files checked so far, most common
+    /// first.
+    misspellings: Arc<Vec<Misspelling>>,
+    counter: Rc<MisspellingCounter>,
+    /// The count whose result should be shown when it arrives.
+    misspellings_id: u64,
+    /// Whether the panel with the applied known fixes is shown.
+    showing_fixes: bool,
+    /// The fixes that were made the last time the known fixes were applied.
+    applied_fixes: Arc<Vec<AppliedFix>>,
+    /// The locale whose dictionary is being loaded for the current file,
+    /// or empty if it's ready.
+    loading_dictionary: Arc<String>,
+    /// Loads the files that are still being read when the window opens.
+    loader: Option<Rc<RefCell<FileLoader>>>,
+    /// How many files are still being read, for the progress shown in
+    /// the header.
+    files_pending: usize,
 }
 
 impl AppState {
-    fn new(files: Rc<Vec<FileState>>) -> Self {
-        AppState {
+    fn new(
+        files: Rc<Vec<FileState>>,
+        project: Rc<RefCell<Project>>,
+        wrap_lines: bool,
+        typography: Option<Typography>,
+        suggester: Suggester,
+        counter: MisspellingCounter,
+    ) -> Self {
+        let ignores = Arc::new(project.borrow().ignores().to_vec());
+        let mut data = AppState {
             file: files[0].clone(),
             files: files.clone(),
             file_idx: 0,
             cursor: Cursor::default(),
             suggestions: Arc::new(Vec::new()),
+            no_suggestions: Arc::new(String::new()),
+            suggester: Rc::new(suggester),
+            suggestions_id: 0,
+            model_id: 0,
+            suggested_model: Arc::new(String::new()),
             editing_linenr: 0,
             editing_text: Arc::new(String::new()),
+            accepting: false,
+            accept_model: Arc::new(String::new()),
+            accept_scope: AcceptScope::Personal,
+            pending_accept: None,
+            wrap_lines,
+            fast_suggestions: false,
+            typography,
+            cursor_filter: IssueFilter::Words,
+            project,
+            ignores,
+            managing_ignores: false,
+            showing_issues: false,
+            issue_filter: Severity::Hint,
+            issue_list: Arc::new(Vec::new()),
+            showing_misspellings: false,
+            misspellings: Arc::new(Vec::new()),
+            counter: Rc::new(counter),
+            misspellings_id: 0,
+            showing_fixes: false,
+            applied_fixes: Arc::new(Vec::new()),
+            loading_dictionary: Arc::new(String::new()),
+            loader: None,
+            files_pending: 0,
+        };
+        data.check_dictionary();
+        data.update_issue_list();
+        data
+    }
+
+    /// Note that `count` more files are being read, which `loader` will
+    /// load when they arrive with FILE_READ commands.
+    fn expect_files(&mut self, loader: FileLoader, count: usize) {
+        self.loader = Some(Rc::new(RefCell::new(loader)));
+        self.files_pending = count;
+    }
+
+    /// Add a file that was read after the window opened.
+    fn file_read(&mut self, pathname: &Path, parsed: Result<ParsedFile>) {
+        self.files_pending = self.files_pending.saturating_sub(1);
+        let Some(loader) = &self.loader else {
+            return;
+        };
+        let file = parsed.and_then(|parsed| loader.borrow_mut().load(pathname, parsed));
+        match file {
+            Ok(file) => {
+                Rc::make_mut(&mut self.files).push(file);
+                self.update_issue_list();
+            }
+            Err(err) => eprintln!("{:#}", err),
+        }
+    }
+
+    /// Note whether the current file's dictionary still has to be loaded,
+    /// so that a placeholder can be shown until it is.
+    fn check_dictionary(&mut self) {
+        // Words may have been accepted while another file was shown.
+        self.file.memo.clear();
+        let speller = self.file.speller.borrow();
+        let loading = if speller.is_loaded() {
+            String::new()
+        } else {
+            speller.locale().to_string()
+        };
+        self.loading_dictionary = Arc::new(loading);
+    }
+
+    fn load_file_dictionary(&mut self) {
+        let mut speller = self.file.speller.borrow_mut();
+        if !speller.is_loaded() {
+            // The window shows this too, but it's good to have in the log.
+            eprintln!("Loading dictionary for {}", speller.locale());
+            // The words that the project accepts are kept in the
+            // project file, so they are added to each dictionary.
+            if let Err(err) = speller.load(self.project.borrow().accepted()) {
+                eprintln!("{:#}", err);
+            }
+            // Every word was good while there was no dictionary.
+            self.file.memo.clear();
         }
+        self.loading_dictionary = Arc::new(String::new());
     }
 
     fn file_prev(&mut self) {
         if self.file_idx == 0 {
             return;
         }
-
-        self.update_cursor(Cursor::default());
-        self.update_suggestions();
-
-        self.file_idx -= 1;
-        self.file = self.files[self.file_idx].clone();
+        self.show_file(self.file_idx - 1);
     }
 
     fn file_next(&mut self) {
         if self.file_idx == self.files.len() - 1 {
             return;
         }
+        self.show_file(self.file_idx + 1);
+    }
 
+    fn show_file(&mut self, file_idx: usize) {
         self.update_cursor(Cursor::default());
         self.update_suggestions();
 
-        self.file_idx += 1;
+        self.file_idx = file_idx;
         self.file = self.files[self.file_idx].clone();
+        self.check_dictionary();
     }
 
     fn cursor_prev(&mut self) {
-        let mut cursor = self.cursor;
-        if cursor.wordnr > 1 {
-            cursor.wordnr -= 1;
-        } else {
-            cursor.wordnr = 0;
-            while cursor.linenr > 1 {
-                cursor.linenr -= 1;
-                let nwords = self.file.lines[cursor.linenr - 1].bad_words_range.len();
-                if nwords > 0 {
-                    cursor.wordnr = nwords;
-                    break;
-                }
-            }
-        }
+        let lines = &self.file.lines;
+        let filter = self.cursor_filter;
+        let cursor =
+            appcore::cursor_prev(self.cursor, |linenr| lines[linenr - 1].issue_nrs(filter));
         self.update_cursor(cursor);
         self.update_suggestions();
     }
 
     fn cursor_next(&mut self) {
-        let mut cursor = self.cursor;
-        let nwords = self.file.lines[cursor.linenr - 1].bad_words_range.len();
-        let nlines = self.file.lines.len();
-        if cursor.wordnr < nwords {
-            cursor.wordnr += 1;
-        } else {
-            cursor.wordnr = 0;
-            while cursor.linenr < nlines {
-                cursor.linenr += 1;
-                let nwords = self.file.lines[cursor.linenr - 1].bad_words_range.len();
-                if nwords > 0 {
-                    cursor.wordnr = 1;
-                    break;
-                }
-            }
-        }
+        let lines = &self.file.lines;
+        let filter = self.cursor_filter;
+        let cursor = appcore::cursor_next(self.cursor, lines.len(), |linenr| {
+            lines[linenr - 1].issue_nrs(filter)
+        });
         self.update_cursor(cursor);
         self.update_suggestions();
     }
 
-    fn cursor_word(&self) -> Option<&String> {
-        if self.cursor.wordnr == 0 {
+    /// Returns the number of the first issue on line `linenr` that the
+    /// cursor can go to.
+    fn first_issue_nr(&self, linenr: usize) -> Option<usize> {
+        self.file.lines[linenr - 1]
+            .issue_nrs(self.cursor_filter)
+            .next()
+    }
+
+    /// Put the cursor on the first issue of line `linenr` that it can
+    /// go to, or on the line itself if there is none.
+    fn goto_line(&mut self, linenr: usize) {
+        let issuenr = self.first_issue_nr(linenr).unwrap_or(0);
+        self.update_cursor(Cursor { linenr, issuenr });
+        self.update_suggestions();
+    }
+
+    /// After the cursor line has changed, move on if the cursor is no
+    /// longer on an issue that it can go to.
+    fn recheck_cursor(&mut self) {
+        if self
+            .cursor_issue()
+            .is_some_and(|issue| self.cursor_filter.accepts(issue.category))
+        {
+            self.update_suggestions();
+        } else {
+            self.cursor_next();
+        }
+    }
+
+    fn cursor_issue(&self) -> Option<&Issue> {
+        if self.cursor.issuenr == 0 {
             return None;
         }
         self.file.lines[self.cursor.linenr - 1]
-            .bad_words_text
-            .get(self.cursor.wordnr - 1)
+            .issues
+            .get(self.cursor.issuenr - 1)
+    }
+
+    /// Returns the text of the cursor issue, if it's one that
+    /// suggestions can be given for.
+    fn cursor_word(&self) -> Option<&String> {
+        self.cursor_issue()
+            .filter(|issue| issue.category.is_word())
+            .map(|issue| &issue.text)
+    }
+
+    /// Returns what the cursor word should be instead, for the issues
+    /// that have only one fix: an ellipsis or dash that isn't written the
+    /// project's way, or a word with letters from another script.
+    fn cursor_fix(&self) -> Option<String> {
+        let issue = self.cursor_issue()?;
+        match issue.category {
+            Category::Typography => self.typography?.fix(&issue.text).map(str::to_string),
+            Category::MixedScript => homoglyph::unmix(&issue.text),
+            _ => None,
+        }
+    }
+
+    /// Returns the fixes that the project prefers for the cursor word:
+    /// the one from the autocorrect list, and the one that was chosen
+    /// the last time the word was corrected.
+    fn preferred_fixes(&self) -> Vec<String> {
+        let Some(word) = self.cursor_word() else {
+            return Vec::new();
+        };
+        let project = self.project.borrow();
+        let mut fixes = Vec::new();
+        fixes.extend(project.autocorrect(word));
+        if let Some(fix) = project.correction(word) {
+            if !fixes.iter().any(|f| f == fix) {
+                fixes.push(fix.to_string());
+            }
+        }
+        fixes
     }
 
     // If the cursor word is from a WordPart + Custom, then the Custom part is fixed
     // and can't be changed by suggestions. This is a helper function for dealing with that.
     fn cursor_word_fixed_suffix(&self) -> Option<String> {
-        if let Some(word) = self.cursor_word() {
-            // These indexes are safe because cursor_word() succeeded so there's a word there.
-            let lineinfo = &self.file.lines[self.cursor.linenr - 1];
-            let range = &lineinfo.bad_words_range[self.cursor.wordnr - 1];
-            let wordpart = &lineinfo.line.line[range.clone()];
-            if let Some(suffix) = word.strip_prefix(wordpart) {
-                if !suffix.is_empty() {
-                    return Some(suffix.to_string());
-                }
-            }
-        }
-        None
+        let word = self.cursor_word()?;
+        // These indexes are safe because cursor_word() succeeded so there's a word there.
+        let lineinfo = &self.file.lines[self.cursor.linenr - 1];
+        let range = &lineinfo.issues[self.cursor.issuenr - 1].range;
+        let wordpart = &lineinfo.line.line[range.clone()];
+        appcore::fixed_suffix(word, wordpart).map(str::to_string)
     }
 
     fn update_cursor(&mut self, cursor: Cursor) {
+        // The accept word panel is only for the word it was opened for.
+        self.accepting = false;
+        self.pending_accept = None;
         if self.cursor.linenr != cursor.linenr {
             self.change_line(self.cursor.linenr, |lineinfo| {
-                lineinfo.highlight_word_nr = 0
+                lineinfo.highlight_issue_nr = 0
             });
         }
         self.change_line(cursor.linenr, |lineinfo| {
-            lineinfo.highlight_word_nr = cursor.wordnr
+            lineinfo.highlight_issue_nr = cursor.issuenr
         });
         self.cursor = cursor;
+        self.update_cursor_word();
+    }
+
+    /// Tell the lines which word is at the cursor, so that they can
+    /// highlight its other occurrences. The lines that contain the old
+    /// or the new word highlight themselves again on CURSOR_CHANGED.
+    fn update_cursor_word(&mut self) {
+        let lineinfo = &self.file.lines[self.cursor.linenr - 1];
+        let word = self
+            .cursor_issue()
+            .filter(|issue| issue.category.is_word())
+            .map(|issue| lineinfo.line.line[issue.range.clone()].to_string())
+            .unwrap_or_default();
+        let mut cursor_word = self.file.cursor_word.borrow_mut();
+        if **cursor_word != word {
+            *cursor_word = Rc::new(word);
+        }
     }
 
+    /// Start looking for suggestions for the cursor word. They are shown
+    /// by `suggestions_found` when they arrive.
     fn update_suggestions(&mut self) {
-        self.suggestions = if let Some(word) = self.cursor_word() {
-            let opt_suffix = self.cursor_word_fixed_suffix();
-            Arc::new(
-                self.file
-                    .speller
-                    .borrow()
-                    .suggestions(word, 9)
+        self.suggester.cancel();
+        self.suggestions = Arc::new(Vec::new());
+        self.suggested_model = Arc::new(String::new());
+        self.model_id = 0;
+        if let Some(fix) = self.cursor_fix() {
+            self.suggestions = Arc::new(vec![Suggestion {
+                suggestion_nr: 1,
+                suggestion: Rc::new(fix),
+            }]);
+            self.suggestions_id = 0;
+            return;
+        }
+        // Offer the preferred fixes right away; the search adds the rest.
+        self.suggestions = Arc::new(
+            self.preferred_fixes()
+                .into_iter()
+                .enumerate()
+                .map(|(i, fix)| Suggestion {
+                    suggestion_nr: i + 1,
+                    suggestion: Rc::new(fix),
+                })
+                .collect(),
+        );
+        let config = if self.fast_suggestions {
+            SuggestionConfig::fast()
+        } else {
+            SuggestionConfig::default()
+        };
+        self.file.speller.borrow_mut().set_suggestion_config(config);
+        let speller = self.file.speller.borrow().shared();
+        let id = match (self.cursor_word(), speller) {
+            (Some(word), Some(speller)) => self.suggester.query(speller, word, 9),
+            _ => 0,
+        };
+        self.suggestions_id = id;
+        self.model_id = id;
+        self.no_suggestions = Arc::new(if id == 0 {
+            "(no suggestions)".to_string()
+        } else {
+            "(looking for suggestions…)".to_string()
+        });
+    }
+
+    /// Show the suggestions of search `id`, unless the cursor has moved
+    /// on since it started.
+    fn suggestions_found(&mut self, id: u64, found: &Suggestions) {
+        if id != self.suggestions_id {
+            return;
+        }
+        self.suggestions_id = 0;
+        let mut reason = &Suggestions::NoCandidates;
+        let suggestions = match found {
+            Suggestions::Found(suggestions) => &suggestions[..],
+            r => {
+                reason = r;
+                &[]
+            }
+        };
+        let opt_suffix = self.cursor_word_fixed_suffix();
+        let preferred = self.preferred_fixes();
+        self.suggestions = Arc::new(
+            preferred
+                .iter()
+                .chain(
+                    suggestions
+                        .iter()
+                        .filter(|s| !preferred.contains(s))
+                        .filter(|s| {
+                            if let Some(suffix) = &opt_suffix {
+                                s.ends_with(suffix)
+                            } else {
+                                true
+                            }
+                        }),
+                )
+                .take(9)
+                .enumerate()
+                .map(|(i, s)| Suggestion {
+                    suggestion_nr: i + 1,
+                    suggestion: Rc::new(s.to_string()),
+                })
+                .collect(),
+        );
+        self.no_suggestions = Arc::new(
+            match reason {
+                Suggestions::TimedOut => "(no suggestions: the search took too long)",
+                Suggestions::AllForbidden => "(no suggestions: the similar words are forbidden)",
+                Suggestions::TooLong => "(no suggestions: the word is too long)",
+                _ => "(no suggestions)",
+            }
+            .to_string(),
+        );
+    }
+
+    /// Keep the model word found by search `id`, unless the cursor has
+    /// moved on since it started. If the accept word panel is already
+    /// open and its model is still empty, it gets this one.
+    fn model_found(&mut self, id: u64, model: Option<&str>) {
+        if id != self.model_id {
+            return;
+        }
+        self.model_id = 0;
+        self.suggested_model = Arc::new(model.unwrap_or_default().to_string());
+        if self.accepting && self.accept_model.is_empty() {
+            self.accept_model = Arc::clone(&self.suggested_model);
+        }
+    }
+
+    /// Show the accept word panel for the cursor word, with the model
+    /// word that was found for it to copy the endings of.
+    fn start_accept(&mut self) {
+        // Punctuation can't be added to the dictionary, and words with
+        // letters from another script shouldn't be.
+        if self
+            .cursor_issue()
+            .is_some_and(|issue| issue.category != Category::Spelling)
+        {
+            return;
+        }
+        if self.cursor_word().is_some() {
+            self.accept_model = Arc::clone(&self.suggested_model);
+            self.accepting = true;
+        }
+    }
+
+    /// Describe which words will become valid if the cursor word is
+    /// accepted with the endings of the model word.
+    fn accept_preview(&self) -> String {
+        let Some(word) = self.cursor_word() else {
+            return String::new();
+        };
+        let model = self.accept_model.trim();
+        if model.is_empty() {
+            return format!("Only {} will be accepted.", word);
+        }
+        let words = self.file.speller.borrow().words_like(word, model);
+        if words.is_empty() {
+            format!("{} is not in the dictionary.", model)
+        } else {
+            format!("Will accept: {}", words.join(", "))
+        }
+    }
+
+    /// Accept `word` into the dictionary, with the endings of `model` if
+    /// given, and remember it as the accept scope says.
+    /// Returns false if the word could not be accepted.
+    fn accept_word(&mut self, word: &str, model: Option<&str>) -> Result<bool> {
+        let scope = self.accept_scope;
+        // The suggestions search holds the dictionary until it's done,
+        // but it checks for being cancelled as it goes, so this makes it
+        // let go soon.
+        self.suggester.cancel();
+        let accepted =
+            appcore::accept_word(&mut *self.file.speller.borrow_mut(), scope, word, model)?;
+        if accepted && scope == AcceptScope::Project {
+            self.project.borrow_mut().add_accepted(word, model);
+            self.save_project();
+            // The other languages' dictionaries that are already loaded
+            // need the word too. The rest get it when they are loaded.
+            let added = [AcceptedWord {
+                word: word.to_string(),
+                model: model.map(str::to_string),
+            }];
+            let mut done = vec![Rc::clone(&self.file.speller)];
+            for file in self.files.iter() {
+                if !done
                     .iter()
-                    .filter(|s| {
-                        if let Some(suffix) = &opt_suffix {
-                            s.ends_with(suffix)
-                        } else {
-                            true
-                        }
-                    })
-                    .take(9)
-                    .enumerate()
-                    .map(|(i, s)| Suggestion {
-                        suggestion_nr: i + 1,
-                        suggestion: Rc::new(s.to_string()),
-                    })
-                    .collect(),
-            )
+                    .any(|speller| Rc::ptr_eq(speller, &file.speller))
+                {
+                    file.speller.borrow_mut().add_accepted(&added);
+                    done.push(Rc::clone(&file.speller));
+                }
+            }
+        }
+        Ok(accepted)
+    }
+
+    /// Replace the cursor word with the suggestion `word`, and remember
+    /// the choice in the project so that it's offered first next time.
+    fn apply_suggestion(&mut self, word: &str, env: &Env) {
+        let suffix = self.cursor_word_fixed_suffix();
+        let issuenr = self.cursor.issuenr;
+        if issuenr > 0 {
+            // Only spelling fixes are a choice; the others are always the same.
+            if self
+                .cursor_issue()
+                .is_some_and(|issue| issue.category == Category::Spelling)
+            {
+                if let Some(misspelled) = self.cursor_word().cloned() {
+                    if self.project.borrow_mut().add_correction(&misspelled, word) {
+                        self.save_project();
+                    }
+                }
+            }
+            self.change_line(self.cursor.linenr, |lineinfo| {
+                if let Some(issue) = lineinfo.issues.get(issuenr - 1) {
+                    let linetext = appcore::apply_suggestion(
+                        &lineinfo.line.line,
+                        issue.range.clone(),
+                        word,
+                        suffix.as_deref(),
+                    );
+                    lineinfo.line.line = Rc::new(linetext);
+                    lineinfo.highlight(env);
+                }
+            });
+            self.recheck_cursor();
+        }
+    }
+
+    /// Replace every misspelled word in the current file that the
+    /// project knows a fix for. Returns the fixes that were made.
+    fn autofix_file(&mut self, env: &Env) -> Vec<AppliedFix> {
+        self.load_file_dictionary();
+        self.change_all_lines(|lineinfo| lineinfo.highlight(env));
+        let mut fixes = Vec::new();
+        for linenr in 1..=self.file.lines.len() {
+            let lineinfo = &self.file.lines[linenr - 1];
+            let project = self.project.borrow();
+            let mut linetext = lineinfo.line.line.to_string();
+            let mut line_fixes = Vec::new();
+            let words: Vec<&Issue> = lineinfo.words().collect();
+            // Go from the end of the line, so that the earlier ranges stay valid.
+            for issue in words.into_iter().rev() {
+                let Some(fix) = project.known_fix(&issue.text) else {
+                    continue;
+                };
+                let wordpart = &lineinfo.line.line[issue.range.clone()];
+                let suffix = appcore::fixed_suffix(&issue.text, wordpart);
+                if suffix.is_some_and(|suffix| !fix.ends_with(suffix)) {
+                    continue;
+                }
+                linetext = appcore::apply_suggestion(&linetext, issue.range.clone(), &fix, suffix);
+                line_fixes.push(AppliedFix {
+                    pathname: Rc::clone(&self.file.pathname),
+                    line_nr: linenr,
+                    word: issue.text.clone(),
+                    fix,
+                });
+            }
+            drop(project);
+            if !line_fixes.is_empty() {
+                let linetext = Rc::new(linetext);
+                self.change_line(linenr, |lineinfo| {
+                    lineinfo.line.line = Rc::clone(&linetext);
+                    lineinfo.highlight(env);
+                });
+                fixes.extend(line_fixes.into_iter().rev());
+            }
+        }
+        fixes
+    }
+
+    /// Apply the known fixes to all the files, and keep the list of
+    /// what was changed for the fixes panel.
+    fn apply_known_fixes(&mut self, env: &Env) {
+        let mut fixes = Vec::new();
+        self.for_each_file(|data| fixes.extend(data.autofix_file(env)));
+        // The cursor word may have been one of them.
+        self.recheck_cursor();
+        self.applied_fixes = Arc::new(fixes);
+    }
+
+    /// Show the line of an applied fix, if its file is still open.
+    fn goto_fix(&mut self, fix: &AppliedFix) {
+        let Some(idx) = self.files.iter().position(|f| f.pathname == fix.pathname) else {
+            return;
+        };
+        if idx != self.file_idx {
+            self.show_file(idx);
+        }
+        self.goto_line(fix.line_nr);
+    }
+
+    /// Start editing the cursor line.
+    fn start_edit(&mut self) {
+        self.editing_linenr = self.cursor.linenr;
+        self.editing_text = Arc::new(
+            self.file.lines[self.cursor.linenr - 1]
+                .line
+                .line
+                .to_string(),
+        );
+    }
+
+    /// Put the edited text in place of the line that was being edited.
+    fn apply_edit(&mut self, env: &Env) {
+        if self.editing_linenr == 0 {
+            return;
+        }
+        let new_text = self.editing_text.clone();
+        self.change_line(self.editing_linenr, |lineinfo| {
+            lineinfo.line.line = Rc::new(new_text.to_string());
+            lineinfo.highlight(env);
+        });
+        self.editing_linenr = 0;
+        self.editing_text = Arc::new(String::new());
+        self.recheck_cursor();
+    }
+
+    /// Put the cursor on an issue picked from the issues panel.
+    fn goto_issue(&mut self, found: &LineIssue) {
+        let Some(idx) = self.files.iter().position(|f| f.pathname == found.pathname) else {
+            return;
+        };
+        if idx != self.file_idx {
+            self.show_file(idx);
+        }
+        // The issue panel's issue may be one that the cursor
+        // filter skips, but it was picked, so go there anyway.
+        let lineinfo = &self.file.lines[found.line_nr - 1];
+        if let Some(idx) = lineinfo.issues.iter().position(|i| *i == found.issue) {
+            self.update_cursor(Cursor {
+                linenr: found.line_nr,
+                issuenr: idx + 1,
+            });
+            self.update_suggestions();
         } else {
-            Arc::new(Vec::new())
+            self.goto_line(found.line_nr);
+        }
+    }
+
+    /// Accept the cursor word, as it is or with the endings of the accept
+    /// model. If the dictionary marks the word as one to avoid, only ask
+    /// the user to confirm it, and let `confirm_accept` finish the job.
+    /// Returns the words that became valid, or None if nothing was
+    /// accepted.
+    fn accept_cursor_word(&mut self, like: bool) -> Option<Vec<String>> {
+        let word = self.cursor_word()?;
+        if self.file.speller.borrow().is_discouraged(word) {
+            let warning = format!(
+                "The dictionary marks {} as a word to avoid. Accept it anyway?",
+                word
+            );
+            self.pending_accept = Some(PendingAccept {
+                warning: Arc::new(warning),
+                like,
+            });
+            return None;
+        }
+        self.finish_accept(like)
+    }
+
+    /// Accept the word that the user was asked to confirm.
+    fn confirm_accept(&mut self) -> Option<Vec<String>> {
+        let pending = self.pending_accept.take()?;
+        self.finish_accept(pending.like)
+    }
+
+    fn finish_accept(&mut self, like: bool) -> Option<Vec<String>> {
+        let word = self.cursor_word()?.clone();
+        if !like {
+            return match self.accept_word(&word, None) {
+                Ok(true) => {
+                    self.accepting = false;
+                    Some(vec![word])
+                }
+                Ok(false) => {
+                    eprintln!("Could not accept {}", word);
+                    None
+                }
+                Err(err) => {
+                    eprintln!("{:#}", err);
+                    None
+                }
+            };
+        }
+        let model = self.accept_model.trim().to_string();
+        match self.accept_word(&word, Some(&model)) {
+            Ok(true) => {
+                self.accepting = false;
+                let mut words = self.file.speller.borrow().words_like(&word, &model);
+                words.push(word);
+                Some(words)
+            }
+            // Leave the panel open so the user can pick another model.
+            Ok(false) => {
+                eprintln!("Could not accept {} like {}", word, model);
+                None
+            }
+            Err(err) => {
+                eprintln!("{:#}", err);
+                None
+            }
+        }
+    }
+
+    /// Accept the cursor word for this session only, without adding it
+    /// to the user dictionary. Returns the word.
+    fn ignore_cursor_word(&mut self) -> Option<String> {
+        let word = self.cursor_word()?.clone();
+        self.suggester.cancel();
+        self.file.speller.borrow_mut().add_word(&word);
+        Some(word)
+    }
+
+    /// Remove the bad whitespace and invisible characters of the cursor line.
+    fn fix_cursor_whitespace(&mut self, env: &Env) {
+        self.change_line(self.cursor.linenr, |lineinfo| {
+            let bad = lineinfo.ranges(&[Category::Whitespace, Category::Invisible]);
+            if !bad.is_empty() {
+                let fixed = fix_whitespace(&lineinfo.line.line, &bad);
+                lineinfo.line.line = Rc::new(fixed);
+                lineinfo.highlight(env);
+            }
+        });
+        // The issues after the fixed whitespace have moved.
+        self.goto_line(self.cursor.linenr);
+    }
+
+    /// Record in the project that the cursor word should not be flagged
+    /// in this loc key. Returns the word.
+    fn ignore_at_key(&mut self) -> Option<String> {
+        let word = self.cursor_word()?.clone();
+        let lineinfo = &self.file.lines[self.cursor.linenr - 1];
+        let key = loc_key_name(&lineinfo.line.line, &lineinfo.tokens)?;
+        let ignore = Ignore {
+            file: self.file.filename.to_string(),
+            key: key.to_string(),
+            word: word.clone(),
         };
+        if self.project.borrow_mut().add_ignore(ignore) {
+            self.save_project();
+        }
+        Some(word)
+    }
+
+    fn remove_ignore(&mut self, ignore: &Ignore) {
+        self.project.borrow_mut().remove_ignore(ignore);
+        self.save_project();
+    }
+
+    fn save_project(&mut self) {
+        if let Err(err) = self.project.borrow().save() {
+            eprintln!("{:#}", err);
+        }
+        self.ignores = Arc::new(self.project.borrow().ignores().to_vec());
     }
 
     fn save_file(&self) -> Result<()> {
         self.file.save()
     }
 
+    /// Save the current file and stop showing it. Returns false if it
+    /// was the last file, in which case it's still shown.
+    fn save_and_close(&mut self) -> bool {
+        if let Err(err) = self.save_file().with_context(|| "Could not save file") {
+            eprintln!("{:#}", err);
+        }
+        if self.files.len() == 1 {
+            return false;
+        }
+        self.drop_file();
+        true
+    }
+
+    /// Collect the issues of all the files that pass the filter, for the
+    /// issues panel.
+    fn update_issue_list(&mut self) {
+        let list = self
+            .files
+            .iter()
+            .flat_map(|file| file.issues.iter())
+            .filter(|found| found.issue.severity() <= self.issue_filter)
+            .cloned()
+            .collect();
+        self.issue_list = Arc::new(list);
+    }
+
+    /// Bring the files and the issues panel up to date after the lines
+    /// of the current file have changed.
+    fn lines_changed(&mut self) {
+        self.file.update_issues();
+        Rc::make_mut(&mut self.files)[self.file_idx] = self.file.clone();
+        self.update_issue_list();
+        self.update_misspellings();
+    }
+
+    /// Count the misspelled words of the files checked so far, for the
+    /// misspellings panel if it's shown. The counts arrive later, from
+    /// the worker thread.
+    fn update_misspellings(&mut self) {
+        if !self.showing_misspellings {
+            return;
+        }
+        let issues = self
+            .files
+            .iter()
+            .flat_map(|file| file.issues.iter())
+            .filter(|found| found.issue.category == Category::Spelling)
+            .map(|found| found.issue.clone())
+            .collect();
+        self.misspellings_id = self.counter.count(issues);
+    }
+
+    fn misspellings_counted(&mut self, id: u64, counts: &Arc<Vec<Misspelling>>) {
+        if id == self.misspellings_id {
+            self.misspellings = Arc::clone(counts);
+        }
+    }
+
+    /// Call `f` with each of the files in turn as the current file, and
+    /// then go back to the file that was current.
+    fn for_each_file(&mut self, mut f: impl FnMut(&mut Self)) {
+        let file_idx = self.file_idx;
+        for idx in 0..self.files.len() {
+            self.file_idx = idx;
+            self.file = self.files[idx].clone();
+            f(self);
+        }
+        self.file_idx = file_idx;
+        self.file = self.files[file_idx].clone();
+        self.check_dictionary();
+    }
+
+    /// Move the cursor to the next place where `word` is misspelled,
+    /// looking through the files after the current one too, and going
+    /// round to the first file after the last one.
+    fn goto_misspelling(&mut self, word: &str) {
+        let occurs = |issue: &Issue| issue.category == Category::Spelling && issue.text == word;
+        let nfiles = self.files.len();
+        for step in 0..=nfiles {
+            let idx = (self.file_idx + step) % nfiles;
+            // In the current file, start after the cursor; the last step
+            // comes back round to its start.
+            let start = if step == 0 {
+                self.cursor
+            } else {
+                Cursor::default()
+            };
+            let found = self.files[idx]
+                .lines
+                .iter()
+                .enumerate()
+                .flat_map(|(lineidx, lineinfo)| {
+                    lineinfo
+                        .issues
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, issue)| occurs(issue))
+                        .map(move |(issueidx, _)| Cursor {
+                            linenr: lineidx + 1,
+                            issuenr: issueidx + 1,
+                        })
+                })
+                .find(|c| (c.linenr, c.issuenr) > (start.linenr, start.issuenr));
+            if let Some(cursor) = found {
+                if idx != self.file_idx {
+                    self.show_file(idx);
+                }
+                self.update_cursor(cursor);
+                self.update_suggestions();
+                return;
+            }
+        }
+    }
+
     fn drop_file(&mut self) {
         self.update_cursor(Cursor::default());
         self.update_suggestions();
@@ -360,20 +1408,39 @@ impl AppState {
         let mut files = (*self.files).clone();
         files.remove(self.file_idx);
         self.files = Rc::new(files);
+        // If the last file was dropped, show the one before it.
+        self.file_idx = self.file_idx.min(self.files.len() - 1);
         self.file = self.files[self.file_idx].clone();
+        self.check_dictionary();
+        self.update_issue_list();
+        self.update_misspellings();
     }
 
     fn change_line(&mut self, linenr: usize, f: impl Fn(&mut LineInfo)) {
         // This takes the self.file version of the file as authoritative,
-        // and copies it into the self.files vec.
-        let mut files = (*self.files).clone();
-        let mut lines = (*self.file.lines).clone();
+        // and copies it into the self.files vec. Only the changed lines
+        // are copied; the others stay shared with the previous version.
+        let lines = Arc::make_mut(&mut self.file.lines);
         if let Some(lineinfo) = lines.get_mut(linenr - 1) {
+            let lineinfo = Arc::make_mut(lineinfo);
             f(lineinfo);
-            files[self.file_idx].lines = Arc::new(lines);
-            self.files = Rc::new(files);
-            self.file = self.files[self.file_idx].clone();
+            // The line may have gained or lost a directive for the next line.
+            let suppress = Suppression::for_next_line(&lineinfo.line.line);
+            if let Some(next) = lines.get_mut(linenr) {
+                if *next.suppress_above != suppress {
+                    Arc::make_mut(next).suppress_above = Rc::new(suppress);
+                }
+            }
+            self.lines_changed();
+        }
+    }
+
+    fn change_all_lines(&mut self, f: impl Fn(&mut LineInfo)) {
+        // Same as change_line, but for every line in the file.
+        for lineinfo in Arc::make_mut(&mut self.file.lines) {
+            f(Arc::make_mut(lineinfo));
         }
+        self.lines_changed();
     }
 }
 
@@ -407,18 +1474,55 @@ fn locale_from_filename(pathname: &Path) -> Result<&str> {
     Err(anyhow!("Could not determine language from filename"))
 }
 
+/// Parse `line` into the tokens that are highlighted, including the
+/// words of its comment if comments are checked.
+fn parse_tokens(line: &str, check_comments: bool) -> Vec<Token> {
+    let mut tokens = parse_line(line);
+    if check_comments {
+        if let Some(comment) = tokens.iter().find(|t| t.ttype == TokenType::Comment) {
+            // Don't check the words of a ck3spell directive.
+            let end = Suppression::from_comment(&line[comment.range.clone()])
+                .map_or(comment.range.end, |(_, start)| comment.range.start + start);
+            let words = comment_words(line, comment);
+            tokens.extend(words.into_iter().filter(|t| t.range.end <= end));
+        }
+    }
+    tokens
+}
+
 fn highlight_syntax(lineinfo: &mut LineInfo, env: &Env) {
+    if !matches!(&lineinfo.tokens_line, Some(parsed) if parsed.same(&lineinfo.line.line)) {
+        let tokens = parse_tokens(&lineinfo.line.line, env.get(CHECK_COMMENTS));
+        lineinfo.tokens = Rc::new(tokens);
+        lineinfo.tokens_line = Some(lineinfo.line.line.clone());
+    }
+    let tokens = lineinfo.tokens.clone();
     let line = &lineinfo.line.line;
-    let mut text = RichText::new((*line.as_str()).into());
-    let mut bad_words_range = Vec::new();
-    let mut bad_words_text = Vec::new();
+    // Invisible characters are shown as visible ones, to mark them.
+    let invisible_range = invisible_chars(line);
+    let mut text = RichText::new(show_invisible(line, &invisible_range).into());
+    let mut issues = Vec::new();
+    let mut bad_segments = Vec::new();
+    let segmentation = Segmentation::from_env(env);
+    let joins = JoinPolicies::from_env(env);
+
+    let mut suppress = (*lineinfo.suppress_above).clone();
+    if let Some(comment) = tokens.iter().find(|t| t.ttype == TokenType::Comment) {
+        if let Some((s, _)) = Suppression::from_comment(&line[comment.range.clone()]) {
+            suppress.add(&s);
+        }
+    }
+    if let Some(key) = loc_key_name(line, &tokens) {
+        let project = lineinfo.project.borrow();
+        suppress.add(&project.suppression(&lineinfo.filename, key));
+    }
 
-    let tokens = parse_line(line);
     for i in 0..tokens.len() {
         let token = &tokens[i];
 
         let mut color = match token.ttype {
             TokenType::Comment => env.get(COMMENT_COLOR),
+            TokenType::CommentWord => env.get(COMMENT_COLOR),
             TokenType::LocKey => env.get(LOC_KEY_COLOR),
             TokenType::KeyReference => env.get(KEYWORD_COLOR),
             TokenType::Word => env.get(WORD_COLOR),
@@ -428,54 +1532,212 @@ fn highlight_syntax(lineinfo: &mut LineInfo, env: &Env) {
             TokenType::Custom => env.get(CUSTOM_COLOR),
             TokenType::Markup => env.get(MARKUP_COLOR),
             TokenType::IconTag => env.get(ICON_TAG_COLOR),
+            TokenType::Literal => env.get(LITERAL_COLOR),
+        };
+
+        let policy = match token.ttype {
+            TokenType::Word | TokenType::CommentWord => JoinPolicy::CheckAlone,
+            TokenType::WordPart => joins.policy(word_part_join(line, &tokens, i)),
+            _ => JoinPolicy::Skip,
         };
 
-        if let TokenType::WordPart = token.ttype {
-            // Look for a sequence WordPart, Code, Custom, Code (the last Code
+        if let JoinPolicy::CustomEndings = policy {
+            // The sequence is WordPart, Code, Custom, Code (the last Code
             // is not checked), where the WordPart directly borders the Code.
             // For example: meilleur[bg_opponent.Custom('FR_E')]
             //              ^^^^^^^^ WordPart            ^^^^ Custom
-            if i + 2 < tokens.len()
-                && tokens[i + 2].ttype == TokenType::Custom
-                && tokens[i + 1].ttype == TokenType::Code
-                && token.range.end == tokens[i + 1].range.start
-            {
-                let custom = &line[tokens[i + 2].range.clone()];
-                if let Some(endings) = lineinfo.custom.check(custom) {
+            let custom = &line[tokens[i + 2].range.clone()];
+            if let Some(endings) = lineinfo.custom.check(custom) {
+                let stem = &line[token.range.clone()];
+                if is_long_word(stem, env) {
+                    if !suppress.word(stem) {
+                        issues.push(Issue::new(Category::LongWord, token.range.clone(), stem));
+                    }
+                } else {
                     for ending in endings {
-                        let word = line[token.range.clone()].to_string() + ending;
-                        if !lineinfo.speller.borrow().spellcheck(&word) {
+                        let word = stem.to_string() + ending;
+                        if !suppress.word(&word) && !spellcheck(lineinfo, &word) {
                             color = env.get(MISSPELLED_COLOR);
-                            bad_words_range.push(token.range.clone());
-                            bad_words_text.push(word);
+                            issues.push(Issue::new(Category::Spelling, token.range.clone(), &word));
                             break;
                         }
                     }
                 }
             }
-        } else if let TokenType::Word = token.ttype {
+        } else if let JoinPolicy::CheckJoined = policy {
+            // Check the whole word first, as "Anglo-Saxon" for
+            // `Anglo-[GetTitle]-Saxon`, then mark only its bad parts.
+            if let Some(joined) = joined_word(line, &tokens, i) {
+                if !suppress.word(&joined.text) {
+                    if is_long_word(&joined.text, env) {
+                        let end = joined.parts.last().map_or(token.range.end, |(r, _)| r.end);
+                        let range = token.range.start..end;
+                        issues.push(Issue::new(Category::LongWord, range, &joined.text));
+                    } else if !is_good_word(lineinfo, &joined.text, env) {
+                        for (range, part) in joined.parts {
+                            if !suppress.word(&part) && !is_good_word(lineinfo, &part, env) {
+                                bad_segments.push(range.clone());
+                                issues.push(Issue::new(Category::Spelling, range, &part));
+                            }
+                        }
+                    }
+                }
+            }
+        } else if let JoinPolicy::CheckAlone = policy {
             let word = &line[token.range.clone()];
-            if word.chars().count() > 1 && !lineinfo.speller.borrow().spellcheck(word) {
-                color = env.get(MISSPELLED_COLOR);
-                bad_words_range.push(token.range.clone());
-                bad_words_text.push(lineinfo.line.line[token.range.clone()].to_string())
+            if !suppress.word(word) {
+                if is_long_word(word, env) {
+                    issues.push(Issue::new(Category::LongWord, token.range.clone(), word));
+                } else if homoglyph::is_mixed(word) && !spellcheck(lineinfo, word) {
+                    // Such words are only in the dictionary or the ignore
+                    // list if they were put there on purpose, and their
+                    // suggestions would be for the wrong script.
+                    color = env.get(MISSPELLED_COLOR);
+                    issues.push(Issue::new(Category::MixedScript, token.range.clone(), word));
+                } else if !is_good_word(lineinfo, word, env) {
+                    // Check the segments separately, and only mark the bad ones.
+                    let segments = segmentation.segments(word);
+                    if segments.len() > 1 {
+                        for segment in segments {
+                            let range =
+                                token.range.start + segment.start..token.range.start + segment.end;
+                            let segment = &line[range.clone()];
+                            if !suppress.word(segment) && !is_good_word(lineinfo, segment, env) {
+                                bad_segments.push(range.clone());
+                                issues.push(Issue::new(Category::Spelling, range.clone(), segment));
+                            }
+                        }
+                    } else {
+                        color = env.get(MISSPELLED_COLOR);
+                        issues.push(Issue::new(Category::Spelling, token.range.clone(), word));
+                    }
+                }
             }
         }
 
         text.add_attribute(token.range.clone(), Attribute::text_color(color));
     }
+    // The bad parts of a joined word may be in tokens after it, so they
+    // are colored after all the tokens.
+    for range in bad_segments {
+        text.add_attribute(range, Attribute::text_color(env.get(MISSPELLED_COLOR)));
+    }
+
+    if let Some(typography) = Typography::from_env(env) {
+        for range in find_in_text(line, &tokens, &typography.patterns()) {
+            let found = &line[range.clone()];
+            if suppress.word(found) {
+                continue;
+            }
+            text.add_attribute(
+                range.clone(),
+                Attribute::text_color(env.get(TYPOGRAPHY_COLOR)),
+            );
+            issues.push(Issue::new(Category::Typography, range, found));
+        }
+    }
+
+    if !suppress.line() {
+        for range in unbalanced_markup(line, &tokens) {
+            text.add_attribute(
+                range.clone(),
+                Attribute::text_color(env.get(BAD_MARKUP_COLOR)),
+            );
+            issues.push(Issue::new(Category::Markup, range.clone(), &line[range]));
+        }
+
+        for range in malformed_code(line, &tokens) {
+            text.add_attribute(
+                range.clone(),
+                Attribute::text_color(env.get(BAD_CODE_COLOR)),
+            );
+            issues.push(Issue::new(Category::Code, range.clone(), &line[range]));
+        }
+
+        for range in bad_whitespace(line, &tokens) {
+            // Spaces have no color of their own, so strike them through.
+            text.add_attribute(
+                range.clone(),
+                Attribute::text_color(env.get(WHITESPACE_COLOR)),
+            );
+            text.add_attribute(range.clone(), Attribute::strikethrough(true));
+            issues.push(Issue::new(
+                Category::Whitespace,
+                range.clone(),
+                &line[range],
+            ));
+        }
+
+        for range in invisible_range {
+            text.add_attribute(
+                range.clone(),
+                Attribute::text_color(env.get(INVISIBLE_COLOR)),
+            );
+            issues.push(Issue::new(Category::Invisible, range.clone(), &line[range]));
+        }
+
+        let max_length = env.get(MAX_LENGTH);
+        if max_length > 0 && visible_length(line, &tokens) as u64 > max_length {
+            issues.push(Issue::new(Category::Length, 0..line.len(), ""));
+        }
+    }
+    // Keep the issues in order, for the cursor to go through the words.
+    // The sort is stable, so issues that start together keep their order.
+    issues.sort_by_key(|issue| issue.range.start);
+    lineinfo.issues = Rc::new(issues);
+
+    // Mark the other occurrences of the cursor word, to help decide
+    // whether a fix should be applied everywhere.
+    let cursor_word = Rc::clone(&lineinfo.cursor_word.borrow());
+    if !cursor_word.is_empty() {
+        let marked = lineinfo.marked_range();
+        for token in tokens.iter() {
+            if matches!(
+                token.ttype,
+                TokenType::Word | TokenType::WordPart | TokenType::CommentWord
+            ) && line[token.range.clone()] == *cursor_word
+                && marked.as_ref() != Some(&token.range)
+            {
+                text.add_attribute(
+                    token.range.clone(),
+                    Attribute::text_color(env.get(OCCURRENCE_COLOR)),
+                );
+            }
+        }
+    }
     lineinfo.rendered = text;
-    lineinfo.bad_words_range = Rc::new(bad_words_range);
-    lineinfo.bad_words_text = Rc::new(bad_words_text);
+}
+
+/// Returns true if `word` is too long to be spellchecked, such as a
+/// pasted blob of base64. A maximum of 0 means there is no limit.
+fn is_long_word(word: &str, env: &Env) -> bool {
+    let max = env.get(MAX_WORD_LENGTH);
+    max > 0 && word.chars().count() as u64 > max
+}
+
+/// Returns true if `word` should not be marked as misspelled.
+fn is_good_word(lineinfo: &LineInfo, word: &str, env: &Env) -> bool {
+    word.chars().count() <= 1
+        || lineinfo.numbers.check(word)
+        || (env.get(ROMAN_NUMERALS) && lineinfo.numbers.is_roman_numeral(word))
+        || spellcheck(lineinfo, word)
+}
+
+fn spellcheck(lineinfo: &LineInfo, word: &str) -> bool {
+    lineinfo.memo.check(word, |word| {
+        lineinfo.project.borrow().ignores_word(word) || lineinfo.speller.borrow().spellcheck(word)
+    })
 }
 
 fn split_lines(
     contents: &str,
-    speller: &Rc<RefCell<dyn Speller>>,
-    custom: &Rc<CustomEndings>,
-) -> Vec<LineInfo> {
-    let mut lines: Vec<LineInfo> = Vec::new();
+    file: &FileState,
+    project: &Rc<RefCell<Project>>,
+) -> Vec<Arc<LineInfo>> {
+    let speller: Rc<RefCell<dyn Speller>> = file.speller.clone();
+    let mut lines: Vec<Arc<LineInfo>> = Vec::new();
     let mut line_iter = contents.split('\n').enumerate().peekable();
+    let mut suppress_above = Suppression::default();
     while let Some((nr, line)) = line_iter.next() {
         let numbered_line = if line_iter.peek().is_none() {
             if !line.is_empty() {
@@ -500,15 +1762,23 @@ fn split_lines(
                 line_end: LineEnd::NL,
             }
         };
-        lines.push(LineInfo {
+        let suppress_next = Suppression::for_next_line(&numbered_line.line);
+        lines.push(Arc::new(LineInfo {
             line: numbered_line,
             rendered: RichText::new("".into()),
-            bad_words_range: Rc::new(Vec::new()),
-            bad_words_text: Rc::new(Vec::new()),
-            highlight_word_nr: 0,
-            speller: Rc::clone(speller),
-            custom: Rc::clone(custom),
-        });
+            issues: Rc::new(Vec::new()),
+            highlight_issue_nr: 0,
+            cursor_word: Rc::clone(&file.cursor_word),
+            tokens: Rc::new(Vec::new()),
+            tokens_line: None,
+            suppress_above: Rc::new(replace(&mut suppress_above, suppress_next)),
+            filename: Rc::clone(&file.filename),
+            project: Rc::clone(project),
+            speller: Rc::clone(&speller),
+            memo: Rc::clone(&file.memo),
+            custom: Rc::clone(&file.custom),
+            numbers: Rc::clone(&file.numbers),
+        }));
     }
     lines
 }
@@ -528,131 +1798,1135 @@ fn expand_dir(dir: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Look for a Hunspell-format dictionary for the given `locale` in `dir`,
+/// or in the LibreOffice or Mozilla dictionary extensions in `dir`.
+/// Return a tuple of paths to the dictionary file and the affix file.
+fn dictionary_in_dir(dir: &Path, locale: &str) -> Option<(PathBuf, PathBuf)> {
+    let pdic = dir.join(format!("{}.dic", locale));
+    let paff = dir.join(format!("{}.aff", locale));
+
+    if Path::exists(&pdic) && Path::exists(&paff) {
+        Some((pdic, paff))
+    } else {
+        dictionary_in_extensions(dir, locale)
+    }
+}
+
 /// Look for Hunspell-format dictionaries for the given `locale` in the
 /// provided directory search path. Return a tuple of paths to the
 /// dictionary file and the affix file.
-pub fn find_dictionary(search_path: Vec<&str>, locale: &str) -> Option<(PathBuf, PathBuf)> {
+pub fn find_dictionary(search_path: &[PathBuf], locale: &str) -> Option<(PathBuf, PathBuf)> {
     for dir in search_path {
-        let dir = match expand_dir(&PathBuf::from(dir)) {
+        let dir = match expand_dir(dir) {
             Some(dir) => dir,
             None => {
-                eprintln!("Could not expand path {}", dir);
+                eprintln!("Could not expand path {}", dir.display());
                 continue;
             }
         };
 
         eprint!("Looking for dictionary in {}", dir.display());
 
-        let pdic = dir.join(format!("{}.dic", locale));
-        let paff = dir.join(format!("{}.aff", locale));
-
-        if Path::exists(&pdic) && Path::exists(&paff) {
+        if let Some(found) = dictionary_in_dir(&dir, locale) {
             eprintln!(" ... found");
-            return Some((pdic, paff));
+            return Some(found);
         }
         eprintln!();
     }
     None
 }
 
-fn load_file(
-    pathname: &Path,
-    local_dict: Option<&PathBuf>,
-    dicts: &mut HashMap<String, Rc<RefCell<dyn Speller>>>,
-    customs: &mut HashMap<String, Rc<CustomEndings>>,
-) -> Result<FileState> {
+fn list_languages() {
+    for (tag, locale, name) in LANGUAGES {
+        if locale.is_empty() {
+            println!("{:<16} {:<6} {} (not supported)", tag, "-", name);
+        } else {
+            println!("{:<16} {:<6} {}", tag, locale, name);
+        }
+    }
+}
+
+/// Build the dictionary search path from the `--dict-path` options,
+/// the `CK3SPELL_DICT_PATH` environment variable, and the default
+/// search path, in that order.
+fn dictionary_search_path(dict_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut search_path = dict_paths.to_vec();
+    if let Some(env_path) = var_os(DICTIONARY_PATH_ENV) {
+        search_path.extend(split_paths(&env_path).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    search_path.extend(DICTIONARY_SEARCH_PATH.iter().map(PathBuf::from));
+    search_path
+}
+
+fn list_dicts(search_path: &[PathBuf]) {
+    let dirs: Vec<(&PathBuf, Option<PathBuf>)> = search_path
+        .iter()
+        .map(|dir| (dir, expand_dir(dir)))
+        .collect();
+
+    println!("Dictionary search path:");
+    for (dir, expanded) in &dirs {
+        match expanded {
+            Some(expanded) => println!("  {}", expanded.display()),
+            None => println!("  {} (could not expand)", dir.display()),
+        }
+    }
+
+    println!("Dictionaries:");
+    for (_, locale, name) in LANGUAGES {
+        if locale.is_empty() {
+            continue;
+        }
+        let found = dirs
+            .iter()
+            .filter_map(|(_, expanded)| expanded.as_ref())
+            .find_map(|dir| dictionary_in_dir(dir, locale));
+        match found {
+            Some((pdic, _)) => println!("  {} ({}): {}", locale, name, pdic.display()),
+            None => println!("  {} ({}): not found", locale, name),
+        }
+    }
+}
+
+/// The settings from the command line that every dictionary is loaded with.
+#[derive(Clone, Debug)]
+struct DictionarySettings {
+    engine: Engine,
+    local_dict: Option<PathBuf>,
+    keyboard: Option<String>,
+    max_word_length: u64,
+    normalize_apostrophes: bool,
+}
+
+/// Load the dictionary for `locale`, together with the user's local dictionary.
+fn load_dictionary(
+    locale: &str,
+    dictpath: &Path,
+    affixpath: &Path,
+    settings: &DictionarySettings,
+) -> Result<Box<dyn Speller + Send + Sync>> {
+    let mut speller: Box<dyn Speller + Send + Sync> = match settings.engine {
+        Engine::Native => {
+            let mut speller = SpellerHunspellDict::new(dictpath, affixpath)
+                .with_context(|| format!("Could not load dictionary for {}", locale))?;
+            for e in speller.get_errors() {
+                eprintln!("Dictionary error: {}", e);
+            }
+            speller.set_normalize_apostrophes(settings.normalize_apostrophes);
+            speller.set_keyboard(settings.keyboard.as_deref());
+            let layout_pairs: Vec<(&str, &str)> = WRONG_LAYOUTS
+                .iter()
+                .filter(|(l, _, _)| *l == locale)
+                .map(|&(_, layout, other)| (layout, other))
+                .collect();
+            speller.set_layout_pairs(&layout_pairs);
+            speller.set_config(SpellerConfig {
+                max_word_length: settings.max_word_length as usize,
+                ..speller.config()
+            });
+            Box::new(speller)
+        }
+        #[cfg(feature = "system-hunspell")]
+        Engine::Hunspell => Box::new(
+            SpellerHunspellLib::new(dictpath, affixpath)
+                .with_context(|| format!("Could not load dictionary for {}", locale))?,
+        ),
+    };
+    if let Some(local_dict) = &settings.local_dict {
+        let added = speller.set_user_dict(local_dict)?;
+        eprintln!(
+            "Loaded {} words from local dictionary {}",
+            added,
+            local_dict.display()
+        );
+    }
+    Ok(speller)
+}
+
+fn read_file(pathname: &Path) -> Result<String> {
     let mut contents = std::fs::read_to_string(pathname)
         .with_context(|| format!("Could not read file {}", pathname.display()))?;
     if contents.starts_with('\u{feff}') {
         contents.remove(0); // Remove BOM
     }
+    Ok(contents)
+}
 
-    let locale = locale_from_filename(pathname)?;
-    let speller = if dicts.contains_key(locale) {
-        dicts[locale].clone()
-    } else {
-        eprintln!("Using locale {}", locale);
-        let mut speller = match find_dictionary(DICTIONARY_SEARCH_PATH.to_vec(), locale) {
-            Some((dictpath, affixpath)) => SpellerHunspellDict::new(&dictpath, &affixpath),
-            None => Err(anyhow!("Dictionary not found")),
-        }?;
-        for e in speller.get_errors() {
-            eprintln!("Dictionary error: {}", e);
-        }
-        if let Some(local_dict) = local_dict {
-            eprint!("Using local dictionary {} ...", local_dict.display());
-            let added = speller.set_user_dict(local_dict)?;
-            eprintln!("loaded {} words", added);
-        }
-        let speller = Rc::new(RefCell::new(speller));
-        dicts.insert(locale.to_string(), speller.clone());
-        speller
-    };
+/// A file that was read and parsed on a worker thread, so that only
+/// the spellchecking is left for the UI thread.
+pub struct ParsedFile {
+    contents: String,
+    /// The tokens of each line of `contents`, in the order that
+    /// `split_lines` makes them.
+    tokens: Vec<Vec<Token>>,
+}
 
-    if !customs.contains_key(locale) {
-        customs.insert(locale.to_string(), Rc::new(CustomEndings::new(locale)));
+impl ParsedFile {
+    fn read(pathname: &Path, check_comments: bool) -> Result<Self> {
+        let contents = read_file(pathname)?;
+        let mut tokens = Vec::new();
+        let mut lines = contents.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            // Like split_lines, only lines with a line end after them
+            // lose their '\r'.
+            let line = match lines.peek() {
+                Some(_) => line.strip_suffix('\r').unwrap_or(line),
+                None => line,
+            };
+            tokens.push(parse_tokens(line, check_comments));
+        }
+        Ok(ParsedFile { contents, tokens })
     }
-    let custom = customs[locale].clone();
+}
 
-    Ok(FileState::new(pathname, &contents, speller, custom))
+/// Read and parse the files on several threads at once, because big
+/// mods have hundreds of them. `each` gets the results in the same
+/// order as `paths`, as soon as the files before them are done too.
+/// If it returns false, the rest of the files are not read.
+fn read_files(
+    paths: &[PathBuf],
+    check_comments: bool,
+    mut each: impl FnMut(&Path, Result<ParsedFile>) -> bool,
+) {
+    let threads = available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = channel();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(i) else {
+                    break;
+                };
+                let parsed = ParsedFile::read(path, check_comments);
+                if sender.send((i, parsed)).is_err() {
+                    break;
+                }
+            });
+        }
+        // Drop the original sender so that the loop below ends when
+        // the threads are done.
+        drop(sender);
+        let mut results: Vec<Option<Result<ParsedFile>>> = paths.iter().map(|_| None).collect();
+        let mut done = 0;
+        for (i, result) in receiver {
+            results[i] = Some(result);
+            while let Some(result) = results.get_mut(done).and_then(Option::take) {
+                if !each(&paths[done], result) {
+                    // This drops the receiver, so the threads stop
+                    // when they can't send any more.
+                    return;
+                }
+                done += 1;
+            }
+        }
+    });
+}
+
+/// Makes FileStates of the files that were read, giving the files of
+/// each language the same dictionary and word endings.
+struct FileLoader {
+    project: Rc<RefCell<Project>>,
+    dicts: HashMap<String, Rc<RefCell<LazySpeller>>>,
+    customs: HashMap<String, Rc<CustomEndings>>,
+    numbers: HashMap<String, Rc<NumberSuffixes>>,
+}
+
+impl FileLoader {
+    fn new(
+        project: &Rc<RefCell<Project>>,
+        dicts: HashMap<String, Rc<RefCell<LazySpeller>>>,
+    ) -> Self {
+        FileLoader {
+            project: Rc::clone(project),
+            dicts,
+            customs: HashMap::new(),
+            numbers: HashMap::new(),
+        }
+    }
+
+    fn load(&mut self, pathname: &Path, parsed: ParsedFile) -> Result<FileState> {
+        let locale = locale_from_filename(pathname)?;
+        let speller = self
+            .dicts
+            .get(locale)
+            .ok_or_else(|| anyhow!("No dictionary for {}", locale))?
+            .clone();
+        let custom = self
+            .customs
+            .entry(locale.to_string())
+            .or_insert_with(|| Rc::new(CustomEndings::new(locale)))
+            .clone();
+        let number_suffixes = self
+            .numbers
+            .entry(locale.to_string())
+            .or_insert_with(|| Rc::new(NumberSuffixes::new(locale)))
+            .clone();
+        let mut file = FileState::new(
+            pathname,
+            &parsed.contents,
+            &self.project,
+            speller,
+            custom,
+            number_suffixes,
+        );
+        file.set_tokens(parsed.tokens);
+        Ok(file)
+    }
+}
+
+/// The settings from the command line that change how lines are checked.
+#[derive(Clone, Copy, Debug)]
+struct CheckSettings {
+    segmentation: Segmentation,
+    joins: JoinPolicies,
+    check_comments: bool,
+    roman_numerals: bool,
+    max_length: u64,
+    max_word_length: u64,
+    typography: Option<Typography>,
+}
+
+/// Set the colors, and the options that the highlighting reads from the env.
+fn set_env(env: &mut Env, settings: &CheckSettings) {
+    env.set(LOC_KEY_COLOR, Color::rgb8(0xff, 0xa5, 0x00));
+    env.set(WORD_COLOR, Color::rgb8(0xFF, 0xFF, 0xFF));
+    env.set(MISSPELLED_COLOR, Color::rgb8(0xFF, 0x40, 0x40));
+    env.set(CODE_COLOR, Color::rgb8(0x60, 0x60, 0xFF));
+    env.set(CUSTOM_COLOR, Color::rgb8(0x80, 0x80, 0xFF));
+    env.set(KEYWORD_COLOR, Color::rgb8(0xc0, 0xa0, 0x00));
+    env.set(ESCAPE_COLOR, Color::rgb8(0xc0, 0xa0, 0x00));
+    env.set(COMMENT_COLOR, Color::rgb8(0xc0, 0xa0, 0x50));
+    env.set(MARKUP_COLOR, Color::rgb8(0x80, 0x80, 0xc0));
+    env.set(ICON_TAG_COLOR, Color::rgb8(0xff, 0xd7, 0x00));
+    env.set(BAD_MARKUP_COLOR, Color::rgb8(0xff, 0x40, 0xff));
+    env.set(BAD_CODE_COLOR, Color::rgb8(0xff, 0x80, 0x00));
+    env.set(WHITESPACE_COLOR, Color::rgb8(0x80, 0x80, 0x80));
+    env.set(INVISIBLE_COLOR, Color::rgb8(0xff, 0x40, 0xa0));
+    env.set(TYPOGRAPHY_COLOR, Color::rgb8(0xff, 0x80, 0x80));
+    env.set(LITERAL_COLOR, Color::rgb8(0x80, 0xc0, 0xc0));
+    env.set(OCCURRENCE_COLOR, Color::rgb8(0xc0, 0x80, 0x80));
+    env.set(LONG_LINE_COLOR, Color::rgb8(0x40, 0xa0, 0xff));
+    env.set(LINE_COLOR, Color::rgba8(0x80, 0x80, 0x80, 0x20));
+    env.set(WRAP_LINES, true);
+    settings.segmentation.set_env(env);
+    settings.joins.set_env(env);
+    env.set(CHECK_COMMENTS, settings.check_comments);
+    env.set(ROMAN_NUMERALS, settings.roman_numerals);
+    env.set(MAX_LENGTH, settings.max_length);
+    env.set(MAX_WORD_LENGTH, settings.max_word_length);
+    Typography::set_env(settings.typography, env);
+}
+
+/// Apply the known fixes to all the files, save the ones that changed,
+/// and print the fixes so that they can be reviewed.
+fn autofix_files(data: &mut AppState, env: &Env) {
+    data.for_each_file(|data| {
+        let fixes = data.autofix_file(env);
+        if fixes.is_empty() {
+            return;
+        }
+        for fix in fixes {
+            println!(
+                "{}:{}: {} -> {}",
+                fix.pathname.display(),
+                fix.line_nr,
+                fix.word,
+                fix.fix
+            );
+        }
+        if let Err(err) = data.save_file() {
+            eprintln!("{:#}", err.context("Could not save file"));
+        }
+    });
 }
 
 fn main() -> Result<()> {
     let args = Cli::parse();
+    let search_path = dictionary_search_path(&args.dict_path);
+
+    if args.list_languages || args.list_dicts {
+        if args.list_languages {
+            list_languages();
+        }
+        if args.list_dicts {
+            list_dicts(&search_path);
+        }
+        return Ok(());
+    }
+
+    let mut project = match &args.project {
+        Some(path) => Project::load(path)?,
+        None => Project::default(),
+    };
+    project.set_key_filter(KeyFilter::new(args.only_keys, args.skip_keys));
+    let ignore_list = args.ignore_list.clone().or_else(|| {
+        let default = PathBuf::from(IGNORE_LIST_FILE);
+        default.exists().then_some(default)
+    });
+    if let Some(path) = ignore_list {
+        project.set_ignore_list(IgnoreList::load(&path)?);
+    }
+    let autocorrect_list = args.autocorrect_list.clone().or_else(|| {
+        let default = PathBuf::from(AUTOCORRECT_LIST_FILE);
+        default.exists().then_some(default)
+    });
+    if let Some(path) = autocorrect_list {
+        project.set_autocorrect(AutoCorrect::load(&path)?);
+    }
+    let project = Rc::new(RefCell::new(project));
+
     let mut dicts = HashMap::new();
-    let mut customs = HashMap::new();
-    let mut files = Vec::new();
 
     // Heuristic. Does the shell that invoked us do its own globbing?
     // Windows Powershell and CMD don't glob, and they also don't set SHELL.
     let needs_glob = std::env::var_os("SHELL").is_none();
-    let local_dict = args.local_dict.as_ref();
 
+    // Collect the files first, so that the progress can show how many there are.
+    let mut paths = Vec::new();
     for pathname in args.pathnames.iter() {
         if needs_glob {
             for entry in
                 glob(&pathname.to_string_lossy()).expect("could not understand filename pattern")
             {
                 match entry {
-                    Ok(path) => match load_file(&path, local_dict, &mut dicts, &mut customs) {
-                        Ok(file) => files.push(file),
-                        Err(err) => eprintln!("{:#}", err),
-                    },
+                    Ok(path) => paths.push(path),
                     Err(err) => eprintln!("{:#}", err),
                 }
             }
         } else {
-            match load_file(pathname, local_dict, &mut dicts, &mut customs) {
+            paths.push(pathname.clone());
+        }
+    }
+
+    // Only look for the dictionaries now. Loading one takes a while, so
+    // that's left until a file in its language is shown.
+    let mut locales = Vec::new();
+    for locale in paths
+        .iter()
+        .filter_map(|path| locale_from_filename(path).ok())
+    {
+        if !locales.contains(&locale) {
+            locales.push(locale);
+        }
+    }
+    let settings = DictionarySettings {
+        engine: args.engine,
+        local_dict: args.local_dict.clone(),
+        keyboard: args.keyboard.clone(),
+        max_word_length: args.max_word_length,
+        // Localization texts often use typographic apostrophes.
+        normalize_apostrophes: !args.no_normalize_apostrophes,
+    };
+    for locale in locales {
+        match find_dictionary(&search_path, locale) {
+            Some((dictpath, affixpath)) => {
+                let name = locale.to_string();
+                let settings = settings.clone();
+                let speller = LazySpeller::new(locale, move || {
+                    load_dictionary(&name, &dictpath, &affixpath, &settings)
+                });
+                dicts.insert(locale.to_string(), Rc::new(RefCell::new(speller)));
+            }
+            None => eprintln!(
+                "Could not load dictionary for {}: Dictionary not found",
+                locale
+            ),
+        }
+    }
+
+    let settings = CheckSettings {
+        segmentation: Segmentation::all_except(&args.no_split),
+        joins: JoinPolicies::all_except(&args.skip_joined),
+        check_comments: args.check_comments,
+        roman_numerals: !args.no_roman_numerals,
+        max_length: args.max_length.unwrap_or(0),
+        max_word_length: args.max_word_length,
+        typography: args.typography,
+    };
+
+    let mut loader = FileLoader::new(&project, dicts);
+    let mut files = Vec::new();
+    let later = if args.autofix {
+        let mut progress = Progress::new("Loading file", paths.len());
+        read_files(&paths, settings.check_comments, |path, parsed| {
+            progress.step(path.display());
+            match parsed.and_then(|parsed| loader.load(path, parsed)) {
                 Ok(file) => files.push(file),
+                Err(err) => progress.message(format!("{:#}", err)),
+            }
+            true
+        });
+        progress.finish();
+        Vec::new()
+    } else {
+        // The window only needs one file to open with. The others are
+        // read while it's shown, and the header shows how many are left.
+        let mut paths = paths.into_iter();
+        for path in paths.by_ref() {
+            let parsed = ParsedFile::read(&path, settings.check_comments);
+            match parsed.and_then(|parsed| loader.load(&path, parsed)) {
+                Ok(file) => {
+                    files.push(file);
+                    break;
+                }
                 Err(err) => eprintln!("{:#}", err),
             }
         }
-    }
+        paths.collect::<Vec<_>>()
+    };
+
     if files.is_empty() {
         bail!("No files could be spellchecked.");
     }
 
-    let data = AppState::new(Rc::new(files));
+    if args.autofix {
+        // There is no window, so the environment has only this program's keys.
+        let mut env = Env::empty();
+        set_env(&mut env, &settings);
+        let mut data = AppState::new(
+            Rc::new(files),
+            project,
+            true,
+            settings.typography,
+            Suggester::idle(),
+            MisspellingCounter::idle(),
+        );
+        autofix_files(&mut data, &env);
+        return Ok(());
+    }
+
     let main_window = WindowDesc::new(ui_builder())
         .title(|data: &AppState, _: &Env| {
             format!("{} {}", WINDOW_TITLE, data.file.filename.as_ref())
         })
         .window_size((1000.0, 500.0));
-    AppLauncher::with_window(main_window)
+    let launcher = AppLauncher::with_window(main_window);
+    let suggester = Suggester::new(launcher.get_external_handle());
+    let counter = MisspellingCounter::new(launcher.get_external_handle());
+    let mut data = AppState::new(
+        Rc::new(files),
+        project,
+        !args.no_wrap,
+        settings.typography,
+        suggester,
+        counter,
+    );
+    data.expect_files(loader, later.len());
+    let sink = launcher.get_external_handle();
+    let check_comments = settings.check_comments;
+    thread::spawn(move || {
+        read_files(&later, check_comments, |path, parsed| {
+            let read = SingleUse::new((path.to_path_buf(), parsed));
+            sink.submit_command(FILE_READ, read, Target::Auto).is_ok()
+        });
+    });
+    launcher
         .log_to_console()
-        .configure_env(|env, _| {
-            env.set(LOC_KEY_COLOR, Color::rgb8(0xff, 0xa5, 0x00));
-            env.set(WORD_COLOR, Color::rgb8(0xFF, 0xFF, 0xFF));
-            env.set(MISSPELLED_COLOR, Color::rgb8(0xFF, 0x40, 0x40));
-            env.set(CODE_COLOR, Color::rgb8(0x60, 0x60, 0xFF));
-            env.set(CUSTOM_COLOR, Color::rgb8(0x80, 0x80, 0xFF));
-            env.set(KEYWORD_COLOR, Color::rgb8(0xc0, 0xa0, 0x00));
-            env.set(ESCAPE_COLOR, Color::rgb8(0xc0, 0xa0, 0x00));
-            env.set(COMMENT_COLOR, Color::rgb8(0xc0, 0xa0, 0x50));
-            env.set(MARKUP_COLOR, Color::rgb8(0x80, 0x80, 0xc0));
-            env.set(ICON_TAG_COLOR, Color::rgb8(0xff, 0xd7, 0x00));
-            env.set(LINE_COLOR, Color::rgba8(0x80, 0x80, 0x80, 0x20));
-        })
+        .configure_env(move |env, _| set_env(env, &settings))
         .launch(data)
         .with_context(|| "Could not launch application")
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diagnostics::count_misspellings;
+
+    const CONTENTS: &str = "l_english:\n \
+                            event.1.t:0 \"Helo world\"\n \
+                            event.1.desc:0 \"The wrld is  big\"\n";
+
+    fn test_env() -> Env {
+        let mut env = Env::empty();
+        let settings = CheckSettings {
+            segmentation: Segmentation::all_except(&[]),
+            joins: JoinPolicies::all_except(&[]),
+            check_comments: false,
+            roman_numerals: true,
+            max_length: 0,
+            max_word_length: 100,
+            typography: None,
+        };
+        set_env(&mut env, &settings);
+        env
+    }
+
+    fn load_test_dictionary() -> Result<Box<dyn Speller + Send + Sync>> {
+        // wrld is a known bad form.
+        let speller = SpellerHunspellDict::from_text(
+            "6\nhello\nworld\nthe\nis\nbig\nwrld/!\n",
+            "SET UTF-8\nFORBIDDENWORD !\n",
+        )?;
+        Ok(Box::new(speller))
+    }
+
+    /// The app state for these files, with the dictionary loaded and the
+    /// lines highlighted, as when the window first shows them.
+    fn test_state(pathnames: &[PathBuf], env: &Env) -> AppState {
+        let project = Rc::new(RefCell::new(Project::default()));
+        let speller = Rc::new(RefCell::new(LazySpeller::new(
+            "en_US",
+            load_test_dictionary,
+        )));
+        let files = pathnames
+            .iter()
+            .map(|pathname| {
+                FileState::new(
+                    pathname,
+                    CONTENTS,
+                    &project,
+                    speller.clone(),
+                    Rc::new(CustomEndings::new("en_US")),
+                    Rc::new(NumberSuffixes::new("en_US")),
+                )
+            })
+            .collect();
+        let mut data = AppState::new(
+            Rc::new(files),
+            project,
+            true,
+            None,
+            Suggester::idle(),
+            MisspellingCounter::idle(),
+        );
+        data.load_file_dictionary();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(env));
+        data
+    }
+
+    fn line(data: &AppState, linenr: usize) -> &str {
+        &data.file.lines[linenr - 1].line.line
+    }
+
+    /// What happens in the widgets after a DICTIONARY_UPDATED command.
+    fn dictionary_updated(data: &mut AppState, words: &[String], env: &Env) {
+        data.file.memo.forget(words);
+        data.change_all_lines(|lineinfo| lineinfo.highlight(env));
+        if data.cursor_word().is_none() {
+            data.cursor_next();
+        }
+    }
+
+    #[test]
+    fn test_apply_suggestion() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.cursor_next();
+        assert_eq!(2, data.cursor.linenr);
+        assert_eq!(Some(&"Helo".to_string()), data.cursor_word());
+
+        data.apply_suggestion("Hello", &env);
+        assert_eq!(" event.1.t:0 \"Hello world\"", line(&data, 2));
+        // The word is fixed, so the cursor goes on to the next one.
+        assert_eq!(3, data.cursor.linenr);
+        assert_eq!(Some(&"wrld".to_string()), data.cursor_word());
+    }
+
+    #[test]
+    fn test_accept() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.accept_scope = AcceptScope::Session;
+        data.cursor_next();
+        data.start_accept();
+        assert!(data.accepting);
+        assert_eq!(
+            Some(vec!["Helo".to_string()]),
+            data.accept_cursor_word(false)
+        );
+        assert!(!data.accepting);
+        dictionary_updated(&mut data, &["Helo".to_string()], &env);
+        assert_eq!(Some(&"wrld".to_string()), data.cursor_word());
+
+        // Accepting the last word leaves the cursor on the last line.
+        assert_eq!(Some("wrld".to_string()), data.ignore_cursor_word());
+        dictionary_updated(&mut data, &["wrld".to_string()], &env);
+        assert_eq!(None, data.cursor_word());
+        assert!(data.file.speller.borrow().spellcheck("wrld"));
+        assert!(data.file.lines.iter().all(|l| l.words().next().is_none()));
+    }
+
+    #[test]
+    fn test_dictionary_failed() {
+        let env = test_env();
+        let project = Rc::new(RefCell::new(Project::default()));
+        let speller = LazySpeller::new("en_US", || bail!("No dictionary for en_US"));
+        let file = FileState::new(
+            &PathBuf::from("events_l_english.yml"),
+            CONTENTS,
+            &project,
+            Rc::new(RefCell::new(speller)),
+            Rc::new(CustomEndings::new("en_US")),
+            Rc::new(NumberSuffixes::new("en_US")),
+        );
+        let mut data = AppState::new(
+            Rc::new(vec![file]),
+            project,
+            true,
+            None,
+            Suggester::idle(),
+            MisspellingCounter::idle(),
+        );
+        data.load_file_dictionary();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(&env));
+        assert_eq!(
+            Some("No dictionary for en_US"),
+            data.file.speller.borrow().error()
+        );
+        // No word is flagged, but the file wasn't really checked.
+        assert!(data.file.lines.iter().all(|l| l.is_clean()));
+        assert!(!data.file.is_clean());
+    }
+
+    #[test]
+    fn test_accept_project() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        let loaded = Rc::new(RefCell::new(LazySpeller::new(
+            "en_GB",
+            load_test_dictionary,
+        )));
+        loaded.borrow_mut().load(&[]).unwrap();
+        let unloaded = Rc::new(RefCell::new(LazySpeller::new(
+            "en_AU",
+            load_test_dictionary,
+        )));
+        for (pathname, speller) in [
+            ("events_l_english_gb.yml", &loaded),
+            ("events_l_english_au.yml", &unloaded),
+        ] {
+            let file = FileState::new(
+                &PathBuf::from(pathname),
+                CONTENTS,
+                &data.project,
+                Rc::clone(speller),
+                Rc::new(CustomEndings::new("en_US")),
+                Rc::new(NumberSuffixes::new("en_US")),
+            );
+            Rc::make_mut(&mut data.files).push(file);
+        }
+
+        data.accept_scope = AcceptScope::Project;
+        assert!(data.accept_word("Helo", None).unwrap());
+        assert!(loaded.borrow().spellcheck("Helo"));
+        // The dictionary that wasn't loaded yet gets it from the project.
+        let accepted = data.project.borrow().accepted().to_vec();
+        unloaded.borrow_mut().load(&accepted).unwrap();
+        assert!(unloaded.borrow().spellcheck("Helo"));
+    }
+
+    #[test]
+    fn test_issue_list() {
+        let env = test_env();
+        let pathnames = [
+            PathBuf::from("a_l_english.yml"),
+            PathBuf::from("b_l_english.yml"),
+        ];
+        let mut data = test_state(&pathnames, &env);
+        // Only the first file has been checked so far.
+        assert_eq!(3, data.issue_list.len());
+        data.file_next();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(&env));
+        assert_eq!(6, data.issue_list.len());
+        data.issue_filter = Severity::Error;
+        data.update_issue_list();
+        assert_eq!(4, data.issue_list.len());
+
+        // Picking an issue of the first file goes back to it.
+        let found = data.issue_list[0].clone();
+        data.goto_issue(&found);
+        assert_eq!(0, data.file_idx);
+        assert_eq!(Some(&"Helo".to_string()), data.cursor_word());
+        data.apply_suggestion("Hello", &env);
+        assert_eq!(3, data.issue_list.len());
+    }
+
+    #[test]
+    fn test_accept_discouraged() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.accept_scope = AcceptScope::Session;
+        data.goto_line(3);
+        data.start_accept();
+        assert_eq!(None, data.accept_cursor_word(false));
+        assert!(data.pending_accept.is_some());
+        assert!(data.accepting);
+        assert!(!data.file.speller.borrow().spellcheck("wrld"));
+
+        assert_eq!(Some(vec!["wrld".to_string()]), data.confirm_accept());
+        assert!(data.pending_accept.is_none());
+        assert!(!data.accepting);
+        assert!(data.file.speller.borrow().spellcheck("wrld"));
+    }
+
+    #[test]
+    fn test_ignore_at_key() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.goto_line(3);
+        assert_eq!(Some("wrld".to_string()), data.ignore_at_key());
+        let ignore = Ignore {
+            file: "events_l_english.yml".to_string(),
+            key: "event.1.desc".to_string(),
+            word: "wrld".to_string(),
+        };
+        assert_eq!(vec![ignore.clone()], *data.ignores);
+        data.remove_ignore(&ignore);
+        assert!(data.ignores.is_empty());
+    }
+
+    #[test]
+    fn test_edit_line() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.goto_line(3);
+        data.start_edit();
+        assert_eq!(3, data.editing_linenr);
+        assert_eq!(line(&data, 3), *data.editing_text);
+        data.editing_text = Arc::new(" event.1.desc:0 \"The world is big\"".to_string());
+        data.apply_edit(&env);
+        assert_eq!(0, data.editing_linenr);
+        assert_eq!(" event.1.desc:0 \"The world is big\"", line(&data, 3));
+        assert!(data.file.lines[2].issues.is_empty());
+    }
+
+    #[test]
+    fn test_fix_whitespace() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.cursor_filter = IssueFilter::All;
+        let issues = Arc::clone(&data.issue_list);
+        let found = issues
+            .iter()
+            .find(|found| found.issue.category == Category::Whitespace)
+            .unwrap();
+        data.goto_issue(found);
+        assert_eq!(3, data.cursor.linenr);
+        assert_eq!(
+            Some(Category::Whitespace),
+            data.cursor_issue().map(|issue| issue.category)
+        );
+
+        data.fix_cursor_whitespace(&env);
+        assert_eq!(" event.1.desc:0 \"The wrld is big\"", line(&data, 3));
+        assert_eq!(Some(&"wrld".to_string()), data.cursor_word());
+    }
+
+    #[test]
+    fn test_mixed_script() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        // The o of "world" is Cyrillic.
+        let mixed = Rc::new(" event.1.t:0 \"Hello w\u{43e}rld\"".to_string());
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&mixed);
+            lineinfo.highlight(&env);
+        });
+        data.goto_line(2);
+        assert_eq!(
+            Some(Category::MixedScript),
+            data.cursor_issue().map(|issue| issue.category)
+        );
+        assert_eq!("world", *data.suggestions[0].suggestion);
+
+        data.apply_suggestion("world", &env);
+        assert_eq!(" event.1.t:0 \"Hello world\"", line(&data, 2));
+        // It's not a spelling correction, so it isn't remembered.
+        assert_eq!(None, data.project.borrow().correction("w\u{43e}rld"));
+
+        // A mixed word that was accepted on purpose isn't flagged.
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&mixed);
+            lineinfo.highlight(&env);
+        });
+        let word = "w\u{43e}rld".to_string();
+        data.file.speller.borrow_mut().add_word(&word);
+        dictionary_updated(&mut data, &[word], &env);
+        assert!(data.file.lines[1].issues.is_empty());
+    }
+
+    #[test]
+    fn test_long_word() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        let blob = Rc::new(format!(" event.1.t:0 \"Hello {}\"", "aGVsbG8".repeat(20)));
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&blob);
+            lineinfo.highlight(&env);
+        });
+        let issues = &data.file.lines[1].issues;
+        assert_eq!(1, issues.len());
+        assert_eq!(Category::LongWord, issues[0].category);
+        assert_eq!(0, data.file.lines[1].words().count());
+
+        // Words joined to code are measured whole.
+        let joined = Rc::new(format!(
+            " event.1.t:0 \"{}-[X]-aGVsbG8\"",
+            "aGVsbG8".repeat(20)
+        ));
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&joined);
+            lineinfo.highlight(&env);
+        });
+        let issues = &data.file.lines[1].issues;
+        assert_eq!(1, issues.len());
+        assert_eq!(Category::LongWord, issues[0].category);
+
+        // A maximum of 0 means there is no limit.
+        let mut env = env.clone();
+        env.set(MAX_WORD_LENGTH, 0);
+        let long = Rc::new(format!(" event.1.t:0 \"Hello {}\"", "x".repeat(120)));
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&long);
+            lineinfo.highlight(&env);
+        });
+        let issues = &data.file.lines[1].issues;
+        assert!(issues
+            .iter()
+            .all(|issue| issue.category != Category::LongWord));
+    }
+
+    #[test]
+    fn test_joined_word() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        let text = Rc::new(" event.1.t:0 \"pre-[GetEra] big-[X]-wrld\"".to_string());
+        data.change_line(2, |lineinfo| {
+            lineinfo.line.line = Rc::clone(&text);
+            lineinfo.highlight(&env);
+        });
+        let words: Vec<&str> = data.file.lines[1]
+            .words()
+            .map(|issue| issue.text.as_str())
+            .collect();
+        assert_eq!(vec!["pre-", "wrld"], words);
+
+        // A prefix can be accepted with its hyphen.
+        data.file.speller.borrow_mut().add_word("pre-");
+        data.file.memo.clear();
+        data.change_line(2, |lineinfo| lineinfo.highlight(&env));
+        assert_eq!(1, data.file.lines[1].words().count());
+    }
+
+    #[test]
+    fn test_suggestions_found() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.cursor_next();
+        let id = data.suggestions_id;
+        assert!(id > 0);
+        // Suggestions for a word that the cursor has left are dropped.
+        data.cursor_next();
+        data.suggestions_found(id, &Suggestions::Found(vec!["Hello".to_string()]));
+        assert!(data.suggestions.is_empty());
+        let id = data.suggestions_id;
+        data.suggestions_found(id, &Suggestions::Found(vec!["world".to_string()]));
+        assert_eq!("world", *data.suggestions[0].suggestion);
+        data.suggestions_found(id, &Suggestions::NoCandidates);
+        assert_eq!(1, data.suggestions.len());
+    }
+
+    #[test]
+    fn test_model_found() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        data.cursor_next();
+        let id = data.suggestions_id;
+        data.start_accept();
+        assert_eq!("", *data.accept_model);
+        // The model arrives after the panel was opened.
+        data.model_found(id, Some("Hello"));
+        assert_eq!("Hello", *data.accept_model);
+        data.accepting = false;
+        data.start_accept();
+        assert_eq!("Hello", *data.accept_model);
+        // A model for a word that the cursor has left is dropped.
+        data.cursor_next();
+        data.model_found(id, Some("Help"));
+        assert_eq!("", *data.suggested_model);
+    }
+
+    #[test]
+    fn test_misspellings() {
+        let env = test_env();
+        let mut data = test_state(
+            &[
+                PathBuf::from("events_l_english.yml"),
+                PathBuf::from("other_l_english.yml"),
+            ],
+            &env,
+        );
+        // Only the files checked so far are counted.
+        data.file_next();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(&env));
+        data.file_prev();
+        data.showing_misspellings = true;
+        data.update_misspellings();
+        let id = data.misspellings_id;
+        let issues: Vec<Issue> = data
+            .files
+            .iter()
+            .flat_map(|file| file.issues.iter())
+            .map(|found| found.issue.clone())
+            .collect();
+        data.misspellings_counted(id, &Arc::new(count_misspellings(issues.iter())));
+        let counts: Vec<(&str, usize)> = data
+            .misspellings
+            .iter()
+            .map(|m| (m.word.as_str(), m.count))
+            .collect();
+        assert_eq!(vec![("Helo", 2), ("wrld", 2)], counts);
+        assert_eq!(0, data.file_idx);
+        // A count that was asked for before the lines changed is dropped.
+        data.goto_line(2);
+        data.apply_suggestion("Hello", &env);
+        data.misspellings_counted(id, &Arc::new(Vec::new()));
+        assert_eq!(2, data.misspellings.len());
+        assert_ne!(id, data.misspellings_id);
+
+        // Going through the occurrences goes on to the next file, and
+        // then back round to the first.
+        data.goto_misspelling("wrld");
+        assert_eq!((0, 3), (data.file_idx, data.cursor.linenr));
+        data.goto_misspelling("wrld");
+        assert_eq!((1, 3), (data.file_idx, data.cursor.linenr));
+        assert_eq!(Some(&"wrld".to_string()), data.cursor_word());
+        data.goto_misspelling("wrld");
+        assert_eq!((0, 3), (data.file_idx, data.cursor.linenr));
+    }
+
+    #[test]
+    fn test_autocorrect() {
+        let env = test_env();
+        let mut data = test_state(&[PathBuf::from("events_l_english.yml")], &env);
+        let autocorrect = AutoCorrect::parse("helo hello\nwrld world\n").unwrap();
+        data.project.borrow_mut().set_autocorrect(autocorrect);
+
+        data.goto_line(2);
+        assert_eq!("Hello", *data.suggestions[0].suggestion);
+        let id = data.suggestions_id;
+        let found = vec!["Hell".to_string(), "Hello".to_string()];
+        data.suggestions_found(id, &Suggestions::Found(found));
+        assert_eq!(2, data.suggestions.len());
+        assert_eq!("Hell", *data.suggestions[1].suggestion);
+
+        let fixes = data.autofix_file(&env);
+        let fixes: Vec<(usize, &str, &str)> = fixes
+            .iter()
+            .map(|f| (f.line_nr, f.word.as_str(), f.fix.as_str()))
+            .collect();
+        assert_eq!(vec![(2, "Helo", "Hello"), (3, "wrld", "world")], fixes);
+        assert_eq!(" event.1.t:0 \"Hello world\"", line(&data, 2));
+        assert_eq!(" event.1.desc:0 \"The world is  big\"", line(&data, 3));
+        assert!(data.file.lines.iter().all(|l| l.words().next().is_none()));
+    }
+
+    #[test]
+    fn test_apply_known_fixes() {
+        let env = test_env();
+        let mut data = test_state(
+            &[
+                PathBuf::from("events_l_english.yml"),
+                PathBuf::from("other_l_english.yml"),
+            ],
+            &env,
+        );
+        data.goto_line(2);
+        data.apply_suggestion("Hello", &env);
+        data.apply_known_fixes(&env);
+        // Only the other file still had the misspelling.
+        assert_eq!(1, data.applied_fixes.len());
+        let fix = data.applied_fixes[0].clone();
+        assert_eq!(PathBuf::from("other_l_english.yml"), *fix.pathname);
+        assert_eq!(2, fix.line_nr);
+        assert_eq!(0, data.file_idx);
+        assert_eq!(" event.1.t:0 \"Hello world\"", line(&data, 2));
+
+        data.goto_fix(&fix);
+        assert_eq!(1, data.file_idx);
+        assert_eq!(" event.1.t:0 \"Hello world\"", line(&data, 2));
+    }
+
+    #[test]
+    fn test_remembered_correction() {
+        let env = test_env();
+        let mut data = test_state(
+            &[
+                PathBuf::from("events_l_english.yml"),
+                PathBuf::from("other_l_english.yml"),
+            ],
+            &env,
+        );
+        data.goto_line(2);
+        data.apply_suggestion("Hello", &env);
+        assert_eq!(Some("Hello"), data.project.borrow().correction("Helo"));
+
+        // The same misspelling in another file gets the same fix first.
+        data.file_next();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(&env));
+        data.goto_line(2);
+        assert_eq!(Some(&"Helo".to_string()), data.cursor_word());
+        assert_eq!("Hello", *data.suggestions[0].suggestion);
+        let id = data.suggestions_id;
+        let found = vec!["Hell".to_string(), "Hello".to_string()];
+        data.suggestions_found(id, &Suggestions::Found(found));
+        let suggestions: Vec<&str> = data
+            .suggestions
+            .iter()
+            .map(|s| s.suggestion.as_str())
+            .collect();
+        assert_eq!(vec!["Hello", "Hell"], suggestions);
+    }
+
+    #[test]
+    fn test_save_and_close() {
+        let env = test_env();
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!(
+            "ck3spell_test_{}_first_l_english.yml",
+            std::process::id()
+        ));
+        let second = dir.join(format!(
+            "ck3spell_test_{}_second_l_english.yml",
+            std::process::id()
+        ));
+        let mut data = test_state(&[first.clone(), second.clone()], &env);
+        data.file_next();
+        data.change_all_lines(|lineinfo| lineinfo.highlight(&env));
+        data.goto_line(2);
+        data.apply_suggestion("Hello", &env);
+
+        // Closing the last file shows the one before it.
+        assert!(data.save_and_close());
+        assert_eq!(1, data.files.len());
+        assert_eq!(0, data.file_idx);
+        assert_eq!(first, *data.file.pathname);
+        let saved = std::fs::read_to_string(&second).unwrap();
+        assert_eq!(
+            format!("\u{FEFF}{}", CONTENTS.replace("Helo", "Hello")),
+            saved
+        );
+
+        // The app quits instead of closing the only file.
+        assert!(!data.save_and_close());
+        assert_eq!(1, data.files.len());
+        assert!(first.exists());
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn test_keyboard_keys() {
+        assert_eq!(
+            Ok("azertyuiop|qsdfghjklmù|wxcvbn"),
+            keyboard_keys("AZERTY").as_deref()
+        );
+        assert_eq!(Ok("abc|def"), keyboard_keys("abc|def").as_deref());
+        assert!(keyboard_keys("dvorak").is_err());
+    }
+}
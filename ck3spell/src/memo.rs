@@ -0,0 +1,65 @@
+use fnv::FnvHashMap;
+use std::cell::RefCell;
+
+use crate::syntaxhighlighter::contains_word;
+
+/// Remembers which words of a file passed the spellcheck. Lines are
+/// highlighted again every time the cursor moves past them, and without
+/// this every word of a long line would be looked up in the dictionary
+/// again each time.
+#[derive(Debug, Default)]
+pub struct SpellMemo {
+    known: RefCell<FnvHashMap<String, bool>>,
+}
+
+impl SpellMemo {
+    /// Returns the remembered result for `word`, or asks `spellcheck`
+    /// and remembers its answer.
+    pub fn check(&self, word: &str, spellcheck: impl FnOnce(&str) -> bool) -> bool {
+        if let Some(&good) = self.known.borrow().get(word) {
+            return good;
+        }
+        let good = spellcheck(word);
+        self.known.borrow_mut().insert(word.to_string(), good);
+        good
+    }
+
+    /// Forget the results for `words` after they were added to the
+    /// dictionary, including for their other capitalizations and for
+    /// the words that contain them.
+    /// Adding words can only make words good, so the good ones are kept.
+    pub fn forget(&self, words: &[String]) {
+        self.known
+            .borrow_mut()
+            .retain(|known, good| *good || !words.iter().any(|word| contains_word(known, word)));
+    }
+
+    pub fn clear(&self) {
+        self.known.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memo() {
+        let memo = SpellMemo::default();
+        assert!(!memo.check("Valyrian", |_| false));
+        assert!(!memo.check("Valyrian-born", |_| false));
+        assert!(!memo.check("Targaryen", |_| false));
+        // The remembered result is used without asking again.
+        assert!(!memo.check("Valyrian", |_| panic!()));
+        assert!(memo.check("dragon", |_| true));
+
+        memo.forget(&["valyrian".to_string()]);
+        assert!(memo.check("Valyrian", |_| true));
+        assert!(memo.check("Valyrian-born", |_| true));
+        assert!(!memo.check("Targaryen", |_| panic!()));
+        assert!(memo.check("dragon", |_| panic!()));
+
+        memo.clear();
+        assert!(!memo.check("dragon", |_| false));
+    }
+}
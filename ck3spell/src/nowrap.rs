@@ -0,0 +1,132 @@
+use druid::widget::prelude::*;
+use druid::widget::Label;
+use druid::{theme, Color, Point, WidgetPod};
+
+use crate::commands::CURSOR_CHANGED;
+use crate::LineInfo;
+
+/// Shows a line without wrapping it. If the line is too long to fit,
+/// it is cut off with an ellipsis and can be scrolled sideways with a
+/// horizontal scroll wheel or with shift and the normal scroll wheel.
+/// This keeps every line one row high, so that the line list doesn't
+/// jump around when lines are edited.
+pub struct NoWrapLine<W> {
+    child: WidgetPod<LineInfo, W>,
+    ellipsis: WidgetPod<LineInfo, Label<LineInfo>>,
+    offset: f64,
+    child_width: f64,
+    width: f64,
+}
+
+impl<W: Widget<LineInfo>> NoWrapLine<W> {
+    pub fn new(child: W) -> NoWrapLine<W> {
+        NoWrapLine {
+            child: WidgetPod::new(child),
+            // '\u{2026}' is the ellipsis
+            ellipsis: WidgetPod::new(Label::new("\u{2026}").with_text_color(Color::grey8(160))),
+            offset: 0.0,
+            child_width: 0.0,
+            width: 0.0,
+        }
+    }
+
+    fn max_offset(&self) -> f64 {
+        (self.child_width - self.width).max(0.0)
+    }
+
+    /// Scroll so that the marked word is in view. The position of the
+    /// word is estimated from its place in the line, because the label
+    /// doesn't tell where its characters are.
+    fn show_marked_word(&mut self, data: &LineInfo) {
+        let Some(range) = data.marked_range() else {
+            return;
+        };
+        let line = &data.line.line;
+        let total = line.chars().count().max(1) as f64;
+        let start = line[..range.start].chars().count() as f64 / total * self.child_width;
+        let end = line[..range.end].chars().count() as f64 / total * self.child_width;
+        let visible = self.width - self.ellipsis.layout_rect().width();
+        if start < self.offset || end > self.offset + visible {
+            self.offset = (start - visible / 3.0).clamp(0.0, self.max_offset());
+        }
+    }
+}
+
+impl<W: Widget<LineInfo>> Widget<LineInfo> for NoWrapLine<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut LineInfo, env: &Env) {
+        self.child.event(ctx, event, data, env);
+        if ctx.is_handled() {
+            return;
+        }
+        if let Event::Wheel(mouse) = event {
+            let delta = if mouse.wheel_delta.x != 0.0 {
+                mouse.wheel_delta.x
+            } else if mouse.mods.shift() {
+                mouse.wheel_delta.y
+            } else {
+                0.0
+            };
+            if delta != 0.0 && self.max_offset() > 0.0 {
+                self.offset = (self.offset + delta).clamp(0.0, self.max_offset());
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+        } else if let Event::Command(command) = event {
+            if let Some(cursor) = command.get(CURSOR_CHANGED) {
+                if cursor.linenr == data.line.line_nr {
+                    self.show_marked_word(data);
+                    ctx.request_layout();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &LineInfo, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+        self.ellipsis.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &LineInfo, data: &LineInfo, env: &Env) {
+        self.child.update(ctx, data, env);
+        self.ellipsis.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LineInfo,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("NoWrapLine");
+        let child_bc = BoxConstraints::new(
+            Size::new(0.0, bc.min().height),
+            Size::new(f64::INFINITY, bc.max().height),
+        );
+        let child_size = self.child.layout(ctx, &child_bc, data, env);
+        let ellipsis_size = self.ellipsis.layout(ctx, &child_bc, data, env);
+        let size = bc.constrain(child_size);
+
+        self.child_width = child_size.width;
+        self.width = size.width;
+        self.offset = self.offset.min(self.max_offset());
+        self.child.set_origin(ctx, Point::new(-self.offset, 0.0));
+        self.ellipsis
+            .set_origin(ctx, Point::new(size.width - ellipsis_size.width, 0.0));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LineInfo, env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.with_save(|ctx| {
+            ctx.clip(rect);
+            self.child.paint(ctx, data, env);
+        });
+        if self.offset < self.max_offset() {
+            // Blank out the text under the ellipsis so it can be read.
+            let background = env.get(theme::WINDOW_BACKGROUND_COLOR);
+            ctx.fill(self.ellipsis.layout_rect(), &background);
+            self.ellipsis.paint(ctx, data, env);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use fnv::FnvHashSet;
+
+/// Suffixes that may be written directly after a number, such as the
+/// ordinal in "1er" or the unit in "10km", and abbreviations that go
+/// before a number, such as "Nº". The dictionaries don't know about
+/// these, so they are checked here before asking the speller.
+#[derive(Debug)]
+pub struct NumberSuffixes {
+    ordinals: FnvHashSet<&'static str>,
+    units: FnvHashSet<&'static str>,
+    abbreviations: FnvHashSet<&'static str>,
+    /// Whether the language writes numbers as Roman numerals.
+    roman: bool,
+}
+
+const UNITS: &str = include_str!("../assets/units.txt");
+const ORDINALS_DE: &str = include_str!("../assets/ordinals_DE.txt");
+const ORDINALS_ES: &str = include_str!("../assets/ordinals_ES.txt");
+const ORDINALS_FR: &str = include_str!("../assets/ordinals_FR.txt");
+const ORDINALS_PT: &str = include_str!("../assets/ordinals_PT.txt");
+const ORDINALS_RU: &str = include_str!("../assets/ordinals_RU.txt");
+const ABBREVIATIONS_ES: &str = include_str!("../assets/number_abbreviations_ES.txt");
+const ABBREVIATIONS_FR: &str = include_str!("../assets/number_abbreviations_FR.txt");
+const ABBREVIATIONS_PT: &str = include_str!("../assets/number_abbreviations_PT.txt");
+
+const ROMAN_DIGITS: &str = "IVXLCDM";
+
+/// The languages that write regnal numbers and centuries with Roman
+/// numerals.
+const ROMAN_LANGUAGES: [&str; 6] = ["de", "en", "es", "fr", "pt", "ru"];
+
+/// The biggest Roman numeral that is taken as a number. Regnal numbers
+/// and centuries stay well below it, while bigger ones are more often
+/// words or code, such as "MIX" or "DIV", than years.
+const MAX_ROMAN: u32 = 100;
+
+impl NumberSuffixes {
+    pub fn new(locale: &str) -> Self {
+        let mut new = NumberSuffixes {
+            ordinals: FnvHashSet::default(),
+            units: UNITS.lines().collect(),
+            abbreviations: FnvHashSet::default(),
+            roman: false,
+        };
+        let ordinals = match locale {
+            "de_DE" => ORDINALS_DE,
+            "es_ES" => ORDINALS_ES,
+            "fr_FR" => ORDINALS_FR,
+            "pt_BR" => ORDINALS_PT,
+            "ru_RU" => ORDINALS_RU,
+            _ => "",
+        };
+        new.ordinals.extend(ordinals.lines());
+        let abbreviations = match locale {
+            "es_ES" => ABBREVIATIONS_ES,
+            "fr_FR" => ABBREVIATIONS_FR,
+            "pt_BR" => ABBREVIATIONS_PT,
+            _ => "",
+        };
+        new.abbreviations.extend(abbreviations.lines());
+        let language = locale.split('_').next().unwrap_or_default();
+        new.roman = ROMAN_LANGUAGES.contains(&language);
+        new
+    }
+
+    /// Returns true if `word` is a Roman numeral that is taken as a number
+    /// in this language, such as "XIV" but not "MIX".
+    pub fn is_roman_numeral(&self, word: &str) -> bool {
+        self.roman && roman_numeral_value(word).is_some_and(|value| value <= MAX_ROMAN)
+    }
+
+    /// Returns true if `word` is a number followed by a known ordinal or
+    /// unit suffix, or a known abbreviation for "number". Ordinals may
+    /// also follow Roman numerals, as in "XIVe".
+    pub fn check(&self, word: &str) -> bool {
+        if self.abbreviations.contains(word) {
+            return true;
+        }
+        let split = word.find(|c: char| !c.is_numeric()).unwrap_or(word.len());
+        if split > 0 {
+            let suffix = &word[split..];
+            return self.ordinals.contains(suffix) || self.units.contains(suffix);
+        }
+        let split = word
+            .find(|c: char| !ROMAN_DIGITS.contains(c))
+            .unwrap_or(word.len());
+        self.is_roman_numeral(&word[..split]) && self.ordinals.contains(&word[split..])
+    }
+}
+
+/// Returns the value of `word` if it is a Roman numeral in capitals,
+/// written the usual way, such as "XIV" but not "IIII" or "IC".
+fn roman_numeral_value(word: &str) -> Option<u32> {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let value_of = |c: char| {
+        NUMERALS
+            .iter()
+            .find(|(_, numeral)| numeral.len() == 1 && numeral.starts_with(c))
+            .map(|&(value, _)| value)
+    };
+    let values = word.chars().map(value_of).collect::<Option<Vec<u32>>>()?;
+    // A letter before a bigger one is taken away from it, as in "IV".
+    let mut number = 0;
+    for (i, &value) in values.iter().enumerate() {
+        if values.get(i + 1).is_some_and(|&next| next > value) {
+            number -= value as i64;
+        } else {
+            number += value as i64;
+        }
+    }
+    if !(1..4000).contains(&number) {
+        return None;
+    }
+    // Only accept the numeral if that's how the number is written.
+    let mut written = String::new();
+    let mut rest = number as u32;
+    for (value, numeral) in NUMERALS {
+        while rest >= value {
+            written.push_str(numeral);
+            rest -= value;
+        }
+    }
+    (written == word).then_some(number as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_fr() {
+        let numbers = NumberSuffixes::new("fr_FR");
+        assert!(numbers.check("1er"));
+        assert!(numbers.check("1re"));
+        assert!(numbers.check("2e"));
+        assert!(numbers.check("XIVe"));
+        assert!(numbers.check("10km"));
+        assert!(!numbers.check("1st"));
+        assert!(!numbers.check("XIVth"));
+        assert!(!numbers.check("Xkm"));
+        assert!(!numbers.check("e"));
+        assert!(!numbers.check("IIIIe"));
+        assert!(numbers.check("nº"));
+        assert!(numbers.check("Nº"));
+    }
+
+    #[test]
+    fn test_check_en() {
+        let numbers = NumberSuffixes::new("en_US");
+        assert!(numbers.check("10km"));
+        assert!(numbers.check("5kg"));
+        assert!(!numbers.check("1er"));
+        assert!(!numbers.check("Nº"));
+    }
+
+    #[test]
+    fn test_roman_numeral_value() {
+        for (word, value) in [("XIV", 14), ("MCMXCIX", 1999), ("XL", 40), ("DIV", 504)] {
+            assert_eq!(Some(value), roman_numeral_value(word), "{}", word);
+        }
+        assert_eq!(Some(3999), roman_numeral_value("MMMCMXCIX"));
+        for word in ["", "IIII", "IC", "XCX", "VX", "MMMM", "MID", "xiv"] {
+            assert_eq!(None, roman_numeral_value(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_is_roman_numeral() {
+        let numbers = NumberSuffixes::new("en_GB");
+        for word in ["XIV", "IV", "XL", "XXI", "C"] {
+            assert!(numbers.is_roman_numeral(word), "{}", word);
+        }
+        for word in ["MIX", "DIV", "CIV", "MCMXCIX", "IIII"] {
+            assert!(!numbers.is_roman_numeral(word), "{}", word);
+        }
+        assert!(!NumberSuffixes::new("ko_KR").is_roman_numeral("XIV"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::Display;
+use std::io::{stderr, IsTerminal};
+
+/// Shows on the console how far along the loading is at startup, so that
+/// ck3spell doesn't look frozen while it loads hundreds of files.
+/// On a terminal each step overwrites the previous one, otherwise each
+/// step gets its own line.
+pub struct Progress {
+    what: &'static str,
+    total: usize,
+    count: usize,
+    terminal: bool,
+    // Length of the step that is currently shown on a terminal.
+    shown: usize,
+}
+
+impl Progress {
+    pub fn new(what: &'static str, total: usize) -> Self {
+        Progress {
+            what,
+            total,
+            count: 0,
+            terminal: stderr().is_terminal(),
+            shown: 0,
+        }
+    }
+
+    /// Report that work on `item` has started.
+    pub fn step(&mut self, item: impl Display) {
+        self.count += 1;
+        let status = format!("{} {}/{}: {}", self.what, self.count, self.total, item);
+        if self.terminal {
+            self.clear();
+            eprint!("{}", status);
+            self.shown = status.chars().count();
+        } else {
+            eprintln!("{}", status);
+        }
+    }
+
+    /// Print a message, such as an error, about the current step.
+    /// The step stays on the screen so that it's clear what the message
+    /// is about.
+    pub fn message(&mut self, message: impl Display) {
+        if self.shown > 0 {
+            eprintln!();
+            self.shown = 0;
+        }
+        eprintln!("{}", message);
+    }
+
+    pub fn finish(&mut self) {
+        self.clear();
+    }
+
+    // Blank out the step shown on the terminal. This uses spaces rather
+    // than an escape code because not every Windows console knows them.
+    fn clear(&mut self) {
+        if self.shown > 0 {
+            eprint!("\r{:width$}\r", "", width = self.shown);
+            self.shown = 0;
+        }
+    }
+}
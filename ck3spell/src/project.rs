@@ -0,0 +1,349 @@
+use anyhow::{bail, Context, Result};
+use druid::Data;
+use regex::Regex;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::autocorrect::AutoCorrect;
+use crate::ignorelist::IgnoreList;
+use crate::keyfilter::KeyFilter;
+use crate::suppress::Suppression;
+
+const HEADER: &str = "# ck3spell project file";
+
+/// A word that should not be flagged in the text of one loc key,
+/// such as a deliberately archaic spelling in a single event.
+#[derive(Clone, Data, Debug, PartialEq, Eq)]
+pub struct Ignore {
+    /// Name of the loc file, without its directory, so that the project
+    /// file works wherever the mod is checked out.
+    pub file: String,
+    /// The loc key, without the `:0` version number.
+    pub key: String,
+    pub word: String,
+}
+
+/// A word that the project accepts everywhere, optionally with the
+/// endings of a dictionary word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AcceptedWord {
+    pub word: String,
+    pub model: Option<String>,
+}
+
+/// A misspelling and the suggestion that was chosen to fix it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Correction {
+    pub word: String,
+    pub fix: String,
+}
+
+/// Settings that belong to a mod rather than to the user, kept in a file
+/// that can be committed together with the mod.
+/// Each line of the file is a setting with tab-separated fields:
+/// `accept <word>`, `accept <word> <model>`, `accept-pattern <regex>`,
+/// `ignore <file> <key> <word>`, or `correct <word> <fix>`.
+#[derive(Debug, Default)]
+pub struct Project {
+    /// Where to save the project. If this is None then changes only last
+    /// for this session.
+    path: Option<PathBuf>,
+    accepted: Vec<AcceptedWord>,
+    /// Words that match one of these are always spelled right. They are
+    /// compiled once, when the project is loaded.
+    accept_patterns: Vec<Regex>,
+    ignores: Vec<Ignore>,
+    corrections: Vec<Correction>,
+    /// Which loc keys to check. This comes from the command line, so it
+    /// isn't saved in the project file.
+    key_filter: KeyFilter,
+    /// Words that are never flagged. These are kept in their own file.
+    ignore_list: IgnoreList,
+    /// Misspellings that are always fixed the same way. These are kept
+    /// in their own file.
+    autocorrect: AutoCorrect,
+}
+
+impl Project {
+    /// Load the project file at `path`. A file that doesn't exist yet
+    /// counts as an empty project; it will be created when it's changed.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut project = Project {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(project);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read project file {}", path.display()))?;
+        project
+            .parse(&contents)
+            .with_context(|| format!("Could not load project file {}", path.display()))?;
+        Ok(project)
+    }
+
+    fn parse(&mut self, contents: &str) -> Result<()> {
+        for (nr, line) in contents.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["accept", word] => self.accepted.push(AcceptedWord {
+                    word: word.to_string(),
+                    model: None,
+                }),
+                ["accept", word, model] => self.accepted.push(AcceptedWord {
+                    word: word.to_string(),
+                    model: Some(model.to_string()),
+                }),
+                ["accept-pattern", pattern] => match Regex::new(pattern) {
+                    Ok(re) => self.accept_patterns.push(re),
+                    Err(err) => bail!("line {}: {}", nr + 1, err),
+                },
+                ["ignore", file, key, word] => self.ignores.push(Ignore {
+                    file: file.to_string(),
+                    key: key.to_string(),
+                    word: word.to_string(),
+                }),
+                ["correct", word, fix] => self.corrections.push(Correction {
+                    word: word.to_string(),
+                    fix: fix.to_string(),
+                }),
+                _ => bail!("line {}: could not understand {}", nr + 1, line),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file =
+            File::create(path).with_context(|| format!("Could not write to {}", path.display()))?;
+        writeln!(file, "{}", HEADER)?;
+        for accepted in &self.accepted {
+            match &accepted.model {
+                Some(model) => writeln!(file, "accept\t{}\t{}", accepted.word, model)?,
+                None => writeln!(file, "accept\t{}", accepted.word)?,
+            }
+        }
+        for pattern in &self.accept_patterns {
+            writeln!(file, "accept-pattern\t{}", pattern.as_str())?;
+        }
+        for ignore in &self.ignores {
+            writeln!(
+                file,
+                "ignore\t{}\t{}\t{}",
+                ignore.file, ignore.key, ignore.word
+            )?;
+        }
+        for correction in &self.corrections {
+            writeln!(file, "correct\t{}\t{}", correction.word, correction.fix)?;
+        }
+        Ok(())
+    }
+
+    pub fn accepted(&self) -> &[AcceptedWord] {
+        &self.accepted
+    }
+
+    pub fn add_accepted(&mut self, word: &str, model: Option<&str>) {
+        let accepted = AcceptedWord {
+            word: word.to_string(),
+            model: model.map(str::to_string),
+        };
+        if !self.accepted.contains(&accepted) {
+            self.accepted.push(accepted);
+        }
+    }
+
+    pub fn ignores(&self) -> &[Ignore] {
+        &self.ignores
+    }
+
+    /// Returns false if the word was already ignored there.
+    pub fn add_ignore(&mut self, ignore: Ignore) -> bool {
+        if self.ignores.contains(&ignore) {
+            return false;
+        }
+        self.ignores.push(ignore);
+        true
+    }
+
+    pub fn remove_ignore(&mut self, ignore: &Ignore) {
+        self.ignores.retain(|i| i != ignore);
+    }
+
+    /// Returns the fix that was chosen the last time `word` was corrected.
+    pub fn correction(&self, word: &str) -> Option<&str> {
+        self.corrections
+            .iter()
+            .find(|c| c.word == word)
+            .map(|c| c.fix.as_str())
+    }
+
+    /// Remember that `word` was corrected to `fix`, instead of any fix
+    /// that was chosen for it before. Returns false if nothing changed.
+    pub fn add_correction(&mut self, word: &str, fix: &str) -> bool {
+        // A line break or tab would garble the project file.
+        if word == fix || format!("{}{}", word, fix).contains(['\n', '\r', '\t']) {
+            return false;
+        }
+        match self.corrections.iter_mut().find(|c| c.word == word) {
+            Some(c) if c.fix == fix => false,
+            Some(c) => {
+                c.fix = fix.to_string();
+                true
+            }
+            None => {
+                self.corrections.push(Correction {
+                    word: word.to_string(),
+                    fix: fix.to_string(),
+                });
+                true
+            }
+        }
+    }
+
+    pub fn set_key_filter(&mut self, key_filter: KeyFilter) {
+        self.key_filter = key_filter;
+    }
+
+    pub fn set_ignore_list(&mut self, ignore_list: IgnoreList) {
+        self.ignore_list = ignore_list;
+    }
+
+    pub fn set_autocorrect(&mut self, autocorrect: AutoCorrect) {
+        self.autocorrect = autocorrect;
+    }
+
+    /// Returns what the project's autocorrect list says to replace
+    /// `word` with, if anything.
+    pub fn autocorrect(&self, word: &str) -> Option<String> {
+        self.autocorrect.fix(word)
+    }
+
+    /// Returns the fix to make to `word` without asking: the one from
+    /// the autocorrect list, or else the one that was chosen the last
+    /// time it was corrected.
+    pub fn known_fix(&self, word: &str) -> Option<String> {
+        self.autocorrect(word)
+            .or_else(|| self.correction(word).map(str::to_string))
+    }
+
+    /// Returns true if the project's ignore list or one of its accept
+    /// patterns says never to flag `word`.
+    pub fn ignores_word(&self, word: &str) -> bool {
+        self.accept_patterns.iter().any(|re| re.is_match(word)) || self.ignore_list.contains(word)
+    }
+
+    /// Returns the words that should not be flagged in the text of `key`
+    /// in the file `file`.
+    pub fn suppression(&self, file: &str, key: &str) -> Suppression {
+        if !self.key_filter.accepts(key) {
+            return Suppression::whole_line();
+        }
+        Suppression::ignore(
+            self.ignores
+                .iter()
+                .filter(|i| i.file == file && i.key == key)
+                .map(|i| i.word.clone())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let contents = "# ck3spell project file\n\
+                        accept\tValyria\n\
+                        accept\tValyrian\tVictorian\n\
+                        ignore\tevents_l_english.yml\tevent.1.desc\tolde\n\
+                        \n\
+                        ignore\tevents_l_english.yml\tevent.2.desc\tshoppe\n\
+                        correct\talot\ta lot\n\
+                        accept-pattern\t^[A-Z]{2,4}-\\d+$\n\
+                        accept-pattern\t^Mc[A-Z][a-z]+\n";
+        let mut project = Project::default();
+        project.parse(contents).unwrap();
+        let accepted = project.accepted();
+        assert_eq!(2, accepted.len());
+        assert_eq!("Valyria", accepted[0].word);
+        assert_eq!(None, accepted[0].model);
+        assert_eq!(Some("Victorian".to_string()), accepted[1].model);
+        let ignores = project.ignores();
+        assert_eq!(2, ignores.len());
+        assert_eq!("events_l_english.yml", ignores[0].file);
+        assert_eq!("event.1.desc", ignores[0].key);
+        assert_eq!("olde", ignores[0].word);
+        assert_eq!(Some("a lot"), project.correction("alot"));
+        assert_eq!(None, project.correction("a lot"));
+        assert!(project.ignores_word("MOD-123"));
+        assert!(!project.ignores_word("MODDING-123"));
+        assert!(project.ignores_word("McLeod"));
+        assert!(project.ignores_word("McLeods"));
+        assert!(!project.ignores_word("Mcleod"));
+
+        assert!(Project::default()
+            .parse("ignore\tfile.yml\tolde\n")
+            .is_err());
+        assert!(Project::default()
+            .parse("accept-pattern\t(unclosed\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_corrections() {
+        let mut project = Project::default();
+        assert!(project.add_correction("recieve", "receive"));
+        assert!(!project.add_correction("recieve", "receive"));
+        assert_eq!(Some("receive"), project.correction("recieve"));
+        // The latest choice wins.
+        assert!(project.add_correction("recieve", "relieve"));
+        assert_eq!(Some("relieve"), project.correction("recieve"));
+        assert_eq!(Some("relieve".to_string()), project.known_fix("recieve"));
+        assert_eq!(None, project.known_fix("receive"));
+        assert!(!project.add_correction("two\twords", "words"));
+        assert_eq!(None, project.correction("two\twords"));
+    }
+
+    #[test]
+    fn test_suppression() {
+        let mut project = Project::default();
+        let ignore = Ignore {
+            file: "events_l_english.yml".to_string(),
+            key: "event.1.desc".to_string(),
+            word: "olde".to_string(),
+        };
+        assert!(project.add_ignore(ignore.clone()));
+        assert!(!project.add_ignore(ignore.clone()));
+
+        let s = project.suppression("events_l_english.yml", "event.1.desc");
+        assert!(s.word("olde"));
+        assert!(!s.line());
+        let s = project.suppression("events_l_english.yml", "event.2.desc");
+        assert!(!s.word("olde"));
+        let s = project.suppression("other_l_english.yml", "event.1.desc");
+        assert!(!s.word("olde"));
+
+        project.remove_ignore(&ignore);
+        let s = project.suppression("events_l_english.yml", "event.1.desc");
+        assert!(!s.word("olde"));
+
+        let skip = vec![Regex::new("_debug_desc$").unwrap()];
+        project.set_key_filter(KeyFilter::new(Vec::new(), skip));
+        let s = project.suppression("events_l_english.yml", "event_1_debug_desc");
+        assert!(s.line());
+        let s = project.suppression("events_l_english.yml", "event.1.desc");
+        assert!(!s.line());
+    }
+}
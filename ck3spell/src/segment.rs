@@ -0,0 +1,123 @@
+use clap::ValueEnum;
+use druid::{Env, Key};
+use std::ops::Range;
+
+pub const SPLIT_UNDERSCORES: Key<bool> = Key::new("ck3spell.split-underscores");
+pub const SPLIT_DIGITS: Key<bool> = Key::new("ck3spell.split-digits");
+pub const SPLIT_CAMEL_CASE: Key<bool> = Key::new("ck3spell.split-camel-case");
+
+/// Places where a word that is not in the dictionary may be split into
+/// segments that are checked separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Split {
+    /// "Lord_Protector" is checked as "Lord" and "Protector".
+    Underscores,
+    /// "Tier3Army" is checked as "Tier", "3", and "Army".
+    Digits,
+    /// "SeaKing" is checked as "Sea" and "King".
+    CamelCase,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Segmentation {
+    underscores: bool,
+    digits: bool,
+    camel_case: bool,
+}
+
+impl Segmentation {
+    /// Returns a segmentation that splits words at all boundaries
+    /// except the ones in `disabled`.
+    pub fn all_except(disabled: &[Split]) -> Self {
+        Segmentation {
+            underscores: !disabled.contains(&Split::Underscores),
+            digits: !disabled.contains(&Split::Digits),
+            camel_case: !disabled.contains(&Split::CamelCase),
+        }
+    }
+
+    pub fn from_env(env: &Env) -> Self {
+        Segmentation {
+            underscores: env.get(SPLIT_UNDERSCORES),
+            digits: env.get(SPLIT_DIGITS),
+            camel_case: env.get(SPLIT_CAMEL_CASE),
+        }
+    }
+
+    pub fn set_env(&self, env: &mut Env) {
+        env.set(SPLIT_UNDERSCORES, self.underscores);
+        env.set(SPLIT_DIGITS, self.digits);
+        env.set(SPLIT_CAMEL_CASE, self.camel_case);
+    }
+
+    /// Return the byte ranges of the segments of `word`.
+    /// The underscores between segments are not part of any segment.
+    pub fn segments(&self, word: &str) -> Vec<Range<usize>> {
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut chars = word.char_indices().peekable();
+        let mut prev: Option<char> = None;
+        while let Some((i, c)) = chars.next() {
+            if self.underscores && c == '_' {
+                segments.push(start..i);
+                start = i + c.len_utf8();
+                prev = None;
+                continue;
+            }
+            if let Some(p) = prev {
+                let next = chars.peek().map(|&(_, n)| n);
+                let digit_boundary =
+                    p.is_alphanumeric() && c.is_alphanumeric() && p.is_numeric() != c.is_numeric();
+                // Split "SeaKing" before the K, and "HTMLParser" before the P.
+                let camel_boundary = (p.is_lowercase() && c.is_uppercase())
+                    || (p.is_uppercase()
+                        && c.is_uppercase()
+                        && next.is_some_and(char::is_lowercase));
+                if (self.digits && digit_boundary) || (self.camel_case && camel_boundary) {
+                    segments.push(start..i);
+                    start = i;
+                }
+            }
+            prev = Some(c);
+        }
+        segments.push(start..word.len());
+        segments.retain(|range| !range.is_empty());
+        segments
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split<'a>(seg: &Segmentation, word: &'a str) -> Vec<&'a str> {
+        seg.segments(word)
+            .into_iter()
+            .map(|range| &word[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_segments() {
+        let seg = Segmentation::all_except(&[]);
+        assert_eq!(vec!["Lord", "Protector"], split(&seg, "Lord_Protector"));
+        assert_eq!(vec!["Tier", "3", "Army"], split(&seg, "Tier3Army"));
+        assert_eq!(vec!["Sea", "King"], split(&seg, "SeaKing"));
+        assert_eq!(vec!["HTML", "Parser"], split(&seg, "HTMLParser"));
+        assert_eq!(vec!["word"], split(&seg, "word"));
+        assert_eq!(vec!["Word"], split(&seg, "Word"));
+        assert_eq!(vec!["UPPER"], split(&seg, "UPPER"));
+        assert_eq!(vec!["a", "b"], split(&seg, "_a__b_"));
+    }
+
+    #[test]
+    fn test_segments_disabled() {
+        let seg = Segmentation::all_except(&[Split::Digits, Split::CamelCase]);
+        assert_eq!(vec!["Lord", "Protector"], split(&seg, "Lord_Protector"));
+        assert_eq!(vec!["Tier3Army"], split(&seg, "Tier3Army"));
+
+        let seg = Segmentation::all_except(&[Split::Underscores]);
+        assert_eq!(vec!["Lord_Protector"], split(&seg, "Lord_Protector"));
+        assert_eq!(vec!["Tier", "3", "Army"], split(&seg, "Tier3Army"));
+    }
+}
@@ -0,0 +1,118 @@
+use druid::{ExtEventSink, Target};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::commands::{MODEL_FOUND, SUGGESTIONS_FOUND};
+use crate::lazyspeller::SharedSpeller;
+
+/// A hard word shouldn't keep the suggestions panel empty for long.
+const SUGGESTION_BUDGET: Duration = Duration::from_millis(300);
+/// The model word is only needed when accepting a word, so it gets a
+/// bit longer.
+const MODEL_BUDGET: Duration = Duration::from_secs(1);
+
+struct Query {
+    id: u64,
+    speller: SharedSpeller,
+    word: String,
+    max: usize,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Looks up suggestions on a worker thread, so that stepping quickly
+/// through the misspelled words doesn't wait for the suggestions of
+/// words that are only passed by. Starting a new search cancels the one
+/// before it. The suggestions arrive with a SUGGESTIONS_FOUND command,
+/// and then a model word for accepting the word arrives with a
+/// MODEL_FOUND command, because finding it means going through the
+/// whole dictionary. Both searches stop when cancelled, so that the UI
+/// thread never has to wait long for the dictionary.
+pub struct Suggester {
+    queries: Sender<Query>,
+    last_id: Cell<u64>,
+    /// The cancel flag of the latest search.
+    cancel: RefCell<Arc<AtomicBool>>,
+}
+
+impl Suggester {
+    pub fn new(sink: ExtEventSink) -> Self {
+        let (queries, receiver) = channel::<Query>();
+        thread::spawn(move || {
+            for query in receiver {
+                // Skip searches that were cancelled while they waited.
+                if query.cancel.load(Ordering::Relaxed) {
+                    continue;
+                }
+                // The dictionary is only locked for the length of each
+                // search, so that accepting a word can get in between.
+                let suggestions = query.speller.read().unwrap().suggestions_cancellable(
+                    &query.word,
+                    query.max,
+                    SUGGESTION_BUDGET,
+                    &query.cancel,
+                );
+                if query.cancel.load(Ordering::Relaxed) {
+                    continue;
+                }
+                // This only fails if the window is gone.
+                let _ =
+                    sink.submit_command(SUGGESTIONS_FOUND, (query.id, suggestions), Target::Auto);
+                if query.cancel.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let model = query.speller.read().unwrap().suggest_model_cancellable(
+                    &query.word,
+                    MODEL_BUDGET,
+                    &query.cancel,
+                );
+                if !query.cancel.load(Ordering::Relaxed) {
+                    let _ = sink.submit_command(MODEL_FOUND, (query.id, model), Target::Auto);
+                }
+            }
+        });
+        Suggester {
+            queries,
+            last_id: Cell::new(0),
+            cancel: RefCell::new(Arc::new(AtomicBool::new(false))),
+        }
+    }
+
+    /// A suggester without a worker thread, whose searches never get an
+    /// answer, for using the app state without a window.
+    pub fn idle() -> Self {
+        let (queries, _) = channel::<Query>();
+        Suggester {
+            queries,
+            last_id: Cell::new(0),
+            cancel: RefCell::new(Arc::new(AtomicBool::new(false))),
+        }
+    }
+
+    /// Start looking for suggestions for `word`, and cancel the search
+    /// before it. Returns the id that the suggestions will come with.
+    pub fn query(&self, speller: SharedSpeller, word: &str, max: usize) -> u64 {
+        self.cancel();
+        let id = self.last_id.get() + 1;
+        self.last_id.set(id);
+        let cancel = Arc::new(AtomicBool::new(false));
+        *self.cancel.borrow_mut() = Arc::clone(&cancel);
+        // The worker only stops when this Suggester is dropped.
+        let _ = self.queries.send(Query {
+            id,
+            speller,
+            word: word.to_string(),
+            max,
+            cancel,
+        });
+        id
+    }
+
+    /// Cancel the latest search, if it's still going on.
+    pub fn cancel(&self) {
+        self.cancel.borrow().store(true, Ordering::Relaxed);
+    }
+}
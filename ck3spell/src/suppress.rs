@@ -0,0 +1,171 @@
+/// The start of a directive in a comment, such as
+/// `# ck3spell: ignore(Aethelred,Eadgyth)`.
+const DIRECTIVE: &str = "ck3spell:";
+
+/// Magic comments that suppress everything on the same line, or on the
+/// line after it.
+const IGNORE_LINE: &str = "ck3spell-ignore-line";
+const IGNORE_NEXT_LINE: &str = "ck3spell-ignore-next-line";
+
+/// What the directives in comments say should not be flagged.
+/// `ck3spell: ignore-line` and `ck3spell-ignore-line` suppress everything
+/// on the line, and `ck3spell: ignore(...)` suppresses the listed words
+/// wherever they appear on the line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Suppression {
+    line: bool,
+    words: Vec<String>,
+}
+
+impl Suppression {
+    /// Parse the directives in `comment`. Returns the suppression and the
+    /// offset in `comment` where the directives start, or None if
+    /// there are no directives.
+    /// A `ck3spell-ignore-next-line` is counted as a directive, but it
+    /// doesn't suppress anything on this line.
+    pub fn from_comment(comment: &str) -> Option<(Suppression, usize)> {
+        let (mut suppression, mut starts) = match Suppression::from_directive(comment) {
+            Some((suppression, start)) => (suppression, vec![start]),
+            None => (Suppression::default(), Vec::new()),
+        };
+        if let Some(start) = comment.find(IGNORE_LINE) {
+            suppression.line = true;
+            starts.push(start);
+        }
+        if let Some(start) = comment.find(IGNORE_NEXT_LINE) {
+            starts.push(start);
+        }
+        starts.into_iter().min().map(|start| (suppression, start))
+    }
+
+    /// Parse the `ck3spell:` directive in `comment`, without the magic
+    /// comments. Returns the suppression and the offset where it starts.
+    fn from_directive(comment: &str) -> Option<(Suppression, usize)> {
+        let start = comment.find(DIRECTIVE)?;
+        let mut suppression = Suppression::default();
+        let mut rest = comment[start + DIRECTIVE.len()..].trim_start();
+        loop {
+            if let Some(r) = rest.strip_prefix("ignore-line") {
+                suppression.line = true;
+                rest = r;
+            } else if let Some((list, r)) =
+                rest.strip_prefix("ignore(").and_then(|r| r.split_once(')'))
+            {
+                let words = list.split(',').map(str::trim).filter(|w| !w.is_empty());
+                suppression.words.extend(words.map(str::to_string));
+                rest = r;
+            } else {
+                break;
+            }
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+        }
+        Some((suppression, start))
+    }
+
+    /// Returns the suppression that `line` applies to the line after it.
+    /// A `ck3spell-ignore-next-line` can be in any comment, but the
+    /// `ck3spell:` directives only apply to the next line if they are on
+    /// a line that holds nothing but a comment, so that a directive at
+    /// the end of a line only applies to its own line. A
+    /// `ck3spell-ignore-line` never applies to the next line.
+    pub fn for_next_line(line: &str) -> Suppression {
+        // Comments come after the loc value, which is in quotes.
+        let tail = line.rfind('"').map_or(line, |i| &line[i + 1..]);
+        if let Some((_, comment)) = tail.split_once('#') {
+            if comment.contains(IGNORE_NEXT_LINE) {
+                return Suppression::whole_line();
+            }
+        }
+        match line.trim_start().strip_prefix('#') {
+            Some(comment) => Suppression::from_directive(comment)
+                .map(|(suppression, _)| suppression)
+                .unwrap_or_default(),
+            None => Suppression::default(),
+        }
+    }
+
+    /// A suppression of everything on the line.
+    pub fn whole_line() -> Suppression {
+        Suppression {
+            line: true,
+            words: Vec::new(),
+        }
+    }
+
+    /// A suppression of just the given words.
+    pub fn ignore(words: Vec<String>) -> Suppression {
+        Suppression { line: false, words }
+    }
+
+    pub fn add(&mut self, other: &Suppression) {
+        self.line |= other.line;
+        self.words.extend(other.words.iter().cloned());
+    }
+
+    /// Returns true if nothing on the line should be flagged.
+    pub fn line(&self) -> bool {
+        self.line
+    }
+
+    /// Returns true if `word` should not be flagged.
+    pub fn word(&self, word: &str) -> bool {
+        self.line || self.words.iter().any(|w| w == word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_comment() {
+        assert_eq!(None, Suppression::from_comment("# just a comment"));
+
+        let (s, start) = Suppression::from_comment("# ck3spell: ignore-line").unwrap();
+        assert_eq!(2, start);
+        assert!(s.line());
+        assert!(s.word("anything"));
+
+        let (s, _) = Suppression::from_comment("# ck3spell: ignore(Aethelred, Eadgyth)").unwrap();
+        assert!(!s.line());
+        assert!(s.word("Aethelred"));
+        assert!(s.word("Eadgyth"));
+        assert!(!s.word("aethelred"));
+        assert!(!s.word("Alfred"));
+
+        let (s, _) =
+            Suppression::from_comment("# old spelling ck3spell: ignore(olde) ignore-line").unwrap();
+        assert!(s.line());
+        assert!(s.word("olde"));
+    }
+
+    #[test]
+    fn test_for_next_line() {
+        let s = Suppression::for_next_line("  # ck3spell: ignore(olde)");
+        assert!(s.word("olde"));
+
+        let s = Suppression::for_next_line(r#" key:0 "Ye olde text" # ck3spell: ignore(olde)"#);
+        assert_eq!(Suppression::default(), s);
+    }
+
+    #[test]
+    fn test_magic_comments() {
+        let (s, start) = Suppression::from_comment(" odd ck3spell-ignore-line").unwrap();
+        assert_eq!(5, start);
+        assert!(s.line());
+
+        let (s, _) = Suppression::from_comment(" ck3spell-ignore-next-line").unwrap();
+        assert!(!s.line());
+
+        let s = Suppression::for_next_line(r#" key:0 "Zzzt!" # ck3spell-ignore-next-line"#);
+        assert!(s.line());
+        let s = Suppression::for_next_line("# ck3spell-ignore-next-line");
+        assert!(s.line());
+        let s = Suppression::for_next_line(r#" key:0 "Zzzt!" # ck3spell-ignore-line"#);
+        assert!(!s.line());
+        let s = Suppression::for_next_line("# ck3spell-ignore-line");
+        assert!(!s.line());
+        let s = Suppression::for_next_line("# ck3spell: ignore-line");
+        assert!(s.line());
+    }
+}
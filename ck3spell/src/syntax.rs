@@ -1,3 +1,4 @@
+use clap::ValueEnum;
 use nom::branch::alt;
 use nom::bytes::complete::{is_not, tag, take_until, take_while, take_while1};
 use nom::character::complete::{
@@ -26,6 +27,8 @@ pub enum TokenType {
     Custom,
     Markup,
     IconTag,
+    Literal,
+    CommentWord,
 }
 
 #[derive(Clone, Debug)]
@@ -117,9 +120,11 @@ fn alternate_format_code(s: Span) -> IResult<Span, char> {
 }
 
 fn word(s: Span) -> IResult<Span, Span> {
-    // U+2019 is the unicode apostrophe
+    // U+2019 is the unicode apostrophe.
+    // Underscores are included so that words like Lord_Protector can be
+    // checked as a whole before being split up.
     recognize(separated_list1(
-        one_of("-'\u{2019}"),
+        one_of("-_'\u{2019}"),
         many1_count(pair(
             take_while1(char::is_alphanumeric),
             take_while(char::is_mark),
@@ -127,6 +132,50 @@ fn word(s: Span) -> IResult<Span, Span> {
     ))(s)
 }
 
+fn url(s: Span) -> IResult<Span, Span> {
+    recognize(pair(
+        alt((
+            tag("https://"),
+            tag("http://"),
+            tag("www."),
+            tag("discord.gg/"),
+        )),
+        take_while1(|c: char| !c.is_whitespace() && !"\"[]$".contains(c)),
+    ))(s)
+}
+
+fn domain_label(s: Span) -> IResult<Span, Span> {
+    take_while1(|c: char| c.is_alphanumeric() || c == '-')(s)
+}
+
+fn email(s: Span) -> IResult<Span, Span> {
+    recognize(tuple((
+        take_while1(|c: char| c.is_alphanumeric() || "._%+-".contains(c)),
+        char('@'),
+        domain_label,
+        many1_count(pair(char('.'), domain_label)),
+    )))(s)
+}
+
+// A path must end in a filename with an extension, to avoid taking
+// things like "and/or" for paths.
+fn path(s: Span) -> IResult<Span, Span> {
+    recognize(tuple((
+        many1_count(pair(
+            take_while1(|c: char| c.is_alphanumeric() || "_.-".contains(c)),
+            char('/'),
+        )),
+        take_while1(|c: char| c.is_alphanumeric() || "_-".contains(c)),
+        char('.'),
+        alphanumeric1,
+    )))(s)
+}
+
+// Text that is not meant to be read as words, so it shouldn't be spellchecked.
+fn literal(s: Span) -> IResult<Span, Span> {
+    alt((url, email, path))(s)
+}
+
 fn code_block(s: Span) -> IResult<Span, Span> {
     delimited(char('['), is_not("]"), char(']'))(s)
 }
@@ -174,9 +223,29 @@ fn code_tokens(s: Span) -> IResult<Span, Vec<Token>> {
     alt((custom_code, token(TokenType::Code, code_block)))(s)
 }
 
+fn key_reference(s: Span) -> IResult<Span, Span> {
+    delimited(char('$'), is_not("$"), char('$'))(s)
+}
+
+// A key reference together with the word fragments that are written
+// directly against it, as in `$NAME$s` or `Deine$ADJ$e`. The fragments
+// are WordParts because they only make a word together with whatever
+// text the key reference stands for.
+fn key_reference_tokens(s: Span) -> IResult<Span, Vec<Token>> {
+    map(
+        tuple((
+            opt(token(TokenType::WordPart, word)),
+            token(TokenType::KeyReference, key_reference),
+            opt(token(TokenType::WordPart, word)),
+        )),
+        |(v1, v2, v3)| vec_triple((v1.unwrap_or_default(), v2, v3.unwrap_or_default())),
+    )(s)
+}
+
 fn loc_value(s: Span) -> IResult<Span, Vec<Token>> {
     fold_many0(
         alt((
+            token(TokenType::Literal, literal),
             map(
                 pair(token(TokenType::WordPart, word), code_tokens),
                 vec_pair,
@@ -185,16 +254,48 @@ fn loc_value(s: Span) -> IResult<Span, Vec<Token>> {
                 pair(code_tokens, token(TokenType::WordPart, word)),
                 vec_pair,
             ),
+            // Hyphenated on both sides, as in Anglo-[GetTitle]-Saxon
+            map(
+                pair(
+                    map(
+                        tuple((
+                            token(TokenType::WordPart, word),
+                            no_token(char('-')),
+                            code_tokens,
+                        )),
+                        vec_triple,
+                    ),
+                    map(
+                        pair(no_token(char('-')), token(TokenType::WordPart, word)),
+                        vec_pair,
+                    ),
+                ),
+                vec_pair,
+            ),
+            // Hyphenated, as in pre-[GetEra] and [GetTitle]-bound
+            map(
+                tuple((
+                    token(TokenType::WordPart, word),
+                    no_token(char('-')),
+                    code_tokens,
+                )),
+                vec_triple,
+            ),
+            map(
+                tuple((
+                    code_tokens,
+                    no_token(char('-')),
+                    token(TokenType::WordPart, word),
+                )),
+                vec_triple,
+            ),
+            key_reference_tokens,
             token(TokenType::Word, word),
             code_tokens,
             token(TokenType::IconTag, icon_tag),
             token(TokenType::IconTag, alternate_icon_tag),
             // $$ is used to represent a single $ instead of a KeyReference
             no_token(tag("$$")),
-            token(
-                TokenType::KeyReference,
-                delimited(char('$'), is_not("$"), char('$')),
-            ),
             token(TokenType::Escape, preceded(char('\\'), anychar)),
             token(
                 TokenType::Markup,
@@ -248,6 +349,428 @@ pub fn parse_line(text: &str) -> Vec<Token> {
     }
 }
 
+/// How a WordPart is joined to the code or key reference next to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Join {
+    /// Directly followed by code with a Custom key that gives the ending,
+    /// as in `meilleur[bg_opponent.Custom('FR_E')]`.
+    CustomEnding,
+    /// Followed by a hyphen and code, as in `pre-[GetEra]`.
+    HyphenBefore,
+    /// Preceded by code and a hyphen, as in `[GetTitle]-bound`.
+    HyphenAfter,
+    /// Written directly against code or a key reference, as in `$NAME$s`.
+    #[value(skip)]
+    Attached,
+}
+
+/// Return how the WordPart at `tokens[i]` is joined to its neighbors.
+pub fn word_part_join(line: &str, tokens: &[Token], i: usize) -> Join {
+    let range = &tokens[i].range;
+    let next = tokens.get(i + 1).filter(|t| t.ttype == TokenType::Code);
+    let prev = i
+        .checked_sub(1)
+        .map(|j| &tokens[j])
+        .filter(|t| t.ttype == TokenType::Code);
+    if let Some(next) = next {
+        if next.range.start == range.end
+            && tokens.get(i + 2).map(|t| t.ttype) == Some(TokenType::Custom)
+        {
+            return Join::CustomEnding;
+        }
+        if next.range.start == range.end + 1 && line[range.end..].starts_with('-') {
+            return Join::HyphenBefore;
+        }
+    }
+    if let Some(prev) = prev {
+        if range.start == prev.range.end + 1 && line[prev.range.end..].starts_with('-') {
+            return Join::HyphenAfter;
+        }
+    }
+    Join::Attached
+}
+
+fn is_code(token: &Token) -> bool {
+    matches!(token.ttype, TokenType::Code | TokenType::Custom)
+}
+
+/// Returns true if `a` and `b` are separated by just a hyphen.
+fn hyphen_between(line: &str, a: &Token, b: &Token) -> bool {
+    b.range.start == a.range.end + 1 && line[a.range.end..].starts_with('-')
+}
+
+/// A word made of WordParts that are joined by hyphens to the code
+/// between them, as in `Anglo-[GetTitle]-Saxon` or `pre-[GetEra]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinedWord {
+    /// The word without the code, as "Anglo-Saxon" or "pre-".
+    pub text: String,
+    /// The ranges of the WordParts, each with its own text as it is
+    /// checked if the whole word is bad, such as "Anglo-" and "Saxon".
+    pub parts: Vec<(Range<usize>, String)>,
+}
+
+/// Put together the word that the WordPart at `tokens[i]` starts.
+/// Returns None if the WordPart continues a word that an earlier
+/// WordPart starts.
+pub fn joined_word(line: &str, tokens: &[Token], i: usize) -> Option<JoinedWord> {
+    // Look back past the code, for a WordPart joined to it by a hyphen.
+    if i > 0 && is_code(&tokens[i - 1]) && hyphen_between(line, &tokens[i - 1], &tokens[i]) {
+        let first_code = (0..i).rev().take_while(|&j| is_code(&tokens[j])).last()?;
+        if first_code > 0
+            && tokens[first_code - 1].ttype == TokenType::WordPart
+            && hyphen_between(line, &tokens[first_code - 1], &tokens[first_code])
+        {
+            return None;
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut current = i;
+    loop {
+        let range = tokens[current].range.clone();
+        let mut text = line[range.clone()].to_string();
+        let next = current + 1;
+        if next >= tokens.len()
+            || !is_code(&tokens[next])
+            || !hyphen_between(line, &tokens[current], &tokens[next])
+        {
+            parts.push((range, text));
+            break;
+        }
+        text.push('-');
+        parts.push((range, text));
+        let after = (next..tokens.len())
+            .find(|&j| !is_code(&tokens[j]))
+            .filter(|&j| {
+                tokens[j].ttype == TokenType::WordPart
+                    && hyphen_between(line, &tokens[j - 1], &tokens[j])
+            });
+        match after {
+            Some(after) => current = after,
+            None => break,
+        }
+    }
+    let text = parts.iter().map(|(_, text)| text.as_str()).collect();
+    Some(JoinedWord { text, parts })
+}
+
+/// Return the words in the `comment` token of `line`, as CommentWord tokens.
+pub fn comment_words(line: &str, comment: &Token) -> Vec<Token> {
+    let text = &line[comment.range.clone()];
+    let mut words = match fold_many0(
+        alt((token(TokenType::CommentWord, word), no_token(anychar))),
+        Vec::new,
+        vec_add,
+    )(Span::new(text))
+    {
+        Ok((_, words)) => words,
+        Err(_) => Vec::new(),
+    };
+    for token in &mut words {
+        token.range =
+            token.range.start + comment.range.start..token.range.end + comment.range.start;
+    }
+    words
+}
+
+/// Return the ranges of the markup tokens in `line` that are not balanced.
+/// Each `#format` must be closed by a `#!`, and they can be nested.
+/// The `§` color codes don't nest: a new one replaces the previous one,
+/// and the last one must be closed by a `§!`.
+/// A closing `#!` or `§!` without an opening one is also unbalanced.
+pub fn unbalanced_markup(line: &str, tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut unbalanced = Vec::new();
+    let mut open_formats = Vec::new();
+    let mut open_color = None;
+    for token in tokens.iter().filter(|t| t.ttype == TokenType::Markup) {
+        let range = token.range.clone();
+        match &line[range.clone()] {
+            "#!" => {
+                if open_formats.pop().is_none() {
+                    unbalanced.push(range);
+                }
+            }
+            "§!" => {
+                if open_color.take().is_none() {
+                    unbalanced.push(range);
+                }
+            }
+            markup if markup.starts_with('#') => open_formats.push(range),
+            _ => open_color = Some(range),
+        }
+    }
+    unbalanced.extend(open_formats);
+    unbalanced.extend(open_color);
+    unbalanced.sort_by_key(|range| range.start);
+    unbalanced
+}
+
+/// Returns true if the code inside a `[...]` block looks well formed:
+/// parentheses and quotes are balanced, and there are no empty names
+/// between the dots, as in `[ROOT..GetName]`.
+fn is_valid_code(code: &str) -> bool {
+    // Anything after a | at the top level is a format, like in [ROOT.GetName|U]
+    let mut depth = 0;
+    let mut in_quote = false;
+    let mut name_len = 0;
+    for c in code.chars() {
+        if in_quote {
+            in_quote = c != '\'';
+            continue;
+        }
+        match c {
+            '\'' => {
+                in_quote = true;
+                name_len += 1;
+            }
+            '(' => {
+                if depth == 0 && name_len == 0 {
+                    return false;
+                }
+                depth += 1;
+            }
+            ')' => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+                name_len += 1;
+            }
+            '.' if depth == 0 => {
+                if name_len == 0 {
+                    return false;
+                }
+                name_len = 0;
+            }
+            '|' if depth == 0 => break,
+            c if depth == 0 && !c.is_whitespace() => name_len += 1,
+            _ => (),
+        }
+    }
+    !in_quote && depth == 0 && name_len > 0
+}
+
+/// Return the ranges of the code blocks in `line` that look malformed,
+/// and of any `[` that doesn't start a code block because it is never closed.
+pub fn malformed_code(line: &str, tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut malformed = Vec::new();
+    // A code block with a Custom in it is split into several tokens.
+    let mut blocks: Vec<Range<usize>> = Vec::new();
+    for token in tokens {
+        if !matches!(token.ttype, TokenType::Code | TokenType::Custom) {
+            continue;
+        }
+        match blocks.last_mut() {
+            Some(block)
+                if block.end == token.range.start && !line[block.clone()].ends_with(']') =>
+            {
+                block.end = token.range.end;
+            }
+            _ => blocks.push(token.range.clone()),
+        }
+    }
+    for block in blocks {
+        let code = &line[block.start + 1..block.end - 1];
+        if !is_valid_code(code) {
+            malformed.push(block);
+        }
+    }
+
+    // Look for [ that are not part of any token, inside the loc value.
+    let value_end = tokens
+        .iter()
+        .rfind(|t| !matches!(t.ttype, TokenType::Comment | TokenType::CommentWord))
+        .map_or(0, |t| t.range.end);
+    for (i, _) in line[..value_end].match_indices('[') {
+        if !tokens.iter().any(|t| t.range.contains(&i)) {
+            malformed.push(i..i + 1);
+        }
+    }
+    malformed.sort_by_key(|range| range.start);
+    malformed
+}
+
+/// Return the loc key defined on `line`, without the `:0` after it.
+pub fn loc_key_name<'a>(line: &'a str, tokens: &[Token]) -> Option<&'a str> {
+    let key = tokens.iter().find(|t| t.ttype == TokenType::LocKey)?;
+    line[key.range.clone()].split(':').next()
+}
+
+/// Return the range of the text between the quotes of the loc value
+/// in `line`, if it has one.
+fn loc_value_range(line: &str, tokens: &[Token]) -> Option<Range<usize>> {
+    let key = tokens.iter().find(|t| t.ttype == TokenType::LocKey)?;
+    let after_key = &line[key.range.end..];
+    let start = key.range.end + (after_key.len() - after_key.trim_start().len());
+    if !line[start..].starts_with('"') {
+        return None;
+    }
+    let limit = tokens
+        .iter()
+        .find(|t| t.ttype == TokenType::Comment)
+        .map_or(line.len(), |t| t.range.start);
+    let end = line[..limit].rfind('"').filter(|&end| end > start)?;
+    Some(start + 1..end)
+}
+
+/// Return the ranges of the whitespace in `line` that should be removed:
+/// whitespace at the end of the line, spaces at the end of the loc value,
+/// and the extra spaces where the loc value has more than one in a row.
+/// Spaces at the start of the loc value are left alone, because they
+/// may be meant as indentation.
+pub fn bad_whitespace(line: &str, tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut bad = Vec::new();
+    if let Some(value) = loc_value_range(line, tokens) {
+        let text = &line[value.clone()];
+        let trimmed = text.trim_end_matches(' ');
+        let indent = trimmed.len() - trimmed.trim_start_matches(' ').len();
+        let mut run_start = None;
+        for (i, c) in trimmed.char_indices().skip(indent) {
+            match (c == ' ', run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    if i - start > 1 {
+                        bad.push(value.start + start + 1..value.start + i);
+                    }
+                    run_start = None;
+                }
+                _ => (),
+            }
+        }
+        if trimmed.len() < text.len() {
+            bad.push(value.start + trimmed.len()..value.end);
+        }
+    }
+    let trimmed_len = line.trim_end().len();
+    if trimmed_len < line.len() {
+        bad.push(trimmed_len..line.len());
+    }
+    bad
+}
+
+/// Return the ranges of `patterns` in the text of the loc value in
+/// `line`, leaving out code, markup, and the like. Where two matches
+/// would overlap, only the first one counts.
+pub fn find_in_text(line: &str, tokens: &[Token], patterns: &[&str]) -> Vec<Range<usize>> {
+    let Some(value) = loc_value_range(line, tokens) else {
+        return Vec::new();
+    };
+    let in_code = |i: usize| {
+        tokens.iter().any(|t| {
+            !matches!(t.ttype, TokenType::Word | TokenType::WordPart) && t.range.contains(&i)
+        })
+    };
+    let mut found = Vec::new();
+    let mut next = value.start;
+    for (i, _) in line[value.clone()].char_indices() {
+        let i = value.start + i;
+        if i < next || in_code(i) {
+            continue;
+        }
+        let text = &line[i..value.end];
+        if let Some(pattern) = patterns.iter().find(|p| text.starts_with(**p)) {
+            let end = i + pattern.len();
+            if !in_code(end - 1) {
+                found.push(i..end);
+                next = end;
+            }
+        }
+    }
+    found
+}
+
+/// Characters that take up no space, or only change the direction of
+/// the text around them. Editors don't show them, but they break the way
+/// the game renders text.
+const INVISIBLE_CHARS: [char; 18] = [
+    '\u{061c}', // arabic letter mark
+    '\u{180e}', // mongolian vowel separator
+    '\u{200b}', // zero width space
+    '\u{200c}', // zero width non-joiner
+    '\u{200d}', // zero width joiner
+    '\u{200e}', // left-to-right mark
+    '\u{200f}', // right-to-left mark
+    '\u{202a}', // left-to-right embedding
+    '\u{202b}', // right-to-left embedding
+    '\u{202c}', // pop directional formatting
+    '\u{202d}', // left-to-right override
+    '\u{202e}', // right-to-left override
+    '\u{2060}', // word joiner
+    '\u{2066}', // left-to-right isolate
+    '\u{2067}', // right-to-left isolate
+    '\u{2068}', // first strong isolate
+    '\u{2069}', // pop directional isolate
+    '\u{feff}', // zero width no-break space, or a byte order mark
+];
+
+/// Return the ranges of the invisible characters in `line`, such as
+/// zero-width spaces and right-to-left marks. A byte order mark at the
+/// start of the file is removed when the file is read, so any that is
+/// left here is in the middle of the text.
+pub fn invisible_chars(line: &str) -> Vec<Range<usize>> {
+    line.char_indices()
+        .filter(|(_, c)| INVISIBLE_CHARS.contains(c))
+        .map(|(i, c)| i..i + c.len_utf8())
+        .collect()
+}
+
+/// Return `line` with the invisible characters replaced by visible ones,
+/// so that they can be highlighted. The replacements are as long as the
+/// characters they replace, so the ranges into `line` still fit.
+pub fn show_invisible(line: &str, invisible: &[Range<usize>]) -> String {
+    let mut shown = line.to_string();
+    for range in invisible {
+        let marker = if range.len() == 2 { "¤" } else { "◊" };
+        shown.replace_range(range.clone(), marker);
+    }
+    shown
+}
+
+/// Return the number of characters of the loc value in `line` that are
+/// shown to the player as they are. Markup, code, key references, and
+/// icons are not counted, and an escape such as `\n` counts as one.
+/// The space after a format such as `#bold` is part of the markup.
+pub fn visible_length(line: &str, tokens: &[Token]) -> usize {
+    let Some(value) = loc_value_range(line, tokens) else {
+        return 0;
+    };
+    let mut length = line[value.clone()].chars().count();
+    for token in tokens.iter().filter(|t| value.contains(&t.range.start)) {
+        let text = &line[token.range.clone()];
+        length -= match token.ttype {
+            TokenType::Markup
+                if text.starts_with('#')
+                    && text != "#!"
+                    && line[token.range.end..].starts_with(' ') =>
+            {
+                text.chars().count() + 1
+            }
+            TokenType::Markup
+            | TokenType::Code
+            | TokenType::Custom
+            | TokenType::KeyReference
+            | TokenType::IconTag => text.chars().count(),
+            TokenType::Escape => 1,
+            _ => 0,
+        };
+    }
+    length
+}
+
+/// Return `line` without the whitespace in the `bad` ranges, which must
+/// be sorted and not overlap, as returned by `bad_whitespace`.
+pub fn fix_whitespace(line: &str, bad: &[Range<usize>]) -> String {
+    let mut fixed = String::with_capacity(line.len());
+    let mut pos = 0;
+    for range in bad {
+        fixed.push_str(&line[pos..range.start]);
+        pos = range.end;
+    }
+    fixed.push_str(&line[pos..]);
+    fixed
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -335,6 +858,143 @@ mod test {
         assert_eq!(35..38, tokens[4].range);
     }
 
+    #[test]
+    fn test_hyphenated_code() {
+        let txt = r#" key: "pre-[GetEra] [GetTitle]-bound ami[bg.Custom('FR_E')] [X]s""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(11, tokens.len());
+        assert_eq!(TokenType::WordPart, tokens[1].ttype);
+        assert_eq!(7..10, tokens[1].range);
+        assert_eq!(Join::HyphenBefore, word_part_join(&txt, &tokens, 1));
+        assert_eq!(TokenType::Code, tokens[2].ttype);
+        assert_eq!(11..19, tokens[2].range);
+        assert_eq!(TokenType::Code, tokens[3].ttype);
+        assert_eq!(TokenType::WordPart, tokens[4].ttype);
+        assert_eq!(31..36, tokens[4].range);
+        assert_eq!(Join::HyphenAfter, word_part_join(&txt, &tokens, 4));
+        assert_eq!(TokenType::WordPart, tokens[5].ttype);
+        assert_eq!(Join::CustomEnding, word_part_join(&txt, &tokens, 5));
+        assert_eq!(TokenType::WordPart, tokens[10].ttype);
+        assert_eq!(Join::Attached, word_part_join(&txt, &tokens, 10));
+    }
+
+    #[test]
+    fn test_joined_word() {
+        let txt = r#" key: "pre-[GetEra] Anglo-[GetTitle]-Saxon [X]-bound""#;
+
+        let tokens = parse_line(&txt);
+        let words: Vec<String> = (0..tokens.len())
+            .filter(|&i| tokens[i].ttype == TokenType::WordPart)
+            .filter_map(|i| joined_word(&txt, &tokens, i))
+            .map(|joined| joined.text)
+            .collect();
+        assert_eq!(vec!["pre-", "Anglo-Saxon", "bound"], words);
+
+        let i = tokens.iter().position(|t| &txt[t.range.clone()] == "Anglo");
+        let joined = joined_word(&txt, &tokens, i.unwrap()).unwrap();
+        let parts: Vec<&str> = joined.parts.iter().map(|(_, s)| s.as_str()).collect();
+        assert_eq!(vec!["Anglo-", "Saxon"], parts);
+        assert_eq!("Saxon", &txt[joined.parts[1].0.clone()]);
+    }
+
+    #[test]
+    fn test_key_reference_fragments() {
+        let txt = r#" key: "$NAME$s and Deine$ADJ$e""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(7, tokens.len());
+        assert_eq!(TokenType::KeyReference, tokens[1].ttype);
+        assert_eq!(7..13, tokens[1].range);
+        assert_eq!(TokenType::WordPart, tokens[2].ttype);
+        assert_eq!(13..14, tokens[2].range);
+        assert_eq!(TokenType::Word, tokens[3].ttype);
+        assert_eq!(TokenType::WordPart, tokens[4].ttype);
+        assert_eq!(19..24, tokens[4].range);
+        assert_eq!(TokenType::KeyReference, tokens[5].ttype);
+        assert_eq!(24..29, tokens[5].range);
+        assert_eq!(TokenType::WordPart, tokens[6].ttype);
+        assert_eq!(29..30, tokens[6].range);
+
+        let txt = r#" key: "costs $$5 or $AMOUNT$ gold""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(6, tokens.len());
+        assert_eq!(TokenType::KeyReference, tokens[4].ttype);
+        assert_eq!(TokenType::Word, tokens[5].ttype);
+    }
+
+    #[test]
+    fn test_underscore_word() {
+        let txt = r#" key: "the Lord_Protector""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(3, tokens.len());
+        assert_eq!(TokenType::Word, tokens[2].ttype);
+        assert_eq!(11..25, tokens[2].range);
+    }
+
+    #[test]
+    fn test_url() {
+        let txt = r#" key: "see https://example.com/a?b=c now""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(4, tokens.len());
+        assert_eq!(TokenType::Word, tokens[1].ttype);
+        assert_eq!(TokenType::Literal, tokens[2].ttype);
+        assert_eq!(11..36, tokens[2].range);
+        assert_eq!(TokenType::Word, tokens[3].ttype);
+    }
+
+    #[test]
+    fn test_email() {
+        let txt = r#" key: "mail me@example.org today""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(4, tokens.len());
+        assert_eq!(TokenType::Literal, tokens[2].ttype);
+        assert_eq!(12..26, tokens[2].range);
+    }
+
+    #[test]
+    fn test_path() {
+        let txt = r#" key: "gfx/interface/icon.dds here""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(3, tokens.len());
+        assert_eq!(TokenType::Literal, tokens[1].ttype);
+        assert_eq!(7..29, tokens[1].range);
+
+        let txt = r#" key: "this and/or that""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(5, tokens.len());
+        assert!(tokens.iter().all(|t| t.ttype != TokenType::Literal));
+    }
+
+    #[test]
+    fn test_comment_words() {
+        let txt = r#" key: "word" # a note"#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(3, tokens.len());
+        assert_eq!(TokenType::Comment, tokens[2].ttype);
+        let words = comment_words(&txt, &tokens[2]);
+        assert_eq!(2, words.len());
+        assert_eq!(TokenType::CommentWord, words[0].ttype);
+        assert_eq!(15..16, words[0].range);
+        assert_eq!(17..21, words[1].range);
+    }
+
     #[test]
     fn test_combined_markup() {
         let txt = r##" key: "#high;bold word #!""##;
@@ -351,4 +1011,120 @@ mod test {
         assert_eq!(18..22, tokens[2].range);
         assert_eq!(23..25, tokens[3].range);
     }
+
+    #[test]
+    fn test_balanced_markup() {
+        let txt = r##" key: "#P #bold word#! more#! §Yyellow§Rred§!""##;
+
+        let tokens = parse_line(&txt);
+
+        assert!(unbalanced_markup(&txt, &tokens).is_empty());
+    }
+
+    #[test]
+    fn test_unbalanced_markup() {
+        let txt = r##" key: "#bold word #! #! §Yyellow""##;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(vec![21..23, 24..27], unbalanced_markup(&txt, &tokens));
+
+        let txt = r##" key: "#bold word""##;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(vec![7..12], unbalanced_markup(&txt, &tokens));
+    }
+
+    #[test]
+    fn test_valid_code() {
+        assert!(is_valid_code("ROOT.Char.GetName"));
+        assert!(is_valid_code("ROOT.GetName|U"));
+        assert!(is_valid_code("GetTrait('brave').GetName( ROOT.Char )"));
+        assert!(is_valid_code("bg_opponent.Custom('FR_E')"));
+        assert!(is_valid_code("Concept('faith', 'Faith')|E"));
+        assert!(!is_valid_code("ROOT..GetName"));
+        assert!(!is_valid_code(".GetName"));
+        assert!(!is_valid_code("ROOT.GetName."));
+        assert!(!is_valid_code("GetTrait('brave'.GetName"));
+        assert!(!is_valid_code("GetTrait('brave)).GetName"));
+        assert!(!is_valid_code("Custom('FR_E)"));
+        assert!(!is_valid_code("(ROOT)"));
+        assert!(!is_valid_code(" "));
+    }
+
+    #[test]
+    fn test_malformed_code() {
+        let txt = r#" key: "ami[bg_opponent.Custom('FR_E')] [ROOT..GetName] [oops""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(vec![39..54, 55..56], malformed_code(&txt, &tokens));
+    }
+
+    #[test]
+    fn test_bad_whitespace() {
+        let txt = r#" key: "  indented  with  gaps "  "#;
+
+        let tokens = parse_line(&txt);
+        let bad = bad_whitespace(&txt, &tokens);
+
+        assert_eq!(vec![18..19, 24..25, 29..30, 31..33], bad);
+        assert_eq!(
+            r#" key: "  indented with gaps""#,
+            fix_whitespace(&txt, &bad)
+        );
+
+        let txt = r#" key: "all fine" # a  comment"#;
+
+        let tokens = parse_line(&txt);
+
+        assert!(bad_whitespace(&txt, &tokens).is_empty());
+    }
+
+    #[test]
+    fn test_find_in_text() {
+        let txt = r#" key: "Wait... [GetName('a...b')]--or not -- #bold ...#!" # ..."#;
+
+        let tokens = parse_line(&txt);
+        let found = find_in_text(&txt, &tokens, &["...", "--"]);
+
+        let found: Vec<&str> = found.iter().map(|r| &txt[r.start - 1..r.end + 1]).collect();
+        assert_eq!(vec!["t... ", "]--o", " -- ", " ...#"], found);
+    }
+
+    #[test]
+    fn test_invisible_chars() {
+        let txt = " key: \"Al\u{200b}-Andalus \u{200f}\u{061c}\u{feff}\"";
+
+        let invisible = invisible_chars(txt);
+
+        assert_eq!(vec![9..12, 21..24, 24..26, 26..29], invisible);
+        assert_eq!(" key: \"Al◊-Andalus ◊¤◊\"", show_invisible(txt, &invisible));
+        assert_eq!(" key: \"Al-Andalus \"", fix_whitespace(txt, &invisible));
+        assert!(invisible_chars(" key: \"Al-Andalus\"").is_empty());
+    }
+
+    #[test]
+    fn test_visible_length() {
+        let txt = r##" key: "#bold Hi#! [ROOT.GetName],\nmy $TITLE$ @gold_icon!""##;
+
+        let tokens = parse_line(&txt);
+
+        // "Hi ,\nmy  " is visible, with the escape counting as one.
+        assert_eq!(9, visible_length(&txt, &tokens));
+    }
+
+    #[test]
+    fn test_loc_key_name() {
+        let txt = r#" event.1.desc:0 "Some text""#;
+
+        let tokens = parse_line(&txt);
+
+        assert_eq!(Some("event.1.desc"), loc_key_name(&txt, &tokens));
+
+        let tokens = parse_line("# just a comment");
+
+        assert_eq!(None, loc_key_name("# just a comment", &tokens));
+    }
 }
@@ -5,12 +5,24 @@ use std::ops::Range;
 use std::rc::Rc;
 
 use crate::commands::{CURSOR_CHANGED, DICTIONARY_UPDATED, FILE_CHANGED};
+use crate::suppress::Suppression;
 use crate::{LineInfo, LINE_COLOR};
 
+/// Look for `word` in `text` the way the speller might compare them, so
+/// that accepting a word also counts for its all-caps form or other
+/// apostrophes, and for words it is a part of, such as "Valyria-born"
+/// or the segments of "ValyriaSteel".
+pub fn contains_word(text: &str, word: &str) -> bool {
+    let normalize = |word: &str| word.to_lowercase().replace(['\u{2019}', '\u{2018}'], "'");
+    text.contains(word) || normalize(text).contains(&normalize(word))
+}
+
 pub struct SyntaxHighlighter<W> {
     child: WidgetPod<LineInfo, W>,
     old_line: Option<Rc<String>>,
     old_highlight: Option<Range<usize>>,
+    old_cursor_word: Rc<String>,
+    old_suppress_above: Option<Rc<Suppression>>,
     background: bool,
 }
 
@@ -20,6 +32,8 @@ impl<W: Widget<LineInfo>> SyntaxHighlighter<W> {
             child: WidgetPod::new(child),
             old_line: None,
             old_highlight: None,
+            old_cursor_word: Rc::new(String::new()),
+            old_suppress_above: None,
             background: false,
         }
     }
@@ -31,25 +45,43 @@ impl<W: Widget<LineInfo>> Widget<LineInfo> for SyntaxHighlighter<W> {
         #[allow(clippy::collapsible_if)]
         if let Event::Command(command) = event {
             if let Some(cursor) = command.get(CURSOR_CHANGED) {
-                if self.old_highlight != data.marked_word() {
+                if self.old_highlight != data.marked_range() {
+                    force_update = true;
+                }
+                // Only redo lines that contain the old or new cursor word
+                let cursor_word = Rc::clone(&data.cursor_word.borrow());
+                if *self.old_cursor_word != *cursor_word
+                    && [&self.old_cursor_word, &cursor_word]
+                        .iter()
+                        .any(|word| !word.is_empty() && data.line.line.contains(word.as_str()))
+                {
                     force_update = true;
                 }
                 self.background = cursor.linenr == data.line.line_nr;
-            } else if command.is(DICTIONARY_UPDATED) {
-                if !data.bad_words_range.is_empty() {
+            } else if let Some(words) = command.get(DICTIONARY_UPDATED) {
+                if data
+                    .words()
+                    .any(|bad| words.iter().any(|word| contains_word(&bad.text, word)))
+                {
                     force_update = true;
                 }
             } else if command.is(FILE_CHANGED) {
                 force_update = true;
             }
         }
+        // A directive on the line above may have been added or removed.
+        if !matches!(&self.old_suppress_above, Some(old) if old.same(&data.suppress_above)) {
+            force_update = true;
+        }
         if self.old_line.is_none()
             || force_update
             || !data.line.line.same(self.old_line.as_ref().unwrap())
         {
             data.highlight(env);
             self.old_line = Some(data.line.line.clone());
-            self.old_highlight = data.marked_word();
+            self.old_highlight = data.marked_range();
+            self.old_cursor_word = Rc::clone(&data.cursor_word.borrow());
+            self.old_suppress_above = Some(data.suppress_above.clone());
             ctx.request_paint();
         }
         self.child.event(ctx, event, data, env);
@@ -0,0 +1,70 @@
+use clap::ValueEnum;
+use druid::{Data, Env, Key};
+
+pub const TYPOGRAPHY: Key<u64> = Key::new("ck3spell.typography");
+
+/// How a project writes ellipses and dashes. Texts written the other way
+/// are marked, with the project's way as the suggestion.
+#[derive(Clone, Copy, Data, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Typography {
+    /// "..." should be "…" and "--" should be "—".
+    Unicode,
+    /// "…" should be "..." and "—" should be "--".
+    Ascii,
+}
+
+impl Typography {
+    /// The texts that are written the wrong way in this style, and what
+    /// to write instead.
+    fn replacements(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Typography::Unicode => &[("...", "…"), ("--", "—")],
+            Typography::Ascii => &[("…", "..."), ("—", "--")],
+        }
+    }
+
+    /// The texts that should be marked.
+    pub fn patterns(self) -> Vec<&'static str> {
+        self.replacements().iter().map(|(from, _)| *from).collect()
+    }
+
+    /// Returns what to write instead of `text`, if it's one of the
+    /// texts that should be marked.
+    pub fn fix(self, text: &str) -> Option<&'static str> {
+        self.replacements()
+            .iter()
+            .find(|(from, _)| *from == text)
+            .map(|(_, to)| *to)
+    }
+
+    pub fn from_env(env: &Env) -> Option<Self> {
+        match env.get(TYPOGRAPHY) {
+            1 => Some(Typography::Unicode),
+            2 => Some(Typography::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn set_env(typography: Option<Self>, env: &mut Env) {
+        let value = match typography {
+            None => 0,
+            Some(Typography::Unicode) => 1,
+            Some(Typography::Ascii) => 2,
+        };
+        env.set(TYPOGRAPHY, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(Some("…"), Typography::Unicode.fix("..."));
+        assert_eq!(Some("—"), Typography::Unicode.fix("--"));
+        assert_eq!(None, Typography::Unicode.fix("…"));
+        assert_eq!(Some("--"), Typography::Ascii.fix("—"));
+        assert_eq!(None, Typography::Ascii.fix("-"));
+    }
+}
@@ -1,20 +1,29 @@
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, RawLabel, Scroll,
+    Button, Checkbox, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, RadioGroup,
+    RawLabel, Scroll, SizedBox, TextBox,
 };
 use druid::{Color, Command, Target, WidgetExt};
 
+use crate::acceptcontroller::AcceptController;
 use crate::appcontroller::AppController;
+use crate::appcore::AcceptScope;
 use crate::commands::{
-    ACCEPT_WORD, APPLY_EDIT, APPLY_SUGGESTION, CLOSE_GOOD_FILES, CURSOR_NEXT, CURSOR_PREV,
-    EDIT_LINE, FILE_CHANGED, GOTO_LINE, SAVE_AND_CLOSE,
+    ACCEPT_CANCEL, ACCEPT_CONFIRM, ACCEPT_WORD, ACCEPT_WORD_EXACT, ACCEPT_WORD_LIKE, APPLY_EDIT,
+    APPLY_KNOWN_FIXES, APPLY_SUGGESTION, CLOSE_FIXES, CLOSE_GOOD_FILES, CLOSE_IGNORES,
+    CLOSE_ISSUES, CLOSE_MISSPELLINGS, CURSOR_NEXT, CURSOR_PREV, EDIT_LINE, FILE_CHANGED,
+    FIX_WHITESPACE, GOTO_FIX, GOTO_ISSUE, GOTO_LINE, GOTO_MISSPELLING, IGNORE_AT_KEY, IGNORE_WORD,
+    MANAGE_IGNORES, REMOVE_IGNORE, SAVE_AND_CLOSE, SHOW_ISSUES, SHOW_MISSPELLINGS,
 };
+use crate::diagnostics::{Category, IssueFilter, LineIssue, Misspelling, Severity};
 use crate::edit::EditLineBox;
 use crate::editorcontroller::EditorController;
 use crate::linelist::LineList;
 use crate::linescroller::LineScroller;
+use crate::nowrap::NoWrapLine;
+use crate::project::Ignore;
 use crate::syntaxhighlighter::SyntaxHighlighter;
-use crate::{AppState, FileState, LineInfo, Suggestion};
+use crate::{AppState, AppliedFix, FileState, LineInfo, Suggestion, LONG_LINE_COLOR, WRAP_LINES};
 
 fn make_file_header() -> impl Widget<AppState> {
     let prev = Button::new("Prev")
@@ -30,37 +39,73 @@ fn make_file_header() -> impl Widget<AppState> {
         })
         .disabled_if(|data: &AppState, _| data.file_idx == data.files.len() - 1);
     let file_label = Label::dynamic(|data: &AppState, _| {
-        format!(
-            "{} ({} {})",
-            data.file.filename,
-            data.files.len(),
-            if data.files.len() == 1 {
-                "file"
-            } else {
-                "files"
-            }
-        )
+        let files = if data.files.len() == 1 {
+            "file"
+        } else {
+            "files"
+        };
+        if let Some(err) = data.file.speller.borrow().error() {
+            format!("{} (not spellchecked: {})", data.file.filename, err)
+        } else if data.files_pending > 0 {
+            format!(
+                "{} ({} {}, loading {} more\u{2026})",
+                data.file.filename,
+                data.files.len(),
+                files,
+                data.files_pending
+            )
+        } else {
+            format!("{} ({} {})", data.file.filename, data.files.len(), files)
+        }
     });
+    let filter = RadioGroup::row(vec![
+        ("Words", IssueFilter::Words),
+        ("Spelling", IssueFilter::Only(Category::Spelling)),
+        ("Markup", IssueFilter::Only(Category::Markup)),
+        ("Code", IssueFilter::Only(Category::Code)),
+        ("All issues", IssueFilter::All),
+    ])
+    .lens(AppState::cursor_filter);
+    let wrap = Checkbox::new("Wrap long lines").lens(AppState::wrap_lines);
+    let fast = Checkbox::new("Fast suggestions").lens(AppState::fast_suggestions);
     Flex::row()
         .with_child(prev)
         .with_default_spacer()
         .with_child(next)
         .with_default_spacer()
         .with_flex_child(file_label, 1.0)
+        .with_child(Label::new("Go through:"))
+        .with_child(filter)
+        .with_default_spacer()
+        .with_child(fast)
+        .with_default_spacer()
+        .with_child(wrap)
+        .with_default_spacer()
+}
+
+fn make_line_text(line_break_mode: LineBreaking) -> impl Widget<LineInfo> {
+    RawLabel::new()
+        .with_line_break_mode(line_break_mode)
+        .lens(LineInfo::rendered)
+        .on_click(|ctx, data: &mut LineInfo, _| {
+            ctx.submit_command(Command::new(GOTO_LINE, data.line.line_nr, Target::Auto));
+        })
 }
 
 fn make_line_item() -> impl Widget<LineInfo> {
-    let linenr = Label::dynamic(|line: &LineInfo, _| line.line.line_nr.to_string())
-        .with_text_color(Color::grey8(160))
-        .fix_width(30.0);
-    let line = SyntaxHighlighter::new(
-        RawLabel::new()
-            .with_line_break_mode(LineBreaking::WordWrap)
-            .lens(LineInfo::rendered)
-            .on_click(|ctx, data: &mut LineInfo, _| {
-                ctx.submit_command(Command::new(GOTO_LINE, data.line.line_nr, Target::Auto));
-            }),
-    );
+    let linenr = Either::new(
+        |line: &LineInfo, _| line.too_long(),
+        Label::dynamic(|line: &LineInfo, _| line.line.line_nr.to_string())
+            .with_text_color(LONG_LINE_COLOR),
+        Label::dynamic(|line: &LineInfo, _| line.line.line_nr.to_string())
+            .with_text_color(Color::grey8(160)),
+    )
+    .fix_width(30.0);
+    let line = SyntaxHighlighter::new(Either::new(
+        |_: &LineInfo, env: &Env| env.get(WRAP_LINES),
+        make_line_text(LineBreaking::WordWrap),
+        NoWrapLine::new(make_line_text(LineBreaking::Overflow)),
+    ));
     Flex::row()
         .with_child(Flex::column().with_child(linenr))
         .with_flex_child(line, 1.0)
@@ -80,18 +125,52 @@ fn buttons_builder() -> impl Widget<AppState> {
         .on_click(|ctx, _, _| {
             ctx.submit_command(ACCEPT_WORD);
         })
-        .disabled_if(|data: &AppState, _| data.cursor_word().is_none());
+        .disabled_if(|data: &AppState, _| {
+            data.cursor_word().is_none() || data.cursor_fix().is_some()
+        });
     let edit = Button::new("[E]dit line").on_click(|ctx, _, _| {
         ctx.submit_command(EDIT_LINE);
     });
     let save = Button::new("Save and [C]lose").on_click(|ctx, _, _| {
         ctx.submit_command(SAVE_AND_CLOSE);
     });
+    let fix_whitespace = Button::new("Fix [W]hitespace")
+        .on_click(|ctx, _, _| {
+            ctx.submit_command(FIX_WHITESPACE);
+        })
+        .disabled_if(|data: &AppState, _| {
+            !data
+                .file
+                .lines
+                .get(data.cursor.linenr - 1)
+                .is_some_and(|lineinfo| {
+                    !lineinfo
+                        .ranges(&[Category::Whitespace, Category::Invisible])
+                        .is_empty()
+                })
+        });
     let close_good = Button::new("Close good files")
         .on_click(|ctx, _, _| {
             ctx.submit_command(CLOSE_GOOD_FILES);
         })
         .disabled_if(|data: &AppState, _| !data.file.is_clean());
+    let ignore_at_key = Button::new("Ignore in this key")
+        .on_click(|ctx, _, _| {
+            ctx.submit_command(IGNORE_AT_KEY);
+        })
+        .disabled_if(|data: &AppState, _| data.cursor_word().is_none());
+    let manage_ignores = Button::new("Ignored words").on_click(|ctx, _, _| {
+        ctx.submit_command(MANAGE_IGNORES);
+    });
+    let show_issues = Button::new("Issues").on_click(|ctx, _, _| {
+        ctx.submit_command(SHOW_ISSUES);
+    });
+    let show_misspellings = Button::new("Common misspellings").on_click(|ctx, _, _| {
+        ctx.submit_command(SHOW_MISSPELLINGS);
+    });
+    let apply_known_fixes = Button::new("Apply known fixes").on_click(|ctx, _, _| {
+        ctx.submit_command(APPLY_KNOWN_FIXES);
+    });
     Flex::column()
         .with_child(
             Flex::row()
@@ -105,7 +184,22 @@ fn buttons_builder() -> impl Widget<AppState> {
                 .with_default_spacer()
                 .with_child(save),
         )
-        .with_child(Flex::row().with_child(close_good))
+        .with_child(
+            Flex::row()
+                .with_child(fix_whitespace)
+                .with_default_spacer()
+                .with_child(ignore_at_key)
+                .with_default_spacer()
+                .with_child(manage_ignores)
+                .with_default_spacer()
+                .with_child(show_issues)
+                .with_default_spacer()
+                .with_child(show_misspellings)
+                .with_default_spacer()
+                .with_child(apply_known_fixes)
+                .with_default_spacer()
+                .with_child(close_good),
+        )
 }
 
 fn make_suggestion() -> impl Widget<Suggestion> {
@@ -122,9 +216,304 @@ fn make_suggestion() -> impl Widget<Suggestion> {
     Flex::row().with_child(nr).with_flex_child(word, 1.0)
 }
 
+/// Shown instead of the suggestion list when there are no suggestions,
+/// so that the other ways of dealing with the word are at hand.
+fn no_suggestions_builder() -> impl Widget<AppState> {
+    let reason = Label::dynamic(|data: &AppState, _| data.no_suggestions.to_string());
+    let accept = Button::new("Accept").on_click(|ctx, _, _| {
+        ctx.submit_command(ACCEPT_WORD);
+    });
+    let ignore = Button::new("Ignore").on_click(|ctx, _, _| {
+        ctx.submit_command(IGNORE_WORD);
+    });
+    let edit = Button::new("Edit line").on_click(|ctx, _, _| {
+        ctx.submit_command(EDIT_LINE);
+    });
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(reason)
+        .with_default_spacer()
+        .with_child(
+            Flex::row()
+                .with_child(accept)
+                .with_default_spacer()
+                .with_child(ignore)
+                .with_default_spacer()
+                .with_child(edit),
+        )
+        .padding(5.0)
+        .expand_width()
+}
+
+fn accept_box_builder() -> impl Widget<AppState> {
+    let title = Label::dynamic(|data: &AppState, _| {
+        if let Some(cursor_word) = data.cursor_word() {
+            format!("Accept {}", cursor_word)
+        } else {
+            String::new()
+        }
+    });
+    let exact = Button::new("Accept exactly").on_click(|ctx, _, _| {
+        ctx.submit_command(ACCEPT_WORD_EXACT);
+    });
+    let cancel = Button::new("[Esc] Cancel").on_click(|ctx, _, _| {
+        ctx.submit_command(ACCEPT_CANCEL);
+    });
+    // '\u{23ce}' is the return sign
+    let like = Button::new("[\u{23ce}] Accept with endings like")
+        .on_click(|ctx, _, _| {
+            ctx.submit_command(ACCEPT_WORD_LIKE);
+        })
+        .disabled_if(|data: &AppState, _| data.accept_model.trim().is_empty());
+    let model = TextBox::new()
+        .with_placeholder("dictionary word")
+        .lens(AppState::accept_model)
+        .controller(AcceptController)
+        .fix_width(200.0);
+    // Shown when the word is one that the dictionary says to avoid.
+    let warning = Label::dynamic(|data: &AppState, _| {
+        data.pending_accept
+            .as_ref()
+            .map(|pending| pending.warning.to_string())
+            .unwrap_or_default()
+    })
+    .with_text_color(Color::rgb8(0xff, 0x40, 0x40))
+    .with_line_break_mode(LineBreaking::WordWrap);
+    let confirm = Button::new("[\u{23ce}] Accept anyway").on_click(|ctx, _, _| {
+        ctx.submit_command(ACCEPT_CONFIRM);
+    });
+    let warning = Either::new(
+        |data: &AppState, _| data.pending_accept.is_some(),
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_default_spacer()
+            .with_child(warning)
+            .with_default_spacer()
+            .with_child(confirm),
+        SizedBox::empty(),
+    );
+    let preview = Label::dynamic(|data: &AppState, _| data.accept_preview())
+        .with_line_break_mode(LineBreaking::WordWrap);
+    let scope = RadioGroup::row(vec![
+        ("[Ctrl-1] This session", AcceptScope::Session),
+        ("[Ctrl-2] Personal dictionary", AcceptScope::Personal),
+        ("[Ctrl-3] Project", AcceptScope::Project),
+    ])
+    .lens(AppState::accept_scope);
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(title)
+        .with_child(warning)
+        .with_default_spacer()
+        .with_child(scope)
+        .with_default_spacer()
+        .with_child(
+            Flex::row()
+                .with_child(exact)
+                .with_default_spacer()
+                .with_child(cancel),
+        )
+        .with_default_spacer()
+        .with_child(
+            Flex::row()
+                .with_child(like)
+                .with_default_spacer()
+                .with_child(model),
+        )
+        .with_default_spacer()
+        .with_flex_child(Scroll::new(preview).vertical(), 1.0)
+        .padding(5.0)
+}
+
+fn make_ignore() -> impl Widget<Ignore> {
+    let remove = Button::new("Remove").on_click(|ctx: &mut EventCtx, ignore: &mut Ignore, _| {
+        ctx.submit_command(Command::new(REMOVE_IGNORE, ignore.clone(), Target::Auto))
+    });
+    let text = Label::dynamic(|ignore: &Ignore, _| {
+        format!("{} in {} ({})", ignore.word, ignore.key, ignore.file)
+    });
+    Flex::row()
+        .with_child(remove)
+        .with_default_spacer()
+        .with_flex_child(text, 1.0)
+}
+
+/// Lists the words that the project ignores in particular loc keys,
+/// so that they can be taken back.
+fn ignores_box_builder() -> impl Widget<AppState> {
+    let title = Label::dynamic(|data: &AppState, _| {
+        if data.ignores.is_empty() {
+            "No words are ignored in particular keys.".to_string()
+        } else {
+            format!("Ignored words ({})", data.ignores.len())
+        }
+    });
+    let close = Button::new("Close").on_click(|ctx, _, _| {
+        ctx.submit_command(CLOSE_IGNORES);
+    });
+    let list = Scroll::new(List::new(make_ignore).lens(AppState::ignores)).vertical();
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(title)
+                .with_default_spacer()
+                .with_child(close),
+        )
+        .with_default_spacer()
+        .with_flex_child(list, 1.0)
+        .padding(5.0)
+}
+
+fn make_issue() -> impl Widget<LineIssue> {
+    let goto = Button::dynamic(|found: &LineIssue, _| {
+        let filename = found.pathname.file_name().unwrap_or_default();
+        format!("{}:{}", filename.to_string_lossy(), found.line_nr)
+    })
+    .on_click(|ctx: &mut EventCtx, found: &mut LineIssue, _| {
+        ctx.submit_command(Command::new(GOTO_ISSUE, found.clone(), Target::Auto))
+    });
+    let severity =
+        Label::dynamic(|found: &LineIssue, _| found.issue.severity().to_string()).fix_width(70.0);
+    let text = Label::dynamic(|found: &LineIssue, _| found.issue.describe());
+    Flex::row()
+        .with_child(goto)
+        .with_default_spacer()
+        .with_child(severity)
+        .with_flex_child(text, 1.0)
+}
+
+/// Lists everything wrong with the files, so that the problems other
+/// than misspelled words can be found without scrolling through them.
+/// Files that haven't been shown yet may not have been checked.
+fn issues_box_builder() -> impl Widget<AppState> {
+    let title = Label::dynamic(|data: &AppState, _| {
+        let count = data.issue_list.len();
+        if count == 0 {
+            "No issues found.".to_string()
+        } else {
+            format!("Issues ({})", count)
+        }
+    });
+    let filter = RadioGroup::row(vec![
+        ("Errors", Severity::Error),
+        ("Warnings", Severity::Warning),
+        ("All", Severity::Hint),
+    ])
+    .lens(AppState::issue_filter);
+    let close = Button::new("Close").on_click(|ctx, _, _| {
+        ctx.submit_command(CLOSE_ISSUES);
+    });
+    let list = Scroll::new(List::new(make_issue).lens(AppState::issue_list)).vertical();
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(title)
+                .with_default_spacer()
+                .with_child(filter)
+                .with_default_spacer()
+                .with_child(close),
+        )
+        .with_default_spacer()
+        .with_flex_child(list, 1.0)
+        .padding(5.0)
+}
+
+fn make_misspelling() -> impl Widget<Misspelling> {
+    let count = Label::dynamic(|m: &Misspelling, _| m.count.to_string()).fix_width(50.0);
+    let goto = Button::dynamic(|m: &Misspelling, _| m.word.clone()).on_click(
+        |ctx: &mut EventCtx, m: &mut Misspelling, _| {
+            ctx.submit_command(Command::new(GOTO_MISSPELLING, m.word.clone(), Target::Auto))
+        },
+    );
+    Flex::row()
+        .with_child(count)
+        .with_default_spacer()
+        .with_child(goto)
+}
+
+/// Lists the misspelled words of the files checked so far, most common
+/// first, so that it's easy to see which words are worth accepting
+/// first. Clicking a word goes to its next occurrence.
+fn misspellings_box_builder() -> impl Widget<AppState> {
+    let title = Label::dynamic(|data: &AppState, _| {
+        if data.misspellings.is_empty() {
+            "No misspellings in the files checked so far.".to_string()
+        } else {
+            let total: usize = data.misspellings.iter().map(|m| m.count).sum();
+            format!(
+                "Misspellings ({} words, {} in total)",
+                data.misspellings.len(),
+                total
+            )
+        }
+    });
+    let close = Button::new("Close").on_click(|ctx, _, _| {
+        ctx.submit_command(CLOSE_MISSPELLINGS);
+    });
+    let list = Scroll::new(List::new(make_misspelling).lens(AppState::misspellings)).vertical();
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(title)
+                .with_default_spacer()
+                .with_child(close),
+        )
+        .with_default_spacer()
+        .with_flex_child(list, 1.0)
+        .padding(5.0)
+}
+
+fn make_applied_fix() -> impl Widget<AppliedFix> {
+    let goto = Button::dynamic(|fix: &AppliedFix, _| {
+        let filename = fix.pathname.file_name().unwrap_or_default();
+        format!("{}:{}", filename.to_string_lossy(), fix.line_nr)
+    })
+    .on_click(|ctx: &mut EventCtx, fix: &mut AppliedFix, _| {
+        ctx.submit_command(Command::new(GOTO_FIX, fix.clone(), Target::Auto))
+    });
+    let text = Label::dynamic(|fix: &AppliedFix, _| format!("{} \u{2192} {}", fix.word, fix.fix));
+    Flex::row()
+        .with_child(goto)
+        .with_default_spacer()
+        .with_flex_child(text, 1.0)
+}
+
+/// Lists the fixes that "Apply known fixes" made in all the files, so
+/// that they can be checked before the files are saved.
+fn fixes_box_builder() -> impl Widget<AppState> {
+    let title = Label::dynamic(|data: &AppState, _| {
+        if data.applied_fixes.is_empty() {
+            "There were no known fixes to apply.".to_string()
+        } else {
+            format!("Applied fixes ({})", data.applied_fixes.len())
+        }
+    });
+    let close = Button::new("Close").on_click(|ctx, _, _| {
+        ctx.submit_command(CLOSE_FIXES);
+    });
+    let list = Scroll::new(List::new(make_applied_fix).lens(AppState::applied_fixes)).vertical();
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(title)
+                .with_default_spacer()
+                .with_child(close),
+        )
+        .with_default_spacer()
+        .with_flex_child(list, 1.0)
+        .padding(5.0)
+}
+
 fn lower_box_builder() -> impl Widget<AppState> {
-    let suggestions =
-        Scroll::new(List::new(make_suggestion).lens(AppState::suggestions)).vertical();
+    let suggestions = Either::new(
+        |data: &AppState, _| data.suggestions.is_empty() && data.cursor_word().is_some(),
+        no_suggestions_builder(),
+        Scroll::new(List::new(make_suggestion).lens(AppState::suggestions)).vertical(),
+    );
     let editor = EditLineBox::multiline()
         .lens(AppState::editing_text)
         .controller(EditorController)
@@ -142,10 +531,35 @@ fn lower_box_builder() -> impl Widget<AppState> {
         .with_child(done_row)
         .with_default_spacer()
         .expand();
+    let suggestions = Either::new(
+        |data: &AppState, _| data.managing_ignores,
+        ignores_box_builder(),
+        suggestions,
+    );
+    let suggestions = Either::new(
+        |data: &AppState, _| data.showing_issues,
+        issues_box_builder(),
+        suggestions,
+    );
+    let suggestions = Either::new(
+        |data: &AppState, _| data.showing_misspellings,
+        misspellings_box_builder(),
+        suggestions,
+    );
+    let suggestions = Either::new(
+        |data: &AppState, _| data.showing_fixes,
+        fixes_box_builder(),
+        suggestions,
+    );
+    let choices = Either::new(
+        |data: &AppState, _| data.accepting,
+        accept_box_builder(),
+        suggestions,
+    );
     Either::new(
         |data: &AppState, _| data.editing_linenr > 0,
         editor_frame,
-        suggestions,
+        choices,
     )
 }
 
@@ -154,13 +568,23 @@ pub fn ui_builder() -> impl Widget<AppState> {
     let lines = LineList::new(make_line_item)
         .lens(FileState::lines)
         .lens(AppState::file);
-    let display = LineScroller::new(lines);
-    let word = Label::dynamic(|data: &AppState, _| {
-        if let Some(cursor_word) = data.cursor_word() {
-            format!("Word: {}", cursor_word)
-        } else {
-            String::new()
+    let display = LineScroller::new(lines)
+        .env_scope(|env, data: &AppState| env.set(WRAP_LINES, data.wrap_lines));
+    let loading = Label::dynamic(|data: &AppState, _| {
+        format!("Loading dictionary for {}\u{2026}", data.loading_dictionary)
+    })
+    .center();
+    let display = Either::new(
+        |data: &AppState, _| !data.loading_dictionary.is_empty(),
+        loading,
+        display,
+    );
+    let word = Label::dynamic(|data: &AppState, _| match data.cursor_issue() {
+        Some(issue) if issue.category.is_word() => {
+            format!("Word: {} ({})", issue.text, issue.category.name())
         }
+        Some(issue) => format!("Issue: {}", issue.describe()),
+        None => String::new(),
     });
     let buttons_row = Flex::row()
         .with_default_spacer()
@@ -176,5 +600,5 @@ pub fn ui_builder() -> impl Widget<AppState> {
         .with_child(buttons_row)
         .with_spacer(2.0)
         .with_flex_child(lower_box_builder(), 1.0)
-        .controller(AppController)
+        .controller(AppController::default())
 }
@@ -0,0 +1,87 @@
+#ifndef SPELLER_H
+#define SPELLER_H
+
+/* Generated by cbindgen from src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A speller that loads Hunspell dictionaries
+typedef struct SpellerHunspellDict SpellerHunspellDict;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Load a speller from a Hunspell dictionary file and affix file.
+// Returns null if the files could not be loaded.
+// The returned speller must be freed with `speller_free`.
+//
+// # Safety
+// `dic_path` and `aff_path` must be null or valid NUL-terminated strings.
+SpellerHunspellDict *speller_new(const char *dic_path, const char *aff_path);
+
+// Free a speller that was returned by `speller_new`.
+// Passing null is allowed and does nothing.
+//
+// # Safety
+// `speller` must be null or a pointer returned by `speller_new` that has
+// not been freed yet.
+void speller_free(SpellerHunspellDict *speller);
+
+// Returns 1 if `word` is correctly spelled, 0 if it is not,
+// and -1 if the arguments were invalid.
+//
+// # Safety
+// `speller` must be null or a live pointer returned by `speller_new`.
+// `word` must be null or a valid NUL-terminated string.
+int speller_spellcheck(const SpellerHunspellDict *speller, const char *word);
+
+// Store up to `max` suggestions for `word` in `out`, which must have
+// room for `max` pointers. Returns the number of suggestions stored.
+// The suggestions must be freed with `speller_free_suggestions`.
+//
+// # Safety
+// `speller` must be null or a live pointer returned by `speller_new`.
+// `word` must be null or a valid NUL-terminated string.
+// `out` must be null or point to an array of at least `max` pointers.
+size_t speller_suggest(const SpellerHunspellDict *speller,
+                       const char *word,
+                       char **out,
+                       size_t max);
+
+// Free the first `count` suggestions stored in `suggs` by `speller_suggest`.
+// The array itself is owned by the caller.
+//
+// # Safety
+// `suggs` must be null or point to `count` strings from `speller_suggest`
+// that have not been freed yet.
+void speller_free_suggestions(char **suggs, size_t count);
+
+// Accept `word` into the dictionary for the lifetime of `speller`.
+// Returns 1 if the word was accepted, 0 if it was not,
+// and -1 if the arguments were invalid.
+//
+// # Safety
+// `speller` must be null or a live pointer returned by `speller_new`.
+// `word` must be null or a valid NUL-terminated string.
+int speller_add_word(SpellerHunspellDict *speller, const char *word);
+
+// Accept `word` into the dictionary with the same affixes as `model`,
+// for the lifetime of `speller`.
+// Returns 1 if the word was accepted, 0 if it was not (for example because
+// `model` is not in the dictionary), and -1 if the arguments were invalid.
+//
+// # Safety
+// `speller` must be null or a live pointer returned by `speller_new`.
+// `word` and `model` must be null or valid NUL-terminated strings.
+int speller_add_word_like(SpellerHunspellDict *speller, const char *word, const char *model);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SPELLER_H */
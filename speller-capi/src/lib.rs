@@ -0,0 +1,210 @@
+//! C API for the speller, built as the `speller_capi` shared library.
+//!
+//! All strings passed in and out are NUL-terminated UTF-8.
+//! The header for these functions is in `include/speller.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/speller.h`.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use speller::{Speller, SpellerHunspellDict};
+
+/// Convert a C string argument to a `&str`.
+/// Returns None if the pointer is null or the string is not valid UTF-8.
+unsafe fn arg_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Load a speller from a Hunspell dictionary file and affix file.
+/// Returns null if the files could not be loaded.
+/// The returned speller must be freed with `speller_free`.
+///
+/// # Safety
+/// `dic_path` and `aff_path` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn speller_new(
+    dic_path: *const c_char,
+    aff_path: *const c_char,
+) -> *mut SpellerHunspellDict {
+    let (Some(dic_path), Some(aff_path)) = (arg_str(dic_path), arg_str(aff_path)) else {
+        return ptr::null_mut();
+    };
+    match SpellerHunspellDict::new(Path::new(dic_path), Path::new(aff_path)) {
+        Ok(speller) => Box::into_raw(Box::new(speller)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Free a speller that was returned by `speller_new`.
+/// Passing null is allowed and does nothing.
+///
+/// # Safety
+/// `speller` must be null or a pointer returned by `speller_new` that has
+/// not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn speller_free(speller: *mut SpellerHunspellDict) {
+    if !speller.is_null() {
+        drop(Box::from_raw(speller));
+    }
+}
+
+/// Returns 1 if `word` is correctly spelled, 0 if it is not,
+/// and -1 if the arguments were invalid.
+///
+/// # Safety
+/// `speller` must be null or a live pointer returned by `speller_new`.
+/// `word` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn speller_spellcheck(
+    speller: *const SpellerHunspellDict,
+    word: *const c_char,
+) -> c_int {
+    let (Some(speller), Some(word)) = (speller.as_ref(), arg_str(word)) else {
+        return -1;
+    };
+    c_int::from(speller.spellcheck(word))
+}
+
+/// Store up to `max` suggestions for `word` in `out`, which must have
+/// room for `max` pointers. Returns the number of suggestions stored.
+/// The suggestions must be freed with `speller_free_suggestions`.
+///
+/// # Safety
+/// `speller` must be null or a live pointer returned by `speller_new`.
+/// `word` must be null or a valid NUL-terminated string.
+/// `out` must be null or point to an array of at least `max` pointers.
+#[no_mangle]
+pub unsafe extern "C" fn speller_suggest(
+    speller: *const SpellerHunspellDict,
+    word: *const c_char,
+    out: *mut *mut c_char,
+    max: usize,
+) -> usize {
+    let (Some(speller), Some(word)) = (speller.as_ref(), arg_str(word)) else {
+        return 0;
+    };
+    if out.is_null() {
+        return 0;
+    }
+    let mut count = 0;
+    for sugg in speller.suggestions(word, max) {
+        // Suggestions never contain NUL, but skip them rather than panic.
+        if let Ok(sugg) = CString::new(sugg) {
+            *out.add(count) = sugg.into_raw();
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Free the first `count` suggestions stored in `suggs` by `speller_suggest`.
+/// The array itself is owned by the caller.
+///
+/// # Safety
+/// `suggs` must be null or point to `count` strings from `speller_suggest`
+/// that have not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn speller_free_suggestions(suggs: *mut *mut c_char, count: usize) {
+    if suggs.is_null() {
+        return;
+    }
+    for i in 0..count {
+        let sugg = *suggs.add(i);
+        if !sugg.is_null() {
+            drop(CString::from_raw(sugg));
+            *suggs.add(i) = ptr::null_mut();
+        }
+    }
+}
+
+/// Accept `word` into the dictionary for the lifetime of `speller`.
+/// Returns 1 if the word was accepted, 0 if it was not,
+/// and -1 if the arguments were invalid.
+///
+/// # Safety
+/// `speller` must be null or a live pointer returned by `speller_new`.
+/// `word` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn speller_add_word(
+    speller: *mut SpellerHunspellDict,
+    word: *const c_char,
+) -> c_int {
+    let (Some(speller), Some(word)) = (speller.as_mut(), arg_str(word)) else {
+        return -1;
+    };
+    c_int::from(speller.add_word(word))
+}
+
+/// Accept `word` into the dictionary with the same affixes as `model`,
+/// for the lifetime of `speller`.
+/// Returns 1 if the word was accepted, 0 if it was not (for example because
+/// `model` is not in the dictionary), and -1 if the arguments were invalid.
+///
+/// # Safety
+/// `speller` must be null or a live pointer returned by `speller_new`.
+/// `word` and `model` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn speller_add_word_like(
+    speller: *mut SpellerHunspellDict,
+    word: *const c_char,
+    model: *const c_char,
+) -> c_int {
+    let (Some(speller), Some(word), Some(model)) =
+        (speller.as_mut(), arg_str(word), arg_str(model))
+    else {
+        return -1;
+    };
+    c_int::from(speller.add_word_like(word, model))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn load() -> *mut SpellerHunspellDict {
+        let dic = CString::new("../speller/tests/files/suggest.dic").unwrap();
+        let aff = CString::new("../speller/tests/files/suggest.aff").unwrap();
+        let speller = unsafe { speller_new(dic.as_ptr(), aff.as_ptr()) };
+        assert!(!speller.is_null());
+        speller
+    }
+
+    #[test]
+    fn test_capi_roundtrip() {
+        let speller = load();
+        let good = CString::new("banana").unwrap();
+        let bad = CString::new("bananana").unwrap();
+        let new = CString::new("bananarama").unwrap();
+        unsafe {
+            assert_eq!(1, speller_spellcheck(speller, good.as_ptr()));
+            assert_eq!(0, speller_spellcheck(speller, bad.as_ptr()));
+            assert_eq!(-1, speller_spellcheck(speller, ptr::null()));
+
+            let mut out = [ptr::null_mut(); 4];
+            let count = speller_suggest(speller, bad.as_ptr(), out.as_mut_ptr(), out.len());
+            assert!(count > 0);
+            assert_eq!(Ok("banana"), CStr::from_ptr(out[0]).to_str());
+            speller_free_suggestions(out.as_mut_ptr(), count);
+
+            assert_eq!(0, speller_spellcheck(speller, new.as_ptr()));
+            assert_eq!(1, speller_add_word(speller, new.as_ptr()));
+            assert_eq!(1, speller_spellcheck(speller, new.as_ptr()));
+
+            speller_free(speller);
+        }
+    }
+
+    #[test]
+    fn test_capi_bad_paths() {
+        let missing = CString::new("../speller/tests/files/missing.dic").unwrap();
+        let aff = CString::new("../speller/tests/files/suggest.aff").unwrap();
+        unsafe {
+            assert!(speller_new(missing.as_ptr(), aff.as_ptr()).is_null());
+            assert!(speller_new(ptr::null(), aff.as_ptr()).is_null());
+        }
+    }
+}
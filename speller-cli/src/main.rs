@@ -0,0 +1,172 @@
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::io::{stdin, BufRead};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use speller::{BreakPolicy, Speller, SpellerHunspellDict};
+
+#[derive(Parser)]
+#[clap(author, version, about)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the words that are not in the dictionary.
+    /// Exits with status 1 if there were any.
+    Check(WordArgs),
+    /// Print suggested corrections for each word.
+    Suggest {
+        #[clap(flatten)]
+        args: WordArgs,
+        /// Maximum number of suggestions per word.
+        #[clap(short, long, default_value_t = 9)]
+        max: usize,
+    },
+    /// Print errors from loading the dictionary, and how each word
+    /// is treated by the dictionary.
+    Analyze(WordArgs),
+}
+
+#[derive(Args)]
+struct WordArgs {
+    /// Hunspell dictionary file (.dic).
+    #[clap(short, long)]
+    dict: PathBuf,
+    /// Hunspell affix file (.aff). Defaults to the dictionary path with
+    /// its extension changed to .aff.
+    #[clap(short, long)]
+    aff: Option<PathBuf>,
+    /// How to check words with hyphens or other word breaks.
+    #[clap(long, value_enum, default_value_t = Breaks::Lenient)]
+    breaks: Breaks,
+    /// Treat typographic apostrophes such as ’ the same as the
+    /// apostrophe used in the dictionary.
+    #[clap(long)]
+    normalize_apostrophes: bool,
+    /// Keyboard rows separated by |, such as "azertyuiop|qsdfghjklm|wxcvbn",
+    /// to use for suggestions instead of the KEY of the affix file.
+    #[clap(long, value_name = "KEYS")]
+    keyboard: Option<String>,
+    /// Words to check. If none are given, words are read from stdin.
+    words: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Breaks {
+    /// Accept any word whose pieces between the breaks are valid.
+    Lenient,
+    /// Also reject empty pieces, as in "--word", and forbidden words
+    /// made of several pieces.
+    Strict,
+    /// Only accept words with breaks if they are in the dictionary as is.
+    FlagAll,
+}
+
+impl From<Breaks> for BreakPolicy {
+    fn from(breaks: Breaks) -> Self {
+        match breaks {
+            Breaks::Lenient => BreakPolicy::Lenient,
+            Breaks::Strict => BreakPolicy::Strict,
+            Breaks::FlagAll => BreakPolicy::FlagAll,
+        }
+    }
+}
+
+impl WordArgs {
+    fn load(&self) -> Result<SpellerHunspellDict> {
+        let aff = self
+            .aff
+            .clone()
+            .unwrap_or_else(|| self.dict.with_extension("aff"));
+        let mut builder = SpellerHunspellDict::builder()
+            .dictionary(&self.dict, &aff)
+            .break_policy(self.breaks.into())
+            .normalize_apostrophes(self.normalize_apostrophes);
+        if let Some(keys) = &self.keyboard {
+            builder = builder.keyboard(keys);
+        }
+        builder.build()
+    }
+
+    /// Return the words from the command line, or else all the
+    /// whitespace-separated words from stdin.
+    fn words(&self) -> Result<Vec<String>> {
+        if !self.words.is_empty() {
+            return Ok(self.words.clone());
+        }
+        let mut words = Vec::new();
+        for line in stdin().lock().lines() {
+            words.extend(line?.split_whitespace().map(str::to_string));
+        }
+        Ok(words)
+    }
+}
+
+fn check(args: &WordArgs) -> Result<bool> {
+    let speller = args.load()?;
+    let mut all_good = true;
+    for word in args.words()? {
+        if !speller.spellcheck(&word) {
+            println!("{}", word);
+            all_good = false;
+        }
+    }
+    Ok(all_good)
+}
+
+fn suggest(args: &WordArgs, max: usize) -> Result<()> {
+    let speller = args.load()?;
+    for word in args.words()? {
+        println!("{}: {}", word, speller.suggestions(&word, max).join(", "));
+    }
+    Ok(())
+}
+
+fn analyze(args: &WordArgs) -> Result<()> {
+    let speller = args.load()?;
+    let errors = speller.get_errors();
+    println!("{} errors in affix file", errors.len());
+    for e in errors {
+        println!("  {}", e);
+    }
+    for word in args.words()? {
+        let verdict = if speller.spellcheck(&word) {
+            "correct"
+        } else {
+            "misspelled"
+        };
+        println!("{}: {}", word, verdict);
+        for (style, variant) in [
+            ("lowercase", word.to_lowercase()),
+            ("uppercase", word.to_uppercase()),
+        ] {
+            if variant != word {
+                let verdict = if speller.spellcheck(&variant) {
+                    "correct"
+                } else {
+                    "misspelled"
+                };
+                println!("  {} {}: {}", style, variant, verdict);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<ExitCode> {
+    let args = Cli::parse();
+    match &args.command {
+        Command::Check(args) => {
+            if !check(args)? {
+                return Ok(ExitCode::from(1));
+            }
+        }
+        Command::Suggest { args, max } => suggest(args, *max)?,
+        Command::Analyze(args) => analyze(args)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,111 @@
+//! Python bindings for the speller crate.
+//!
+//! Build with `maturin develop` or `maturin build` in this directory.
+//! The module is called `speller` and has one class, `Speller`:
+//!
+//! ```python
+//! import speller
+//! s = speller.Speller("en_US.dic", "en_US.aff")
+//! s.spellcheck("word")
+//! s.suggestions("wrod", 9)
+//! ```
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+use speller::{Speller, SpellerHunspellDict};
+
+fn os_error(err: anyhow::Error) -> PyErr {
+    PyOSError::new_err(format!("{:#}", err))
+}
+
+/// A speller that loads Hunspell dictionaries
+#[pyclass(name = "Speller", module = "speller")]
+struct PySpeller {
+    inner: SpellerHunspellDict,
+}
+
+#[pymethods]
+impl PySpeller {
+    /// Load a speller from a Hunspell dictionary file and affix file.
+    #[new]
+    fn new(dictionary: PathBuf, affixes: PathBuf) -> PyResult<Self> {
+        let inner = SpellerHunspellDict::new(&dictionary, &affixes).map_err(os_error)?;
+        Ok(PySpeller { inner })
+    }
+
+    /// Load a speller from the contents of a dictionary file and affix file.
+    #[staticmethod]
+    fn from_text(dictionary: &str, affixes: &str) -> PyResult<Self> {
+        let inner = SpellerHunspellDict::from_text(dictionary, affixes)
+            .map_err(|err| PyValueError::new_err(format!("{:#}", err)))?;
+        Ok(PySpeller { inner })
+    }
+
+    /// Returns True if the word is in the dictionary, otherwise False.
+    fn spellcheck(&self, py: Python<'_>, word: &str) -> bool {
+        py.allow_threads(|| self.inner.spellcheck(word))
+    }
+
+    /// Returns a list of possible corrections to a misspelled word.
+    #[pyo3(signature = (word, max = 9))]
+    fn suggestions(&self, py: Python<'_>, word: &str, max: usize) -> Vec<String> {
+        py.allow_threads(|| self.inner.suggestions(word, max))
+    }
+
+    /// Accept `word` into the dictionary.
+    /// Returns False if the word could not be accepted.
+    fn add_word(&mut self, word: &str) -> bool {
+        self.inner.add_word(word)
+    }
+
+    /// Accept `word` into the dictionary with the same affixes as `model`.
+    /// Returns False if `model` is not in the dictionary.
+    fn add_word_like(&mut self, word: &str, model: &str) -> bool {
+        self.inner.add_word_like(word, model)
+    }
+
+    /// Returns the forms of `word` that `add_word_like(word, model)`
+    /// would accept, without changing the dictionary.
+    fn words_like(&self, word: &str, model: &str) -> Vec<String> {
+        self.inner.words_like(word, model)
+    }
+
+    /// Returns a dictionary word that would make a good model for
+    /// `add_word_like`, or None.
+    fn suggest_model(&self, word: &str) -> Option<String> {
+        self.inner.suggest_model(word)
+    }
+
+    /// Load words from a user dictionary file, and append words to it
+    /// when `add_word_to_user_dict` is called.
+    /// Returns the number of words loaded from the file.
+    fn set_user_dict(&mut self, path: PathBuf) -> PyResult<i32> {
+        self.inner.set_user_dict(&path).map_err(os_error)
+    }
+
+    /// Accept `word` into the dictionary and add it to the user dict file.
+    fn add_word_to_user_dict(&mut self, word: &str) -> PyResult<bool> {
+        self.inner.add_word_to_user_dict(word).map_err(os_error)
+    }
+
+    /// Accept `word` like `model` and add it to the user dict file.
+    fn add_word_like_to_user_dict(&mut self, word: &str, model: &str) -> PyResult<bool> {
+        self.inner
+            .add_word_like_to_user_dict(word, model)
+            .map_err(os_error)
+    }
+
+    /// Returns the errors found while parsing the affix file.
+    fn errors(&self) -> Vec<String> {
+        self.inner.get_errors()
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "speller")]
+fn speller_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySpeller>()?;
+    Ok(())
+}
@@ -5,6 +5,8 @@ use std::fs::read_to_string;
 use std::path::PathBuf;
 
 use speller::ngram::ngram as ngram_fn;
+#[cfg(feature = "system-hunspell")]
+use speller::SpellerHunspellLib;
 use speller::{Speller, SpellerHunspellDict};
 
 fn find_dict(name: &str) -> (PathBuf, PathBuf) {
@@ -79,6 +81,8 @@ fn load_speller(name: &str) -> impl Speller {
         }
         match SpellerHunspellDict::new(&dictpath, &affpath) {
             Ok(dict) => {
+                // Don't let the first all-caps word pay for the index.
+                dict.build_folded_index();
                 return dict;
             }
             Err(e) => eprintln!("{:#}", e),
@@ -268,9 +272,116 @@ fn spellcheck_es(c: &mut Criterion) {
     });
 }
 
-criterion_group!(spellcheck, spellcheck_en, spellcheck_es);
+fn spellcheck_de(c: &mut Criterion) {
+    let speller = load_speller("de_DE");
+
+    // Compound words, which the dictionary has to take apart to check,
+    // with 10% of them deliberately misspelled.
+    let words = load_sample_words("words_de.txt");
+    let words = words.lines().collect::<Vec<&str>>();
+
+    eprintln!("Benchmarking {} words", words.len());
+
+    c.bench_function("spellcheck_de_compounds", |b| {
+        b.iter(|| {
+            for word in &words {
+                black_box(speller.spellcheck(word));
+            }
+        })
+    });
+}
+
+// The benchmarks below compare this crate against the hunspell library
+// on the same words. Run them with
+// `cargo bench --features system-hunspell -- compare`.
+
+/// The word lists to compare the two spellers on, with their dictionaries.
+#[cfg(feature = "system-hunspell")]
+const COMPARE_LISTS: [(&str, &str); 3] = [
+    ("en_US", "words_en.txt"),
+    ("es_ES", "words_es.txt"),
+    ("de_DE", "words_de.txt"),
+];
+
+#[cfg(feature = "system-hunspell")]
+fn load_hunspell(name: &str) -> SpellerHunspellLib {
+    let (dictpath, affpath) = find_dict(name);
+    SpellerHunspellLib::new(&dictpath, &affpath).unwrap()
+}
+
+/// Time `f` with each speller, in a group called `group_name`.
+#[cfg(feature = "system-hunspell")]
+fn compare_engines(
+    c: &mut Criterion,
+    group_name: &str,
+    native: &dyn Speller,
+    hunspell: &dyn Speller,
+    f: impl Fn(&dyn Speller),
+) {
+    let mut group = c.benchmark_group(group_name);
+    group.sample_size(10);
+    group.bench_function("native", |b| b.iter(|| f(native)));
+    group.bench_function("hunspell", |b| b.iter(|| f(hunspell)));
+    group.finish();
+}
+
+#[cfg(feature = "system-hunspell")]
+fn compare_spellcheck(c: &mut Criterion) {
+    for (name, list) in COMPARE_LISTS {
+        let native = load_speller(name);
+        let hunspell = load_hunspell(name);
+        let words = load_sample_words(list);
+        let words = words.lines().collect::<Vec<&str>>();
+
+        compare_engines(
+            c,
+            &format!("compare_spellcheck_{}", name),
+            &native,
+            &hunspell,
+            |speller| {
+                for word in &words {
+                    black_box(speller.spellcheck(word));
+                }
+            },
+        );
+    }
+}
+
+#[cfg(feature = "system-hunspell")]
+fn compare_suggest(c: &mut Criterion) {
+    for (name, list) in COMPARE_LISTS {
+        let native = load_speller(name);
+        let hunspell = load_hunspell(name);
+        // Suggestions are slow, so only use a few of the misspelled words.
+        let words = load_sample_words(list);
+        let words = words
+            .lines()
+            .filter(|word| !native.spellcheck(word))
+            .take(5)
+            .collect::<Vec<&str>>();
+
+        compare_engines(
+            c,
+            &format!("compare_suggest_{}", name),
+            &native,
+            &hunspell,
+            |speller| {
+                for word in &words {
+                    black_box(speller.suggestions(word, 9));
+                }
+            },
+        );
+    }
+}
+
+criterion_group!(spellcheck, spellcheck_en, spellcheck_es, spellcheck_de);
 criterion_group!(casefold, casefold_loop);
 criterion_group!(ngram, ngram_loop);
 criterion_group!(load, load_fr, load_en, load_de, load_pt);
 criterion_group!(suggest, suggest_fr, suggest_en, suggest_de, suggest_pt);
+#[cfg(feature = "system-hunspell")]
+criterion_group!(compare, compare_spellcheck, compare_suggest);
+#[cfg(not(feature = "system-hunspell"))]
 criterion_main!(suggest, load, ngram, casefold, spellcheck);
+#[cfg(feature = "system-hunspell")]
+criterion_main!(suggest, load, ngram, casefold, spellcheck, compare);
@@ -0,0 +1,9 @@
+use std::env;
+
+fn main() {
+    if env::var_os("CARGO_FEATURE_SYSTEM_HUNSPELL").is_some() {
+        println!("cargo:rerun-if-env-changed=HUNSPELL_LIB");
+        let lib = env::var("HUNSPELL_LIB").unwrap_or_else(|_| "hunspell-1.7".to_string());
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+}
@@ -0,0 +1,132 @@
+//! Compare this crate's spellchecking against the system's hunspell library.
+//!
+//! Usage: `cargo run --features system-hunspell --example divergence --
+//! DIC [AFF] [CORPUS...]`
+//!
+//! Every whitespace-separated word in the corpus files is checked by both
+//! spellers, as well as randomly mutated words from the dictionary itself.
+//! A corpus can be running text or a word list with one word per line.
+//! Words where the two disagree are printed once each, followed by how
+//! many were accepted by only one of the two.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use speller::{Speller, SpellerHunspellDict, SpellerHunspellLib};
+
+/// How many mutated dictionary words to check
+const MUTATIONS: usize = 10000;
+
+/// Small xorshift generator so that runs are reproducible
+/// without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Apply one random edit to `word`: delete, insert, swap, or replace a
+/// character, or change its case.
+fn mutate(word: &str, rng: &mut Rng, alphabet: &[char]) -> String {
+    let mut chars: Vec<char> = word.chars().collect();
+    if chars.is_empty() {
+        return word.to_string();
+    }
+    let i = rng.below(chars.len());
+    match rng.below(5) {
+        0 => {
+            chars.remove(i);
+        }
+        1 => chars.insert(i, alphabet[rng.below(alphabet.len())]),
+        2 if i + 1 < chars.len() => chars.swap(i, i + 1),
+        3 => chars[i] = alphabet[rng.below(alphabet.len())],
+        _ => return word.to_uppercase(),
+    }
+    chars.into_iter().collect()
+}
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let dic = PathBuf::from(
+        args.next()
+            .context("usage: divergence DIC [AFF] [CORPUS...]")?,
+    );
+    let mut rest: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    let aff = match rest.first() {
+        Some(path) if path.extension().is_some_and(|ext| ext == "aff") => rest.remove(0),
+        _ => dic.with_extension("aff"),
+    };
+
+    let ours = SpellerHunspellDict::new(&dic, &aff)?;
+    let theirs = SpellerHunspellLib::new(&dic, &aff)?;
+
+    let mut words = Vec::new();
+    for corpus in &rest {
+        let text = read_to_string(corpus)
+            .with_context(|| format!("Could not read {}", corpus.display()))?;
+        words.extend(text.split_whitespace().map(str::to_string));
+    }
+
+    // The .dic file has already been decoded by `ours`, so re-read it
+    // lossily just to get stems to mutate.
+    let dic_text = String::from_utf8_lossy(&std::fs::read(&dic)?).into_owned();
+    let stems: Vec<&str> = dic_text
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split(['/', '\t']).next())
+        .filter(|stem| !stem.is_empty())
+        .collect();
+    let mut alphabet: Vec<char> = stems.iter().flat_map(|stem| stem.chars()).collect();
+    alphabet.sort_unstable();
+    alphabet.dedup();
+    if !stems.is_empty() && !alphabet.is_empty() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..MUTATIONS {
+            let stem = stems[rng.below(stems.len())];
+            words.push(mutate(stem, &mut rng, &alphabet));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    words.retain(|word| seen.insert(word.clone()));
+
+    let mut only_ours = 0;
+    let mut only_theirs = 0;
+    for word in &words {
+        let ours_ok = ours.spellcheck(word);
+        let theirs_ok = theirs.spellcheck(word);
+        if ours_ok != theirs_ok {
+            if ours_ok {
+                only_ours += 1;
+            } else {
+                only_theirs += 1;
+            }
+            println!(
+                "{}: speller says {}, hunspell says {}",
+                word,
+                if ours_ok { "correct" } else { "misspelled" },
+                if theirs_ok { "correct" } else { "misspelled" },
+            );
+        }
+    }
+    println!(
+        "{} of {} words diverged: {} accepted only by speller, {} only by hunspell",
+        only_ours + only_theirs,
+        words.len(),
+        only_ours,
+        only_theirs
+    );
+    Ok(())
+}
@@ -1,19 +1,46 @@
+use smallvec::SmallVec;
+
 /// `SuffixTrie` and `PrefixTrie` are very similar, but they differ in their
 /// internal logic and performance is important, so it was easier to make
 /// two separate structs than to make one that can do both.
+///
+/// Each node keeps only the children it actually has, with the bytes that
+/// lead to them in a sorted list next to them. Most nodes have just a few
+/// children, so this takes far less memory than a table of all possible
+/// bytes, while finding a child is still a short search in one small list.
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SuffixTrie<T> {
     end_here: Vec<T>,
+    /// Sorted. `more[i]` is the child for byte `keys[i]`.
+    keys: SmallVec<[u8; 8]>,
     more: Vec<SuffixTrie<T>>,
 }
 
 impl<T: Copy + Default> SuffixTrie<T> {
     pub fn clear(&mut self) {
         self.end_here.clear();
+        self.keys.clear();
         self.more.clear();
     }
 
+    fn child(&self, b: u8) -> Option<&Self> {
+        self.keys.binary_search(&b).ok().map(|i| &self.more[i])
+    }
+
+    fn child_mut(&mut self, b: u8) -> &mut Self {
+        let i = match self.keys.binary_search(&b) {
+            Ok(i) => i,
+            Err(i) => {
+                self.keys.insert(i, b);
+                self.more.insert(i, Self::default());
+                i
+            }
+        };
+        &mut self.more[i]
+    }
+
     pub fn insert(&mut self, suffix: &str, t: T) {
         let mut ptr = self;
         let sufb = suffix.as_bytes();
@@ -24,10 +51,7 @@ impl<T: Copy + Default> SuffixTrie<T> {
                 break;
             }
             pos -= 1;
-            if ptr.more.is_empty() {
-                ptr.more.resize_with(u8::MAX as usize, SuffixTrie::default);
-            }
-            ptr = &mut ptr.more[sufb[pos] as usize];
+            ptr = ptr.child_mut(sufb[pos]);
         }
     }
 
@@ -41,28 +65,51 @@ impl<T: Copy + Default> SuffixTrie<T> {
                     return true;
                 }
             }
-            if pos == 0 || ptr.more.is_empty() {
+            if pos == 0 {
                 break;
             }
             pos -= 1;
-            ptr = &ptr.more[wordb[pos] as usize];
+            match ptr.child(wordb[pos]) {
+                Some(child) => ptr = child,
+                None => break,
+            }
         }
         false
     }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrefixTrie<T> {
     end_here: Vec<T>,
+    /// Sorted. `more[i]` is the child for byte `keys[i]`.
+    keys: SmallVec<[u8; 8]>,
     more: Vec<PrefixTrie<T>>,
 }
 
 impl<T: Copy + Default> PrefixTrie<T> {
     pub fn clear(&mut self) {
         self.end_here.clear();
+        self.keys.clear();
         self.more.clear();
     }
 
+    fn child(&self, b: u8) -> Option<&Self> {
+        self.keys.binary_search(&b).ok().map(|i| &self.more[i])
+    }
+
+    fn child_mut(&mut self, b: u8) -> &mut Self {
+        let i = match self.keys.binary_search(&b) {
+            Ok(i) => i,
+            Err(i) => {
+                self.keys.insert(i, b);
+                self.more.insert(i, Self::default());
+                i
+            }
+        };
+        &mut self.more[i]
+    }
+
     pub fn insert(&mut self, prefix: &str, t: T) {
         let mut ptr = self;
         let preb = prefix.as_bytes();
@@ -72,10 +119,7 @@ impl<T: Copy + Default> PrefixTrie<T> {
                 ptr.end_here.push(t);
                 break;
             }
-            if ptr.more.is_empty() {
-                ptr.more.resize_with(u8::MAX as usize, PrefixTrie::default);
-            }
-            ptr = &mut ptr.more[preb[pos] as usize];
+            ptr = ptr.child_mut(preb[pos]);
             pos += 1;
         }
     }
@@ -90,10 +134,13 @@ impl<T: Copy + Default> PrefixTrie<T> {
                     return true;
                 }
             }
-            if pos == wordb.len() || ptr.more.is_empty() {
+            if pos == wordb.len() {
                 break;
             }
-            ptr = &ptr.more[wordb[pos] as usize];
+            match ptr.child(wordb[pos]) {
+                Some(child) => ptr = child,
+                None => break,
+            }
             pos += 1;
         }
         false
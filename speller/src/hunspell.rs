@@ -1,53 +1,247 @@
-use anyhow::{Context, Result};
-use caseless::default_case_fold_str;
+use anyhow::bail;
+#[cfg(feature = "std-fs")]
+use anyhow::Context;
+use anyhow::Result;
+#[cfg(feature = "std-fs")]
 use encoding::DecoderTrap;
-use fnv::FnvHashMap;
-use smallvec::SmallVec;
+use fnv::FnvHashSet;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Reverse;
+#[cfg(feature = "std-fs")]
 use std::fs::{read, read_to_string, File, OpenOptions};
-use std::io::Write;
+#[cfg(feature = "std-fs")]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(feature = "std-fs")]
 use std::path::{Path, PathBuf};
 use std::str::CharIndices;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+use std::time::Duration;
 use unicode_casing::CharExt;
-use unicode_titlecase::StrTitleCase;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
+mod accents;
 mod affixdata;
+mod builder;
+mod casing;
 mod compoundrule;
 mod condition;
+mod numbers;
 mod parse_aff;
 mod replacements;
 mod suggcollector;
 mod suggestions;
 mod wordflags;
+mod wordstore;
 
+use crate::hunspell::accents::AccentIndex;
 use crate::hunspell::affixdata::{AffixData, AffixFlag};
-use crate::hunspell::parse_aff::{determine_encoding, parse_affix_data};
+pub use crate::hunspell::builder::SpellerBuilder;
+use crate::hunspell::casing::Casing;
+#[cfg(feature = "std-fs")]
+use crate::hunspell::numbers::NumberFormat;
+#[cfg(feature = "std-fs")]
+use crate::hunspell::parse_aff::determine_encoding;
+#[cfg(feature = "system-hunspell")]
+pub(crate) use crate::hunspell::parse_aff::encoding_from_name;
+use crate::hunspell::parse_aff::parse_affix_data;
 use crate::hunspell::suggcollector::SuggCollector;
 use crate::hunspell::suggestions::{
     add_char_suggestions, capitalize_char_suggestions, delete_char_suggestions,
-    delete_doubled_pair_suggestions, delins_suggestions, move_char_suggestions, ngram_suggestions,
-    related_char_suggestions, replace_char_suggestions, split_word_suggestions,
-    split_word_with_dash_suggestions, swap_char_suggestions, wrong_key_suggestions,
+    delete_doubled_pair_suggestions, delins_suggestions, join_words_suggestions,
+    move_char_suggestions, ngram_suggestions, related_char_suggestions, replace_char_suggestions,
+    split_word_suggestions, split_word_with_dash_suggestions, swap_char_suggestions,
+    wrong_key_suggestions, wrong_layout_suggestions,
 };
 use crate::hunspell::wordflags::WordFlags;
-use crate::Speller;
+use crate::hunspell::wordstore::WordStore;
+use crate::{Speller, Suggestion, Suggestions};
 
 /// A limit on the recursive attempts to break a word at breakpoints such as -
-const MAX_WORD_BREAK_ATTEMPTS: u16 = 1000;
+const MAX_WORD_BREAK_ATTEMPTS: u32 = 1000;
+
+/// A limit on the pieces tried while dividing a word into compound parts.
+/// A long blob of text that happens to contain many dictionary words
+/// could otherwise keep the search busy for a very long time.
+const MAX_COMPOUND_ATTEMPTS: u32 = 10_000;
+
+/// Words longer than this are not looked up. It's the same limit that
+/// Hunspell has.
+const MAX_WORD_LENGTH: usize = 100;
+
+/// No more than this many suggestion attempts from any one source.
+const MAX_SUGGESTS_PER_SOURCE: usize = 1000;
+
+/// The default limits on the ngram and delins searches, which score
+/// every dictionary word, then look closer at the best roots and keep
+/// the best of their forms.
+const MAX_NGRAM_ROOTS: usize = 100;
+const MAX_NGRAM_SUGG: usize = 20;
+const MAX_DELINS_ROOTS: usize = 100;
+const MAX_DELINS_SUGG: usize = 20;
+
+/// How many letters a word must have in common at the end with a
+/// dictionary word for `suggest_model` to offer it.
+const MIN_MODEL_ENDING: usize = 2;
+
+/// The default symbols that may follow a number, as in "50%" or "12°C".
+/// See `SpellerHunspellDict::set_number_suffixes`.
+const NUMBER_SUFFIXES: [&str; 7] = ["%", "\u{2030}", "°", "°C", "°F", "\u{2032}", "\u{2033}"];
+
+/// Characters that are used as apostrophes. When apostrophes are
+/// normalized, they all count as the one that the dictionary uses.
+const APOSTROPHES: [char; 4] = ['\'', '\u{2019}', '\u{2018}', '\u{2bc}'];
 
 /// A speller that loads Hunspell dictionaries
+///
+/// If a misspelled word is two words with a space between them, and the
+/// dictionary allows compound words, the suggestions include the words
+/// joined together.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpellerHunspellDict {
     affix_data: AffixData,
+    #[cfg(feature = "std-fs")]
     user_dict: Option<PathBuf>,
-    words: FnvHashMap<String, SmallVec<[WordInfo; 1]>>,
+    words: WordStore<WordInfo>,
     // An index of case-folded words, to help with spell checking of
     // all-caps words and phrases. It combines all the WordInfo of the
     // original words, so that for example both "ROSE'S" (name) and
     // "ROSES" (flower) are valid in all caps.
-    folded_words: FnvHashMap<String, SmallVec<[WordInfo; 1]>>,
+    // It's nearly as big as `words` but is rarely needed, so it's only
+    // built when it's first used. See `build_folded_index`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    folded_words: OnceLock<WordStore<WordInfo>>,
+    // An index of the words with accents, by how they look without them,
+    // for suggestions. It's only built when it's first used.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    accent_index: OnceLock<AccentIndex>,
+    // The words that were added after loading the dictionary, either
+    // with add_word and add_word_like or from the user dict.
+    user_words: FnvHashSet<String>,
+    break_policy: BreakPolicy,
+    // The apostrophe that the dictionary's words use, if the other
+    // apostrophes should be changed to it.
+    apostrophe: Option<char>,
+    suggestion_time_limit: Option<Duration>,
+    // Symbols that may follow a number, such as "%".
+    number_suffixes: Vec<String>,
+    // The keyboard layout to use instead of the affix file's KEY.
+    keyboard: Option<String>,
+    // Pairs of keyboard layouts that words may have been typed in by
+    // mistake, as the characters on the same key in each.
+    layout_pairs: Vec<Vec<(char, char)>>,
+    config: SpellerConfig,
+    suggestion_config: SuggestionConfig,
+}
+
+/// Limits on how much work the speller does for one word. The defaults
+/// keep checking fast even for strange input, such as a long blob of
+/// letters. Dictionary developers may want to raise them in tests, and
+/// applications that need quick answers may want to lower them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpellerConfig {
+    /// How many times to try breaking a word at its word breaks, such
+    /// as the `-` in "blood-brothers". The default is 1000.
+    pub max_word_break_attempts: u32,
+    /// How many pieces to try while dividing a word into compound parts.
+    /// The default is 10000.
+    pub max_compound_attempts: u32,
+    /// Words longer than this many characters, such as pasted URLs or
+    /// blobs of base64, are not words at all. `spellcheck` accepts them
+    /// without looking them up, and no suggestions are searched for.
+    /// 0 means no limit. The default is 100.
+    pub max_word_length: usize,
+    /// How many candidates to try from each source of suggestions, such
+    /// as swapping letters or the REP table. The default is 1000.
+    pub max_suggests_per_source: usize,
+}
+
+impl Default for SpellerConfig {
+    fn default() -> Self {
+        SpellerConfig {
+            max_word_break_attempts: MAX_WORD_BREAK_ATTEMPTS,
+            max_compound_attempts: MAX_COMPOUND_ATTEMPTS,
+            max_word_length: MAX_WORD_LENGTH,
+            max_suggests_per_source: MAX_SUGGESTS_PER_SOURCE,
+        }
+    }
+}
+
+/// Which sources of suggestions to try, and how much work each may do.
+/// The defaults give the best suggestions. Applications with huge
+/// dictionaries that need suggestions quickly can use
+/// `SuggestionConfig::fast` or tune the limits themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuggestionConfig {
+    /// The sources not to try, by the names that `suggestions_detailed`
+    /// reports, such as "swap_char" or "ngram". The default is none.
+    pub disabled_sources: Vec<String>,
+    /// How many of the dictionary words with the best ngram scores to
+    /// look at with all their affixes. The default is 100.
+    pub max_ngram_roots: usize,
+    /// How many of the forms of those words to offer. The default is 20.
+    pub max_ngram_forms: usize,
+    /// Like `max_ngram_roots`, for the delins search. The default is 100.
+    pub max_delins_roots: usize,
+    /// Like `max_ngram_forms`, for the delins search. The default is 20.
+    pub max_delins_forms: usize,
+}
+
+impl Default for SuggestionConfig {
+    fn default() -> Self {
+        SuggestionConfig {
+            disabled_sources: Vec::new(),
+            max_ngram_roots: MAX_NGRAM_ROOTS,
+            max_ngram_forms: MAX_NGRAM_SUGG,
+            max_delins_roots: MAX_DELINS_ROOTS,
+            max_delins_forms: MAX_DELINS_SUGG,
+        }
+    }
+}
+
+impl SuggestionConfig {
+    /// Settings that skip the ngram and delins searches. Those go
+    /// through the whole dictionary, which takes a while for huge ones,
+    /// and they are only tried when nothing else found a suggestion.
+    pub fn fast() -> Self {
+        SuggestionConfig {
+            disabled_sources: vec!["ngram".to_string(), "delins".to_string()],
+            ..SuggestionConfig::default()
+        }
+    }
+
+    pub fn is_enabled(&self, source: &str) -> bool {
+        !self.disabled_sources.iter().any(|s| s == source)
+    }
+}
+
+/// How to treat words that contain word breaks, such as the `-` in
+/// "blood-brothers". The break strings come from the BREAK directive
+/// in the affix file, or are `-` at the start, middle, or end of a word
+/// if the affix file doesn't have one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BreakPolicy {
+    /// Accept a word if all the pieces between the breaks are valid.
+    /// This also accepts runs of breaks, as in "----angle---".
+    #[default]
+    Lenient,
+    /// Accept a word if all the pieces between the breaks are valid,
+    /// but reject empty pieces and repeated breaks at the start or end
+    /// of the word. Also reject the word if any of the hyphenated
+    /// combinations inside it is a forbidden word.
+    Strict,
+    /// Never accept a word because of its breaks. Only words that are
+    /// in the dictionary with their breaks are accepted.
+    FlagAll,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct WordInfo {
     word_flags: WordFlags,
     affix_flags: Vec<AffixFlag>,
@@ -70,6 +264,17 @@ impl WordInfo {
     }
 }
 
+/// One word from a user dict file.
+#[cfg(feature = "std-fs")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct UserDictEntry<'a> {
+    pub word: String,
+    /// Affix flags given as `word/FLAGS`
+    pub flags: &'a str,
+    /// Existing word whose affixes this word takes, given as `like:MODEL`
+    pub model: Option<&'a str>,
+}
+
 /// A word's place in the word compounding sequence.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Compound {
@@ -118,7 +323,7 @@ pub enum CapStyle {
     Mixed,
     Neutral,
     // Folded is a special category for words that have been run
-    // through caseless::default_case_fold_str.
+    // through Casing::fold.
     Folded,
     // Decapitalized is a special category for Capitalized words that
     // have been lowercased.
@@ -127,6 +332,14 @@ pub enum CapStyle {
 
 impl CapStyle {
     fn from_str(word: &str) -> Self {
+        if word.is_ascii() {
+            CapStyle::from_ascii(word.as_bytes())
+        } else {
+            CapStyle::from_chars(word)
+        }
+    }
+
+    fn from_chars(word: &str) -> Self {
         let mut iter = word.chars();
         let c1 = match iter.next() {
             Some(c1) => c1,
@@ -141,7 +354,9 @@ impl CapStyle {
                 }
             }
             CapStyle::Lowercase
-        } else if c1.is_uppercase() {
+        } else if c1.is_uppercase() || c1.is_titlecase() {
+            // A titlecase digraph such as ǈ is the capital of its pair of
+            // letters, so it can start a capitalized or an all-caps word.
             let mut seen_ucase = false;
             let mut seen_lcase = false;
             for c in iter {
@@ -155,20 +370,39 @@ impl CapStyle {
             }
             if seen_ucase && seen_lcase {
                 CapStyle::Mixed
-            } else if seen_lcase {
+            } else if seen_lcase || (!seen_ucase && c1.is_titlecase()) {
                 CapStyle::Capitalized
             } else {
                 CapStyle::AllCaps
             }
-        } else if c1.is_titlecase() {
-            for c in iter {
-                if c.is_uppercase() || c.is_titlecase() {
-                    return CapStyle::Mixed;
-                }
+        } else {
+            CapStyle::from_chars(&word[c1.len_utf8()..])
+        }
+    }
+
+    // The same as from_chars, for words that are all ASCII. Most words
+    // are, and this avoids decoding chars and looking up their case.
+    fn from_ascii(word: &[u8]) -> Self {
+        let Some(start) = word.iter().position(u8::is_ascii_alphabetic) else {
+            return CapStyle::Neutral;
+        };
+        let rest = &word[start + 1..];
+        if word[start].is_ascii_lowercase() {
+            if rest.iter().any(u8::is_ascii_uppercase) {
+                CapStyle::Mixed
+            } else {
+                CapStyle::Lowercase
             }
-            CapStyle::Capitalized
         } else {
-            CapStyle::from_str(&word[c1.len_utf8()..])
+            let seen_ucase = rest.iter().any(u8::is_ascii_uppercase);
+            let seen_lcase = rest.iter().any(u8::is_ascii_lowercase);
+            if seen_ucase && seen_lcase {
+                CapStyle::Mixed
+            } else if seen_lcase {
+                CapStyle::Capitalized
+            } else {
+                CapStyle::AllCaps
+            }
         }
     }
 
@@ -196,6 +430,126 @@ impl CapStyle {
     }
 }
 
+/// Add a word's entry to the index of case-folded words.
+/// If there is no room for it, the word is only found in its own case.
+fn add_folded(folded_words: &mut WordStore<WordInfo>, word: &str, winfo: WordInfo, casing: Casing) {
+    // Forbidden words are case sensitive, so don't add them
+    // to the case-folded dictionary.
+    if winfo
+        .word_flags
+        .intersects(WordFlags::Forbidden | WordFlags::KeepCase)
+    {
+        return;
+    }
+    let _ = folded_words.push(&casing.fold(word), winfo);
+}
+
+/// Add a word to the index of accented words, unless it's one that
+/// shouldn't be suggested.
+fn add_accented(accent_index: &mut AccentIndex, word: &str, winfo: &WordInfo, casing: Casing) {
+    if winfo
+        .word_flags
+        .intersects(WordFlags::Forbidden | WordFlags::NoSuggest)
+    {
+        return;
+    }
+    accent_index.add(word, casing);
+}
+
+/// Compose letters and the accents after them into single characters
+/// (NFC), so that an "é" typed as "e" plus a combining accent matches
+/// the "é" in the dictionary.
+fn compose(word: &str) -> Cow<'_, str> {
+    if is_nfc(word) {
+        Cow::Borrowed(word)
+    } else {
+        Cow::Owned(word.nfc().collect())
+    }
+}
+
+/// The capitalization of a word, worked out once so that the stages of
+/// spellchecking don't each have to look at the word again.
+/// The other-case forms of the word are only made if a stage needs them.
+#[derive(Clone, Copy, Debug)]
+struct CaseInfo<'a> {
+    word: &'a str,
+    caps: CapStyle,
+    ascii: bool,
+    casing: Casing,
+}
+
+impl<'a> CaseInfo<'a> {
+    fn new(word: &'a str, casing: Casing) -> Self {
+        CaseInfo {
+            word,
+            caps: CapStyle::from_str(word),
+            ascii: word.is_ascii(),
+            casing,
+        }
+    }
+
+    fn lowercase(&self) -> String {
+        self.casing.lowercase(self.word)
+    }
+
+    fn folded(&self) -> String {
+        self.casing.fold(self.word)
+    }
+
+    /// Returns the word with its first letter in titlecase, if that's
+    /// different from how it's written. That's the case for an uppercase
+    /// digraph such as Ǉ, whose titlecase form is ǈ.
+    fn titlecase(&self) -> Option<String> {
+        if self.ascii {
+            return None;
+        }
+        let titled = self.casing.titlecase(self.word);
+        (titled != self.word).then_some(titled)
+    }
+}
+
+/// What the recursive search for the compound parts of one word keeps
+/// track of.
+struct CompoundSearch {
+    /// How many more pieces may be tried.
+    budget: u32,
+    /// By byte offset: whether the rest of the word from there is known
+    /// not to divide into compound parts.
+    dead_ends: Vec<bool>,
+}
+
+impl CompoundSearch {
+    fn new(word: &str, budget: u32) -> Self {
+        CompoundSearch {
+            budget,
+            dead_ends: vec![false; word.len()],
+        }
+    }
+
+    /// Returns false if the budget has run out.
+    fn attempt(&mut self) -> bool {
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+        true
+    }
+}
+
+/// What the recursive search through the word breaks of one word keeps
+/// track of.
+#[derive(Default)]
+struct BreakSearch {
+    /// How many pieces have been checked.
+    attempts: u32,
+    /// Pieces that are known to be bad, by where they are in memory.
+    /// All the pieces are slices of the same word, so this identifies
+    /// them by their place in it. Different breaks can lead to the same
+    /// piece, such as `-foo-` with the breaks `^-` and `-$`, and this
+    /// keeps it from being checked (with compounding and all) each time.
+    failed: FnvHashSet<(usize, usize)>,
+}
+
 #[derive(Clone, Copy, Debug)]
 enum StrictMode {
     Strict,
@@ -205,7 +559,20 @@ enum StrictMode {
 
 impl SpellerHunspellDict {
     /// Returns a Speller that uses a Hunspell-format dictionary and affix file.
+    /// Use `builder` to load it with other options.
+    #[cfg(feature = "std-fs")]
     pub fn new(dictionary: &Path, affixes: &Path) -> Result<Self> {
+        Self::builder().dictionary(dictionary, affixes).build()
+    }
+
+    /// Returns a builder, to load a dictionary together with a user
+    /// dict and other options.
+    pub fn builder() -> SpellerBuilder {
+        SpellerBuilder::default()
+    }
+
+    #[cfg(feature = "std-fs")]
+    fn load(dictionary: &Path, affixes: &Path) -> Result<Self> {
         let affixes_bytes = read(affixes)
             .map_err(anyhow::Error::from)
             .with_context(|| format!("Could not read affix data from {}", affixes.display()))?;
@@ -214,66 +581,208 @@ impl SpellerHunspellDict {
             .decode(&affixes_bytes, DecoderTrap::Strict)
             .map_err(anyhow::Error::msg)
             .with_context(|| format!("Could not read affix data from {}", affixes.display()))?;
-        let affix_data = parse_affix_data(&affixes_text)?;
 
-        let mut dict = SpellerHunspellDict {
+        let mut dict = Self::with_affixes(&affixes_text)?;
+        // Without a LANG directive, the file name may say the language.
+        if dict.affix_data.lang.is_none() {
+            if let Some(name) = affixes.file_stem().and_then(|name| name.to_str()) {
+                dict.affix_data.number_format = NumberFormat::from_lang(name);
+            }
+        }
+
+        // Big dictionaries are read a line at a time, so that the whole
+        // file doesn't have to be in memory next to the words.
+        let mut reader = File::open(dictionary)
+            .map(BufReader::new)
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("Could not read words from {}", dictionary.display()))?;
+        let mut bytes = Vec::new();
+        let mut counted = false;
+        loop {
+            bytes.clear();
+            let len = reader
+                .read_until(b'\n', &mut bytes)
+                .map_err(anyhow::Error::from)
+                .with_context(|| format!("Could not read words from {}", dictionary.display()))?;
+            if len == 0 {
+                break;
+            }
+            let line = encoding
+                .decode(&bytes, DecoderTrap::Strict)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("Could not read words from {}", dictionary.display()))?;
+            dict.add_dic_line(&line, &mut counted);
+        }
+        Ok(dict)
+    }
+
+    /// Returns a Speller that uses the contents of a Hunspell-format
+    /// dictionary and affix file. The contents must already be decoded
+    /// from whatever encoding the files specify.
+    /// This does not touch the filesystem, so it can be used on targets
+    /// such as wasm32-unknown-unknown, with dictionaries embedded by
+    /// `include_str!`, and in tests that make up small dictionaries.
+    pub fn from_text(dictionary: &str, affixes: &str) -> Result<Self> {
+        Self::from_strings(affixes, dictionary)
+    }
+
+    /// Returns a Speller for the contents `aff` of an affix file and `dic`
+    /// of a dictionary file, in the order that Hunspell itself takes them.
+    /// Like `from_text`, this does not touch the filesystem.
+    pub fn from_strings(aff: &str, dic: &str) -> Result<Self> {
+        let mut dict = Self::with_affixes(aff)?;
+        let mut counted = false;
+        for line in dic.lines() {
+            dict.add_dic_line(line, &mut counted);
+        }
+        Ok(dict)
+    }
+
+    /// Returns a dictionary without words, for the affix file `affixes`.
+    fn with_affixes(affixes: &str) -> Result<Self> {
+        let affix_data = parse_affix_data(affixes)?;
+
+        Ok(SpellerHunspellDict {
             affix_data,
+            #[cfg(feature = "std-fs")]
             user_dict: None,
-            words: FnvHashMap::default(),
-            folded_words: FnvHashMap::default(),
+            words: WordStore::default(),
+            folded_words: OnceLock::new(),
+            accent_index: OnceLock::new(),
+            user_words: FnvHashSet::default(),
+            break_policy: BreakPolicy::default(),
+            apostrophe: None,
+            suggestion_time_limit: None,
+            number_suffixes: NUMBER_SUFFIXES.map(str::to_string).to_vec(),
+            keyboard: None,
+            layout_pairs: Vec::new(),
+            config: SpellerConfig::default(),
+            suggestion_config: SuggestionConfig::default(),
+        })
+    }
+
+    /// Add the word on a line of a .dic file. The first line with
+    /// anything on it is normally the number of words, which is only
+    /// used to make room for them; `counted` is set once it's been seen.
+    /// Dictionaries that leave it out start with a word instead.
+    fn add_dic_line(&mut self, line: &str, counted: &mut bool) {
+        let Some(line) = Self::dic_line_entry(line) else {
+            return;
         };
+        if !*counted {
+            *counted = true;
+            if let Ok(wordcount) = line.parse::<usize>() {
+                self.words.reserve(wordcount);
+                return;
+            }
+        }
+        let (word, _morphs) = Self::split_morphological_fields(line);
+        let (word, flagstr) = word.split_once('/').unwrap_or((word, ""));
+        // The words go through the same steps as the user's words, so
+        // that they look the same: composing accents, then ICONV.
+        // Dictionaries with ICONV rules may keep their words in another
+        // form on purpose, such as Korean ones that spell out the letters
+        // of each syllable, and ICONV takes them back to that form.
+        let word = compose(word.trim());
+        if self.affix_data.iconv.is_empty() {
+            self.insert_word(&word, flagstr);
+        } else {
+            let word = self.affix_data.iconv.conv(&word);
+            self.insert_word(&word, flagstr);
+        }
+    }
 
-        let dict_bytes = read(dictionary)
-            .map_err(anyhow::Error::from)
-            .with_context(|| format!("Could not read words from {}", dictionary.display()))?;
-        let dict_text = encoding
-            .decode(&dict_bytes, DecoderTrap::Strict)
-            .map_err(anyhow::Error::msg)
-            .with_context(|| format!("Could not read words from {}", dictionary.display()))?;
+    /// Returns the dictionary entries of `model` that a word accepted
+    /// like it should copy. The model is looked up as given, then
+    /// case-insensitively.
+    fn model_homonyms(&self, model: &str) -> Vec<WordInfo> {
+        self.words
+            .get(model)
+            .or_else(|| self.folded_words().get(self.casing().fold(model).as_str()))
+            .map(|homonyms| {
+                homonyms
+                    .iter()
+                    .filter(|winfo| !winfo.word_flags.intersects(WordFlags::Forbidden))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        // The first line is the number of words
-        let mut lines = dict_text.lines();
-        let wordcount: usize = lines
-            .next()
-            .unwrap_or("0")
-            .trim_matches('\u{feff}')
-            .parse()?;
-        dict.words.reserve(wordcount);
-        dict.folded_words.reserve(wordcount);
-
-        for line in lines {
-            if line.starts_with('\t') {
-                // comment
-                continue;
+    /// Add a word with the flags from a .dic file line.
+    /// Returns false if the word is empty or there is no room for it.
+    fn insert_word(&mut self, word: &str, flagstr: &str) -> bool {
+        // If parsing the flags fails, just ignore them.
+        // Printing errors isn't worth it.
+        // TODO: maybe collect errors in the struct.
+        let affix_flags = self.affix_data.parse_flags(flagstr).unwrap_or_default();
+        self.insert_word_with_affix_flags(word, affix_flags)
+    }
+
+    /// Add a word with the given affix flags.
+    /// Returns false if the word is empty or there is no room for it.
+    fn insert_word_with_affix_flags(&mut self, word: &str, affix_flags: Vec<AffixFlag>) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        let word_flags = self.affix_data.special_flags.word_flags(&affix_flags);
+        let winfo = WordInfo::new(word_flags, affix_flags);
+        if self.words.push(word, winfo.clone()).is_err() {
+            return false;
+        }
+        // Once the index is built it has to be kept up to date.
+        if let Some(accent_index) = self.accent_index.get_mut() {
+            add_accented(accent_index, word, &winfo, self.affix_data.casing);
+        }
+        if let Some(folded_words) = self.folded_words.get_mut() {
+            add_folded(folded_words, word, winfo, self.affix_data.casing);
+        }
+        true
+    }
+
+    /// Build the index of case-folded words now, rather than when it's
+    /// first needed. Checking a word in all caps needs it, and building
+    /// it takes about as long as loading the words did, so this is
+    /// useful for applications that would rather wait at the start and
+    /// for benchmarks that shouldn't time it.
+    pub fn build_folded_index(&self) {
+        self.folded_words();
+    }
+
+    fn folded_words(&self) -> &WordStore<WordInfo> {
+        self.folded_words.get_or_init(|| {
+            let mut folded_words = WordStore::default();
+            folded_words.reserve(self.words.len());
+            for (word, homonyms) in self.words.iter() {
+                for winfo in homonyms {
+                    add_folded(
+                        &mut folded_words,
+                        word,
+                        winfo.clone(),
+                        self.affix_data.casing,
+                    );
+                }
             }
-            let (word, _morphs) = Self::split_morphological_fields(line);
-            let (word, flagstr) = word.split_once('/').unwrap_or((word, ""));
-            // If parsing the flags fails, just ignore them.
-            // Printing errors isn't worth it.
-            // TODO: maybe collect errors in the struct.
-            let affix_flags = dict.affix_data.parse_flags(flagstr).unwrap_or_default();
-            let word = word.trim();
-            if !word.is_empty() {
-                let word_flags = dict.affix_data.special_flags.word_flags(&affix_flags);
-                let winfo = WordInfo::new(word_flags, affix_flags);
-                dict.words
-                    .entry(word.to_string())
-                    .or_default()
-                    .push(winfo.clone());
-
-                // Forbidden words are case sensitive, so don't add them
-                // to the case-folded dictionary.
-                if !winfo
-                    .word_flags
-                    .intersects(WordFlags::Forbidden | WordFlags::KeepCase)
-                {
-                    let folded = default_case_fold_str(word);
-                    dict.folded_words.entry(folded).or_default().push(winfo);
+            folded_words
+        })
+    }
+
+    fn accent_index(&self) -> &AccentIndex {
+        self.accent_index.get_or_init(|| {
+            let mut accent_index = AccentIndex::default();
+            for (word, homonyms) in self.words.iter() {
+                for winfo in homonyms {
+                    add_accented(&mut accent_index, word, winfo, self.affix_data.casing);
                 }
             }
-        }
+            accent_index
+        })
+    }
 
-        Ok(dict)
+    /// Returns the language of the dictionary, such as "tr_TR", if the
+    /// affix file gives one with the LANG directive.
+    pub fn language(&self) -> Option<&str> {
+        self.affix_data.lang.as_deref()
     }
 
     #[must_use]
@@ -281,6 +790,178 @@ impl SpellerHunspellDict {
         self.affix_data.errors.clone()
     }
 
+    /// Returns the root words of the dictionary that start with `prefix`,
+    /// for example for autocompletion. These are the words as listed in
+    /// the dictionary or added later, without affixes. Roots that aren't
+    /// words on their own, because they need an affix or only appear in
+    /// compounds, are left out, as are forbidden words and words that
+    /// shouldn't be suggested.
+    pub fn words_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = String> + 'a {
+        let conv_prefix = self.conv_input(prefix);
+        let unusable = WordFlags::Forbidden
+            | WordFlags::NoSuggest
+            | WordFlags::NeedAffix
+            | WordFlags::OnlyInCompound;
+        self.words
+            .iter()
+            .filter(move |(word, homonyms)| {
+                word.starts_with(&conv_prefix)
+                    && homonyms
+                        .iter()
+                        .any(|winfo| !winfo.word_flags.intersects(unusable))
+            })
+            .map(move |(word, _)| self.conv_output(word, prefix))
+    }
+
+    /// Returns the root words of the dictionary that have the affix flag
+    /// `flag`, written as in the affix file, for example to see which
+    /// words an affix applies to. All roots with the flag are included,
+    /// even forbidden ones.
+    pub fn words_with_flag(&self, flag: &str) -> Result<impl Iterator<Item = String> + '_> {
+        let [flag] = self.affix_data.parse_flags(flag)?[..] else {
+            bail!("Expected a single affix flag, got {}", flag);
+        };
+        Ok(self
+            .words
+            .iter()
+            .filter(move |(_, homonyms)| homonyms.iter().any(|winfo| winfo.has_affix_flag(flag)))
+            .map(|(word, _)| self.affix_data.oconv.conv(word)))
+    }
+
+    /// Set how words with word breaks (usually hyphens) are checked.
+    /// The default is `BreakPolicy::Lenient`.
+    pub fn set_break_policy(&mut self, policy: BreakPolicy) {
+        self.break_policy = policy;
+    }
+
+    /// Stop looking for more suggestions after `limit` has passed.
+    /// This is the budget that `suggestions_with_budget` gets for every
+    /// search, and a shorter one given to it still applies.
+    /// The default is no limit. A limit can't be used on targets that
+    /// don't have a clock, such as wasm32-unknown-unknown.
+    pub fn set_suggestion_time_limit(&mut self, limit: Option<Duration>) {
+        self.suggestion_time_limit = limit;
+    }
+
+    /// Set the symbols that may be written right after a number, such as
+    /// the `%` in "50%". The default is `%`, `‰`, `°`, `°C`, `°F`, `′`
+    /// and `″`. With no suffixes, only plain numbers are accepted.
+    pub fn set_number_suffixes(&mut self, suffixes: &[&str]) {
+        self.number_suffixes = suffixes.iter().map(|s| s.to_string()).collect();
+    }
+
+    /// Suggest replacing letters with the ones next to them on this
+    /// keyboard, instead of on the one given by the affix file's KEY.
+    /// `keys` has the same format as KEY: the rows of the keyboard,
+    /// separated by `|`, as in "azertyuiop|qsdfghjklm|wxcvbn". With None,
+    /// the affix file's KEY is used again.
+    pub fn set_keyboard(&mut self, keys: Option<&str>) {
+        self.keyboard = keys.map(str::to_string);
+    }
+
+    /// The keyboard layout that the suggestions use, if any.
+    pub fn keyboard(&self) -> Option<&str> {
+        self.keyboard
+            .as_deref()
+            .or(self.affix_data.keyboard_string.as_deref())
+    }
+
+    /// Suggest what a word would be if it was typed with the keyboard
+    /// set to the wrong layout, such as "привет" for "ghbdtn". Each pair
+    /// is the characters on the keys of two layouts, in the same order,
+    /// as in ("qwerty", "йцукен"). Words are changed both ways. These
+    /// suggestions come before the others. The default is no pairs.
+    pub fn set_layout_pairs(&mut self, pairs: &[(&str, &str)]) {
+        self.layout_pairs = pairs
+            .iter()
+            .map(|(a, b)| a.chars().zip(b.chars()).collect())
+            .collect();
+    }
+
+    /// Set the limits on how much work is done for one word.
+    pub fn set_config(&mut self, config: SpellerConfig) {
+        self.config = config;
+    }
+
+    pub fn config(&self) -> SpellerConfig {
+        self.config
+    }
+
+    pub fn suggestion_config(&self) -> &SuggestionConfig {
+        &self.suggestion_config
+    }
+
+    /// How the dictionary's language changes the case of letters.
+    pub(crate) fn casing(&self) -> Casing {
+        self.affix_data.casing
+    }
+
+    /// Treat the typographic apostrophes, such as `’`, and the plain `'`
+    /// as the same character, so that words match the dictionary no
+    /// matter which one they use. Suggestions get the same apostrophe as
+    /// the misspelled word. This is off by default.
+    pub fn set_normalize_apostrophes(&mut self, normalize: bool) {
+        self.apostrophe = normalize.then(|| self.dictionary_apostrophe());
+    }
+
+    // Find the apostrophe that is used in the most dictionary words.
+    // If there are none, this returns the plain `'`.
+    fn dictionary_apostrophe(&self) -> char {
+        let count = |apo: char| self.words.keys().filter(|w| w.contains(apo)).count();
+        // max_by_key returns the last maximum, so go in reverse to make
+        // ties go to the plain apostrophe.
+        APOSTROPHES
+            .into_iter()
+            .rev()
+            .max_by_key(|&apo| count(apo))
+            .unwrap_or('\'')
+    }
+
+    // Prepare a word from the user for lookup, by composing accents,
+    // applying ICONV, and normalizing apostrophes.
+    fn conv_input(&self, word: &str) -> String {
+        let word = self.affix_data.iconv.conv(&compose(word.trim()));
+        match self.apostrophe {
+            Some(apo) if word.contains(APOSTROPHES) => word
+                .chars()
+                .map(|c| if APOSTROPHES.contains(&c) { apo } else { c })
+                .collect(),
+            _ => word,
+        }
+    }
+
+    // Prepare a dictionary word for the user, by applying OCONV and
+    // using the same apostrophe as the user's word `original`.
+    fn conv_output(&self, word: &str, original: &str) -> String {
+        let word = self.affix_data.oconv.conv(word);
+        if let Some(apo) = self.apostrophe {
+            if let Some(user_apo) = original.chars().find(|c| APOSTROPHES.contains(c)) {
+                if user_apo != apo {
+                    return word.replace(apo, user_apo.encode_utf8(&mut [0; 4]));
+                }
+            }
+        }
+        word
+    }
+
+    /// Returns the entry on a line of a .dic file, without the line
+    /// ending, surrounding whitespace, or comment, or None if the line
+    /// doesn't have one. Lines starting with a tab are comments, as in
+    /// Hunspell, and so is anything from a `#` at the start of the line
+    /// or after whitespace.
+    fn dic_line_entry(line: &str) -> Option<&str> {
+        if line.starts_with('\t') {
+            return None;
+        }
+        let line = line.trim_start_matches('\u{feff}').trim();
+        let comment = line
+            .char_indices()
+            .find(|&(i, c)| c == '#' && (i == 0 || line[..i].ends_with([' ', '\t'])))
+            .map_or(line.len(), |(i, _)| i);
+        let entry = line[..comment].trim_end();
+        (!entry.is_empty()).then_some(entry)
+    }
+
     fn split_morphological_fields(s: &str) -> (&str, Option<&str>) {
         // Parsing these is tricky because they are separated from the
         // word by a space, but the word may itself contain a space.
@@ -300,35 +981,140 @@ impl SpellerHunspellDict {
         (s, None)
     }
 
-    fn _user_dict_adder(&self, word: &str) -> Result<()> {
+    #[cfg(feature = "std-fs")]
+    fn _user_dict_adder(&self, word: &str, model: Option<&str>) -> Result<()> {
         if let Some(user_dict) = &self.user_dict {
             let mut file = OpenOptions::new().append(true).open(user_dict)?;
-            file.write_all(word.as_bytes())?;
-            file.write_all("\n".as_bytes())?;
+            file.write_all(Self::user_dict_line(word, model).as_bytes())?;
         }
         Ok(())
     }
 
-    fn is_numeric(word: &str) -> bool {
-        // allow numbers with dots or commas
-        // allow -- at the end and - at the front
-        let word = word.strip_suffix("--").unwrap_or(word);
-        let word = word.strip_prefix('-').unwrap_or(word);
-        let mut seen_digit = false;
+    /// Format a line for the user dict file, including the newline.
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn user_dict_line(word: &str, model: Option<&str>) -> String {
+        let mut line = Self::escape_user_dict_word(word);
+        if let Some(model) = model {
+            line.push_str("\tlike:");
+            line.push_str(model);
+        }
+        line.push('\n');
+        line
+    }
+
+    /// Escape a word for the user dict file, so that a `/` is not read
+    /// back as the start of flags and a leading `#` is not read back as
+    /// a comment. This uses the same `\/` escape that hunspell uses.
+    /// Other backslashes are written as they are, so that user dicts
+    /// from before there was escaping are still read the same way.
+    #[cfg(feature = "std-fs")]
+    fn escape_user_dict_word(word: &str) -> String {
+        let mut escaped = String::with_capacity(word.len() + 2);
+        // A word such as `\#tag` needs the extra backslash too, because
+        // one leading backslash before a `#` is always taken off.
+        if word.trim_start_matches('\\').starts_with('#') {
+            escaped.push('\\');
+        }
         for c in word.chars() {
-            // TODO check for unicode number separators here
-            if c == '.' || c == ',' {
-                if !seen_digit {
-                    return false;
-                }
-                seen_digit = false;
-            } else if c.is_ascii_digit() {
-                seen_digit = true;
+            if c == '/' {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    /// Split a user dict line into the word and its flags and model, if any.
+    /// This reverses `user_dict_line`; an unescaped `/` starts the flags,
+    /// as in a .dic file, and a tab starts the `like:` field.
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn split_user_dict_line(line: &str) -> UserDictEntry<'_> {
+        let (line, fields) = line.split_once('\t').unwrap_or((line, ""));
+        let model = fields
+            .split('\t')
+            .find_map(|field| field.trim().strip_prefix("like:"));
+        let mut entry = UserDictEntry {
+            word: String::with_capacity(line.len()),
+            flags: "",
+            model,
+        };
+        let mut word = line;
+        if word.trim_start_matches('\\').starts_with('#') {
+            word = &word[1..];
+        }
+        let mut iter = word.char_indices().peekable();
+        while let Some((i, c)) = iter.next() {
+            if c == '\\' && iter.peek().is_some_and(|&(_, c2)| c2 == '/') {
+                entry.word.push('/');
+                iter.next();
+                continue;
+            } else if c == '/' {
+                entry.flags = &word[i + 1..];
+                break;
+            }
+            entry.word.push(c);
+        }
+        entry
+    }
+
+    /// Parse the contents of a user dict file into words and their flags
+    /// or models.
+    /// Comment lines starting with `#` are skipped, and so is a word count
+    /// on the first line, so that hunspell personal dictionaries and .dic
+    /// files can be used as user dicts.
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn user_dict_entries(dict: &str) -> impl Iterator<Item = UserDictEntry<'_>> + '_ {
+        dict.lines()
+            .enumerate()
+            .filter(|(i, line)| {
+                let line = line.trim_matches('\u{feff}').trim();
+                let is_count =
+                    *i == 0 && !line.is_empty() && line.chars().all(|c| c.is_ascii_digit());
+                !is_count && !line.starts_with('#')
+            })
+            .map(|(_, line)| Self::split_user_dict_line(line))
+    }
+
+    /// Add the words in the format of a user dict, and return how many
+    /// were added.
+    #[cfg(feature = "std-fs")]
+    fn add_user_dict_entries(&mut self, dict: &str) -> i32 {
+        let mut added = 0;
+        for entry in Self::user_dict_entries(dict) {
+            let success = if let Some(model) = entry.model {
+                // If the model is gone, at least accept the word itself.
+                self.add_word_like(&entry.word, model) || self.add_word(&entry.word)
+            } else if entry.flags.is_empty() {
+                self.add_word(&entry.word)
             } else {
-                return false;
+                let word = self.conv_input(&entry.word);
+                let success = self.insert_word(&word, entry.flags.trim());
+                if success {
+                    self.user_words.insert(word);
+                }
+                success
+            };
+            if success {
+                added += 1;
             }
         }
-        true
+        added
+    }
+
+    fn has_compounding(&self) -> bool {
+        self.affix_data.special_flags.has_compounds() || !self.affix_data.compound_rules.is_empty()
+    }
+
+    /// Returns true if `word` is a number, possibly followed by one of
+    /// the number suffixes.
+    fn is_number(&self, word: &str) -> bool {
+        let format = self.affix_data.number_format;
+        format.is_numeric(word)
+            || self.number_suffixes.iter().any(|suffix| {
+                word.strip_suffix(suffix.as_str()).is_some_and(|number| {
+                    number.ends_with(char::is_numeric) && format.is_numeric(number)
+                })
+            })
     }
 
     fn is_forbidden(&self, word: &str) -> bool {
@@ -381,7 +1167,7 @@ impl SpellerHunspellDict {
 
     fn word_iter_fold(&self, word: &str, caps: CapStyle) -> std::slice::Iter<'_, WordInfo> {
         if caps == CapStyle::Folded {
-            if let Some(homonyms) = self.folded_words.get(word) {
+            if let Some(homonyms) = self.folded_words().get(word) {
                 homonyms.iter()
             } else {
                 [].iter()
@@ -424,6 +1210,7 @@ impl SpellerHunspellDict {
         caps: CapStyle,
         v: &mut Vec<&'a str>,
         mut iter: CharIndices,
+        search: &mut CompoundSearch,
     ) -> bool {
         let mut wlen = 0;
         let mut wstart = None;
@@ -440,6 +1227,9 @@ impl SpellerHunspellDict {
                 // If the "piece" is the whole word, then it's not compound.
                 continue;
             }
+            if !search.attempt() {
+                return false;
+            }
             let piece = &word[wstart.unwrap()..iafter];
             if !self.words.contains_key(piece) {
                 continue;
@@ -452,7 +1242,7 @@ impl SpellerHunspellDict {
             // work anyway.
             for rule in &self.affix_data.compound_rules {
                 if rule.partial_match(v, |word, flag| self.has_affix_flag_fold(word, caps, flag)) {
-                    if self._spellcheck_compoundrule(word, caps, v, iter.clone()) {
+                    if self._spellcheck_compoundrule(word, caps, v, iter.clone(), search) {
                         return true;
                     }
                     break;
@@ -481,7 +1271,17 @@ impl SpellerHunspellDict {
         caps: CapStyle,
         v: &mut Vec<&'a str>,
         mut iter: CharIndices,
+        search: &mut CompoundSearch,
     ) -> bool {
+        // Whether the rest can be divided into pieces only depends on
+        // where it starts, once there are two pieces before it. (With
+        // fewer, the first piece can only have been tried once here,
+        // and the word pair check below depends on it.)
+        let start = iter.offset();
+        let remember = v.len() >= 2 && start < word.len();
+        if remember && search.dead_ends[start] {
+            return false;
+        }
         let mut wlen = 0;
         let mut wstart = None;
         while let Some((i, c)) = iter.next() {
@@ -497,6 +1297,9 @@ impl SpellerHunspellDict {
                 // If the "piece" is the whole word, then it's not compound.
                 continue;
             }
+            if !search.attempt() {
+                return false;
+            }
             let piece = &word[wstart.unwrap()..iafter];
             let compound = if v.is_empty() {
                 Compound::Begin
@@ -520,12 +1323,15 @@ impl SpellerHunspellDict {
             // Found a possible word piece.
             // Recurse to try the piece.
             v.push(piece);
-            if self._spellcheck_compounding(word, caps, v, iter.clone()) {
+            if self._spellcheck_compounding(word, caps, v, iter.clone(), search) {
                 return true;
             }
             // Then loop to try not using the piece.
             v.pop();
         }
+        if remember {
+            search.dead_ends[start] = true;
+        }
         // Success if we exactly consumed `word`.
         // Also check a special case: if a word pair is in the dictionary
         // separated by a space, then don't accept it as a compound.
@@ -541,44 +1347,78 @@ impl SpellerHunspellDict {
         // For COMPOUNDRULE, divide the word into pieces that are all
         // directly in the dictionary (no prefix/suffix processing).
         if !self.affix_data.compound_rules.is_empty()
-            && self._spellcheck_compoundrule(word, caps, &mut Vec::new(), word.char_indices())
+            && self._spellcheck_compoundrule(
+                word,
+                caps,
+                &mut Vec::new(),
+                word.char_indices(),
+                &mut CompoundSearch::new(word, self.config.max_compound_attempts),
+            )
         {
             return true;
         }
 
         // Early return for dictionaries that don't support compounding.
         self.affix_data.special_flags.has_compounds()
-            && self._spellcheck_compounding(word, caps, &mut Vec::new(), word.char_indices())
+            && self._spellcheck_compounding(
+                word,
+                caps,
+                &mut Vec::new(),
+                word.char_indices(),
+                &mut CompoundSearch::new(word, self.config.max_compound_attempts),
+            )
     }
 
     // Check a word against the dictionary and try word breaks and affixes
-    fn _spellcheck(&self, word: &str, strict: StrictMode, count: &mut u16) -> bool {
-        if Self::is_numeric(word) {
+    fn _spellcheck(&self, word: &str, strict: StrictMode, search: &mut BreakSearch) -> bool {
+        if self.is_number(word) {
             return true;
         }
 
-        let caps = CapStyle::from_str(word);
-        if *count > MAX_WORD_BREAK_ATTEMPTS {
+        let piece = (word.as_ptr() as usize, word.len());
+        if search.failed.contains(&piece) || search.attempts > self.config.max_word_break_attempts {
             return false;
         }
-        *count += 1;
+        search.attempts += 1;
 
-        if self._spellcheck_caps(word, caps, strict) {
+        if self._spellcheck_breaks(word, strict, search) {
             return true;
         }
+        search.failed.insert(piece);
+        false
+    }
+
+    fn _spellcheck_breaks(&self, word: &str, strict: StrictMode, search: &mut BreakSearch) -> bool {
+        if self._spellcheck_caps(&CaseInfo::new(word, self.casing()), strict) {
+            return true;
+        }
+
+        let breaks = if self.break_policy == BreakPolicy::FlagAll {
+            &[][..]
+        } else {
+            &self.affix_data.word_breaks[..]
+        };
+        let strict_breaks = self.break_policy == BreakPolicy::Strict;
 
         // break patterns may be anchored with ^ or $
-        // Try those first.
-        for brk in &self.affix_data.word_breaks {
+        // Try those first. There must be something left after removing
+        // the break; the break by itself is not a word.
+        for brk in breaks {
             if let Some(brk) = brk.strip_prefix('^') {
                 if let Some(bword) = word.strip_prefix(brk) {
-                    if self._spellcheck(bword, strict, count) {
+                    if bword.is_empty() || (strict_breaks && bword.starts_with(brk)) {
+                        continue;
+                    }
+                    if self._spellcheck(bword, strict, search) {
                         return true;
                     }
                 }
             } else if let Some(brk) = brk.strip_suffix('$') {
                 if let Some(bword) = word.strip_suffix(brk) {
-                    if self._spellcheck(bword, strict, count) {
+                    if bword.is_empty() || (strict_breaks && bword.ends_with(brk)) {
+                        continue;
+                    }
+                    if self._spellcheck(bword, strict, search) {
                         return true;
                     }
                 }
@@ -587,46 +1427,109 @@ impl SpellerHunspellDict {
 
         // If the word ends on a '.', try removing it.
         if let Some(bword) = word.strip_suffix('.') {
-            if self._spellcheck(bword, strict, count) {
+            if self._spellcheck(bword, strict, search) {
                 return true;
             }
         }
 
         // Try breaking words into pieces.
-        for brk in &self.affix_data.word_breaks {
+        for brk in breaks {
             if brk.starts_with('^') || brk.ends_with('$') {
                 continue;
             }
-            if let Some((worda, wordb)) = word.split_once(brk) {
-                if self._spellcheck(worda, strict, count) && self._spellcheck(wordb, strict, count)
-                {
+            if strict_breaks {
+                if self._spellcheck_break_strict(word, brk, strict, search) {
                     return true;
                 }
+            } else {
+                // Try each of the breaks, not just the first, in case
+                // there is a dictionary word with a break in it, such
+                // as "e-mail" in "e-mail-address".
+                for (i, _) in word.match_indices(brk.as_str()) {
+                    let (worda, wordb) = (&word[..i], &word[i + brk.len()..]);
+                    // Breaks at the very start or end are up to the
+                    // anchored patterns.
+                    if worda.is_empty() || wordb.is_empty() {
+                        continue;
+                    }
+                    if self._spellcheck(worda, strict, search)
+                        && self._spellcheck(wordb, strict, search)
+                    {
+                        return true;
+                    }
+                }
             }
         }
         false
     }
 
+    // Break a word into all its pieces at `brk`, for BreakPolicy::Strict.
+    // All the pieces must be non-empty and valid, and no run of
+    // consecutive pieces, together with the breaks between them,
+    // may be a forbidden word.
+    fn _spellcheck_break_strict(
+        &self,
+        word: &str,
+        brk: &str,
+        strict: StrictMode,
+        search: &mut BreakSearch,
+    ) -> bool {
+        // The byte offsets where each piece starts and ends
+        let mut starts = vec![0];
+        let mut ends = Vec::new();
+        for (i, _) in word.match_indices(brk) {
+            ends.push(i);
+            starts.push(i + brk.len());
+        }
+        ends.push(word.len());
+        if starts.len() < 2 || starts.iter().zip(&ends).any(|(s, e)| s >= e) {
+            return false;
+        }
+
+        for (i, &start) in starts.iter().enumerate() {
+            for &end in &ends[i..] {
+                if self.is_forbidden(&word[start..end]) {
+                    return false;
+                }
+            }
+        }
+        starts
+            .iter()
+            .zip(&ends)
+            .all(|(&s, &e)| self._spellcheck(&word[s..e], strict, search))
+    }
+
     // Check a word against the dictionary and try different capitalization
-    fn _spellcheck_caps(&self, word: &str, caps: CapStyle, strict: StrictMode) -> bool {
-        if self._spellcheck_compound(word, caps) {
+    fn _spellcheck_caps(&self, case: &CaseInfo, strict: StrictMode) -> bool {
+        let caps = case.caps;
+        if self._spellcheck_compound(case.word, caps) {
             return true;
         }
 
+        // A capitalized word may start with an uppercase digraph where
+        // the dictionary has the titlecase one, as in Ǉubljana.
+        if caps == CapStyle::Capitalized {
+            if let Some(titled) = case.titlecase() {
+                if self._spellcheck_compound(&titled, caps) {
+                    return true;
+                }
+            }
+        }
+
         // Any word might be capitalized at the beginning of a sentence,
         // and any phrase might be written in all caps for emphasis,
         // so those should all be detected as correctly spelled.
 
         if matches!(strict, StrictMode::AllowAll)
             && caps == CapStyle::AllCaps
-            && self._spellcheck_compound(&default_case_fold_str(word), CapStyle::Folded)
+            && self._spellcheck_compound(&case.folded(), CapStyle::Folded)
         {
             return true;
         }
 
         if matches!(strict, StrictMode::AllowDecap | StrictMode::AllowAll)
             && caps == CapStyle::Capitalized
-            && self._spellcheck_compound(&word.to_lowercase(), CapStyle::Decapitalized)
+            && self._spellcheck_compound(&case.lowercase(), CapStyle::Decapitalized)
         {
             return true;
         }
@@ -639,8 +1542,7 @@ impl SpellerHunspellDict {
             return false;
         }
 
-        let mut count = 0u16;
-        if self._spellcheck(word, origcaps.strict(), &mut count) {
+        if self._spellcheck(word, origcaps.strict(), &mut BreakSearch::default()) {
             return true;
         }
 
@@ -657,22 +1559,146 @@ impl SpellerHunspellDict {
             return false;
         }
 
-        let caps = CapStyle::from_str(word);
-        self._spellcheck_caps(word, caps, origcaps.strict())
+        self._spellcheck_caps(&CaseInfo::new(word, self.casing()), origcaps.strict())
+    }
+
+    // See SpellerConfig::max_word_length.
+    fn is_too_long(&self, word: &str) -> bool {
+        let max = self.config.max_word_length;
+        max != 0 && word.len() > max && word.chars().count() > max
+    }
+
+    // The dictionary word that is the best model for `word`, as in
+    // `suggest_model`. Returns None if `timed_out` says to stop before
+    // all the words were looked at.
+    fn find_model(&self, word: &str, timed_out: impl Fn() -> bool) -> Option<String> {
+        let original = word;
+        let word = self.conv_input(word);
+        let caps = CapStyle::from_str(&word);
+        let len = word.chars().count();
+        let stopped = Cell::new(false);
+        let best = self
+            .words
+            .iter()
+            .take_while(|_| {
+                stopped.set(timed_out());
+                !stopped.get()
+            })
+            .filter(|(candidate, homonyms)| {
+                **candidate != *word
+                    && homonyms.iter().any(|winfo| {
+                        !winfo.affix_flags.is_empty()
+                            && !winfo
+                                .word_flags
+                                .intersects(WordFlags::Forbidden | WordFlags::NoSuggest)
+                    })
+            })
+            .map(|(candidate, _)| {
+                let common = candidate
+                    .chars()
+                    .rev()
+                    .zip(word.chars().rev())
+                    .take_while(|(c1, c2)| c1 == c2)
+                    .count();
+                let same_caps = CapStyle::from_str(candidate) == caps;
+                let len_diff = candidate.chars().count().abs_diff(len);
+                // Sort by the longest common ending, then by similar
+                // capitalization and length. The candidate itself is
+                // the last key so that the choice is deterministic.
+                (
+                    (common, same_caps, Reverse(len_diff), Reverse(candidate)),
+                    candidate,
+                )
+            })
+            .filter(|((common, ..), _)| *common >= MIN_MODEL_ENDING)
+            .max_by(|(key1, _), (key2, _)| key1.cmp(key2))
+            .map(|(_, candidate)| candidate);
+        if stopped.get() {
+            return None;
+        }
+        best.map(|candidate| self.conv_output(candidate, original))
+    }
+
+    // A shorter limit set with set_suggestion_time_limit still applies.
+    fn budget_limit(&self, budget: Duration) -> Duration {
+        self.suggestion_time_limit
+            .map_or(budget, |limit| limit.min(budget))
+    }
+
+    fn suggestions_within(
+        &self,
+        word: &str,
+        max: usize,
+        time_limit: Option<Duration>,
+        cancel: Option<&AtomicBool>,
+    ) -> Suggestions {
+        match self.detailed_within(word, max, time_limit, cancel) {
+            Ok(suggs) => Suggestions::Found(suggs.into_iter().map(|sugg| sugg.word).collect()),
+            Err(reason) => reason,
+        }
+    }
+
+    fn detailed_within(
+        &self,
+        word: &str,
+        max: usize,
+        time_limit: Option<Duration>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<Vec<Suggestion>, Suggestions> {
+        let original = word;
+        let word = self.conv_input(word);
+        if word.is_empty() || max == 0 {
+            return Err(Suggestions::NoCandidates);
+        }
+        if self.is_too_long(&word) {
+            return Err(Suggestions::TooLong);
+        }
+
+        let mut suggs = self._suggestions(&word, max, time_limit, cancel)?;
+        for sugg in &mut suggs {
+            sugg.word = self.conv_output(&sugg.word, original);
+        }
+        Ok(suggs)
     }
 
-    fn _suggestions(&self, word: &str, max: usize) -> Vec<String> {
-        let mut collector = SuggCollector::new(self, word, max);
+    fn _suggestions(
+        &self,
+        word: &str,
+        max: usize,
+        time_limit: Option<Duration>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<Vec<Suggestion>, Suggestions> {
+        let mut collector = SuggCollector::new(self, word, max, time_limit);
+        if let Some(cancel) = cancel {
+            collector.set_cancel(cancel);
+        }
+
+        // The suggestions are mostly in the order they are found, with
+        // two exceptions. REP entries with a space, such as "alot a_lot",
+        // and words typed in the wrong keyboard layout always come first.
+        // After them comes a split into a word pair that is in the
+        // dictionary as one entry, such as "in spite", which replaces all
+        // other suggestions.
 
         // Try lowercased, capitalized, or all caps
         // TODO: also match mixed case words, such as "ipod" -> "iPod"
         collector.new_source("different_case");
-        collector.suggest(&word.to_lowercase());
-        collector.suggest(&word.to_titlecase_lower_rest());
-        collector.suggest(&word.to_uppercase());
+        let casing = self.casing();
+        collector.suggest(&casing.lowercase(word));
+        collector.suggest(&casing.titlecase_lower_rest(word));
+        collector.suggest(&casing.uppercase(word));
+
+        wrong_layout_suggestions(word, &self.layout_pairs, &mut collector);
+
+        // Try joining words, if the dictionary can make compound words.
+        if self.has_compounding() {
+            join_words_suggestions(word, &mut collector);
+        }
 
         self.affix_data.replacements.suggest(word, &mut collector);
 
+        self.accent_index().suggest(word, &mut collector);
+
         related_char_suggestions(&self.affix_data.related_chars, word, &mut collector);
 
         delete_char_suggestions(word, &mut collector);
@@ -690,7 +1716,7 @@ impl SpellerHunspellDict {
 
         move_char_suggestions(word, &mut collector);
 
-        if let Some(keys) = &self.affix_data.keyboard_string {
+        if let Some(keys) = self.keyboard() {
             wrong_key_suggestions(word, keys, &mut collector);
         }
 
@@ -708,7 +1734,7 @@ impl SpellerHunspellDict {
 
         // Only try the ngram and delins algorithms if the straightforward
         // corrections didn't produce any usable suggestions.
-        if !has_good {
+        if !has_good && !collector.timed_out() {
             // Re-use MAXNGRAMSUGGS to limit delins suggestions too.
             let divided_max = (usize::from(self.affix_data.max_ngram_suggestions) + 1) / 2;
 
@@ -721,51 +1747,143 @@ impl SpellerHunspellDict {
             collector.set_limit(max);
         }
 
-        collector.into_iter().collect()
+        collector.into_detailed()
     }
 }
 
 impl Speller for SpellerHunspellDict {
     fn spellcheck(&self, word: &str) -> bool {
-        let word = self.affix_data.iconv.conv(word.trim());
-        if word.is_empty() {
+        let word = self.conv_input(word);
+        if word.is_empty() || self.is_too_long(&word) {
             return true;
         }
         if self.is_forbidden(&word) {
             return false;
         }
-        let mut count = 0u16;
-        self._spellcheck(&word, StrictMode::AllowAll, &mut count)
+        self._spellcheck(&word, StrictMode::AllowAll, &mut BreakSearch::default())
     }
 
     fn suggestions(&self, word: &str, max: usize) -> Vec<String> {
-        let word = self.affix_data.iconv.conv(word.trim());
-        if word.is_empty() || max == 0 {
-            return Vec::new();
+        self.suggestions_with_reason(word, max).into_vec()
+    }
+
+    fn suggestions_with_reason(&self, word: &str, max: usize) -> Suggestions {
+        match self.suggestion_time_limit {
+            Some(limit) => self.suggestions_with_budget(word, max, limit),
+            None => self.suggestions_within(word, max, None, None),
         }
+    }
 
-        self._suggestions(&word, max)
-            .into_iter()
-            .map(|sugg| self.affix_data.oconv.conv(&sugg))
-            .collect()
+    fn suggestions_with_budget(&self, word: &str, max: usize, budget: Duration) -> Suggestions {
+        self.suggestions_cancellable(word, max, budget, &AtomicBool::new(false))
+    }
+
+    fn suggestions_cancellable(
+        &self,
+        word: &str,
+        max: usize,
+        budget: Duration,
+        cancel: &AtomicBool,
+    ) -> Suggestions {
+        self.suggestions_within(word, max, Some(self.budget_limit(budget)), Some(cancel))
+    }
+
+    fn suggestions_detailed(&self, word: &str, max: usize) -> Vec<Suggestion> {
+        self.detailed_within(word, max, self.suggestion_time_limit, None)
+            .unwrap_or_default()
+    }
+
+    fn set_suggestion_config(&mut self, config: SuggestionConfig) {
+        self.suggestion_config = config;
     }
 
     fn add_word(&mut self, word: &str) -> bool {
-        let word = self.affix_data.iconv.conv(word.trim());
-        if word.is_empty() {
+        let word = self.conv_input(word);
+        if !self.insert_word_with_affix_flags(&word, Vec::new()) {
             return false;
         }
-        self.folded_words
-            .entry(default_case_fold_str(&word))
-            .or_default()
-            .push(WordInfo::default());
-        self.words
-            .entry(word)
-            .or_default()
-            .push(WordInfo::default());
+        self.user_words.insert(word);
+        true
+    }
+
+    fn add_words(&mut self, words: &[&str]) -> usize {
+        // Make room in both word maps at once, instead of growing them
+        // again and again while adding.
+        self.words.reserve(words.len());
+        if let Some(folded_words) = self.folded_words.get_mut() {
+            folded_words.reserve(words.len());
+        }
+        self.user_words.reserve(words.len());
+        words.iter().filter(|word| self.add_word(word)).count()
+    }
+
+    fn add_word_like(&mut self, word: &str, model: &str) -> bool {
+        let word = self.conv_input(word);
+        let model = self.conv_input(model);
+        let homonyms = self.model_homonyms(&model);
+        if word.is_empty() || homonyms.is_empty() {
+            return false;
+        }
+        // Copy each homonym separately, so that the word gets the same
+        // combinations of affixes as the model.
+        let mut added = false;
+        for winfo in homonyms {
+            added |= self.insert_word_with_affix_flags(&word, winfo.affix_flags);
+        }
+        if !added {
+            return false;
+        }
+        self.user_words.insert(word);
         true
     }
 
+    fn words_like(&self, word: &str, model: &str) -> Vec<String> {
+        let original = word;
+        let word = self.conv_input(word);
+        let model = self.conv_input(model);
+        let mut words = Vec::new();
+        if word.is_empty() {
+            return words;
+        }
+        for winfo in self.model_homonyms(&model) {
+            self.affix_data
+                .generate_words_from_winfo(&word, &winfo, self, |w| {
+                    words.push(self.conv_output(w, original));
+                });
+        }
+        words.sort_unstable();
+        words.dedup();
+        words
+    }
+
+    fn suggest_model(&self, word: &str) -> Option<String> {
+        self.find_model(word, || false)
+    }
+
+    fn suggest_model_cancellable(
+        &self,
+        word: &str,
+        budget: Duration,
+        cancel: &AtomicBool,
+    ) -> Option<String> {
+        let mut collector = SuggCollector::new(self, word, 0, Some(self.budget_limit(budget)));
+        collector.set_cancel(cancel);
+        self.find_model(word, || collector.timed_out())
+    }
+
+    fn is_discouraged(&self, word: &str) -> bool {
+        let word = self.conv_input(word);
+        // A capitalized word at the start of a sentence is still the
+        // dictionary's lowercase word.
+        self.is_forbidden_suggestion(&word)
+            || self.is_forbidden_suggestion(&CaseInfo::new(&word, self.casing()).lowercase())
+    }
+
+    fn user_words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.user_words.iter().map(String::as_str))
+    }
+
+    #[cfg(feature = "std-fs")]
     fn set_user_dict(&mut self, path: &Path) -> Result<i32> {
         if !path.exists() {
             File::create(path).with_context(|| format!("Could not create {}", path.display()))?;
@@ -774,23 +1892,36 @@ impl Speller for SpellerHunspellDict {
             read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
 
         self.user_dict = Some(path.to_path_buf());
+        Ok(self.add_user_dict_entries(&dict))
+    }
 
-        let mut added = 0;
-        for word in dict.lines() {
-            if self.add_word(word) {
-                added += 1;
-            }
+    #[cfg(feature = "std-fs")]
+    fn add_word_to_user_dict(&mut self, word: &str) -> Result<bool> {
+        // A line break would split the word into separate entries
+        // when the user dict is loaded again.
+        if word.contains(['\n', '\r']) || !self.add_word(word) {
+            return Ok(false);
+        }
+
+        if let Some(user_dict) = &self.user_dict {
+            self._user_dict_adder(word, None)
+                .with_context(|| format!("Could not append to {}", user_dict.display()))?;
         }
-        Ok(added)
+        Ok(true)
     }
 
-    fn add_word_to_user_dict(&mut self, word: &str) -> Result<bool> {
-        if !self.add_word(word) {
+    #[cfg(feature = "std-fs")]
+    fn add_word_like_to_user_dict(&mut self, word: &str, model: &str) -> Result<bool> {
+        // A tab would be read back as the start of the model field.
+        if word.contains(['\n', '\r', '\t'])
+            || model.contains(['\n', '\r', '\t'])
+            || !self.add_word_like(word, model)
+        {
             return Ok(false);
         }
 
         if let Some(user_dict) = &self.user_dict {
-            self._user_dict_adder(word)
+            self._user_dict_adder(word, Some(model.trim()))
                 .with_context(|| format!("Could not append to {}", user_dict.display()))?;
         }
         Ok(true)
@@ -802,14 +1933,90 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_is_numeric() {
-        assert_eq!(true, SpellerHunspellDict::is_numeric("54"));
-        assert_eq!(true, SpellerHunspellDict::is_numeric("-1,000.00"));
-        assert_eq!(true, SpellerHunspellDict::is_numeric("-1,000.--"));
-        assert_eq!(false, SpellerHunspellDict::is_numeric("1,ooo"));
-        assert_eq!(false, SpellerHunspellDict::is_numeric("100,,000"));
-        assert_eq!(false, SpellerHunspellDict::is_numeric(".."));
-        assert_eq!(false, SpellerHunspellDict::is_numeric(".50"));
+    fn test_capstyle() {
+        for (word, caps) in [
+            ("", CapStyle::Neutral),
+            ("123", CapStyle::Neutral),
+            ("hello", CapStyle::Lowercase),
+            ("1st", CapStyle::Lowercase),
+            ("Hello", CapStyle::Capitalized),
+            ("'Tis", CapStyle::Capitalized),
+            ("HELLO", CapStyle::AllCaps),
+            ("A", CapStyle::AllCaps),
+            ("iPod", CapStyle::Mixed),
+            ("McDonald", CapStyle::Mixed),
+            ("HELLo", CapStyle::Mixed),
+            ("école", CapStyle::Lowercase),
+            ("Émile", CapStyle::Capitalized),
+            ("ÉCOLE", CapStyle::AllCaps),
+            ("éCOLE", CapStyle::Mixed),
+            ("ǉudi", CapStyle::Lowercase),
+            ("ǈudi", CapStyle::Capitalized),
+            ("Ǉudi", CapStyle::Capitalized),
+            ("ǇUDI", CapStyle::AllCaps),
+            ("ǈUDI", CapStyle::AllCaps),
+            ("ǈ", CapStyle::Capitalized),
+            ("Ǉ", CapStyle::AllCaps),
+            ("koǈudi", CapStyle::Mixed),
+            ("ǈudI", CapStyle::Mixed),
+            ("ǈǉ", CapStyle::Capitalized),
+            ("ǈǈ", CapStyle::Mixed),
+        ] {
+            // The ASCII words are checked both ways, so that the fast
+            // path can't drift from the general one.
+            assert_eq!(caps, CapStyle::from_chars(word), "{}", word);
+            if word.is_ascii() {
+                assert_eq!(caps, CapStyle::from_ascii(word.as_bytes()), "{}", word);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std-fs")]
+    fn test_user_dict_escape() {
+        for word in [
+            "plain",
+            "and/or",
+            "#hashtag",
+            "back\\slash",
+            "mid#dle",
+            "\\/#",
+            "\\#tag",
+            "end\\",
+        ] {
+            let escaped = SpellerHunspellDict::escape_user_dict_word(word);
+            assert!(!escaped.starts_with('#'));
+            assert!(!escaped.replace("\\/", "").contains('/'));
+            assert_eq!(
+                word,
+                SpellerHunspellDict::split_user_dict_line(&escaped).word
+            );
+        }
+        // Lines written before there was escaping read the same as before.
+        assert_eq!(
+            "back\\slash",
+            SpellerHunspellDict::split_user_dict_line("back\\slash").word
+        );
+        assert_eq!(
+            "back\\slash",
+            SpellerHunspellDict::escape_user_dict_word("back\\slash")
+        );
+        assert_eq!(
+            "and\\/or",
+            SpellerHunspellDict::escape_user_dict_word("and/or")
+        );
+        assert_eq!("\\#tag", SpellerHunspellDict::escape_user_dict_word("#tag"));
+        let entry = SpellerHunspellDict::split_user_dict_line("and\\/or/AB");
+        assert_eq!(
+            ("and/or", "AB", None),
+            (&*entry.word, entry.flags, entry.model)
+        );
+        let line = SpellerHunspellDict::user_dict_line("Valyrian", Some("Victorian"));
+        let entry = SpellerHunspellDict::split_user_dict_line(line.trim_end());
+        assert_eq!(
+            ("Valyrian", "", Some("Victorian")),
+            (&*entry.word, entry.flags, entry.model)
+        );
     }
 
     #[test]
@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::hunspell::casing::Casing;
+use crate::hunspell::suggcollector::SuggCollector;
+use crate::hunspell::wordstore::WordStore;
+
+/// The shortest start of a word that is looked up in the index. Shorter
+/// ones match too many words that only happen to start the same way.
+const MIN_STEM_CHARS: usize = 3;
+
+/// Returns `word` without the accents on its letters, such as "interet"
+/// for "intérêt". Letters that aren't made of a base letter and an
+/// accent, such as "ø" and "ß", stay as they are.
+pub fn strip_accents(word: &str) -> Cow<'_, str> {
+    if word.is_ascii() {
+        return Cow::Borrowed(word);
+    }
+    Cow::Owned(word.nfd().filter(|c| !is_combining_mark(*c)).collect())
+}
+
+/// An index of the dictionary's words that have accents, by their
+/// case-folded form without the accents, so that a word typed without
+/// its accents leads straight to the words that have them. Trying the
+/// accented letters in each place instead, as the related_char
+/// suggestions do, takes too long for long words.
+#[derive(Clone, Debug, Default)]
+pub struct AccentIndex {
+    words: WordStore<String>,
+}
+
+impl AccentIndex {
+    /// Add `word` to the index, if it has accents.
+    pub fn add(&mut self, word: &str, casing: Casing) {
+        let folded = casing.fold(word);
+        let stripped = strip_accents(&folded);
+        if stripped == folded {
+            return;
+        }
+        // Homonyms only need to be in the index once. If there is no
+        // room for the word, it's only not suggested this way.
+        if !self
+            .words
+            .get(&stripped)
+            .is_some_and(|words| words.iter().any(|w| w == word))
+        {
+            let _ = self.words.push(&stripped, word.to_string());
+        }
+    }
+
+    /// Suggest the accented words that `word` is, if it were written
+    /// with the accents. The start of `word` is looked up too, with the
+    /// rest added back on, so that the accents of a dictionary word are
+    /// also restored in its forms with a suffix.
+    pub fn suggest(&self, word: &str, collector: &mut SuggCollector) {
+        collector.new_source("accents");
+        if self.words.len() == 0 {
+            return;
+        }
+        let casing = collector.casing();
+        // Longest first, because the whole word is the best match.
+        let mut ends: Vec<usize> = word.char_indices().map(|(i, _)| i).collect();
+        ends.push(word.len());
+        for &end in ends[MIN_STEM_CHARS.min(ends.len() - 1)..].iter().rev() {
+            let (stem, rest) = word.split_at(end);
+            let folded = casing.fold(stem);
+            let Some(accented) = self.words.get(&strip_accents(&folded)) else {
+                continue;
+            };
+            for accented in accented {
+                collector.suggest(&format!("{}{}", accented, rest));
+                if collector.limit() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strip_accents() {
+        assert_eq!("interet", strip_accents("intérêt"));
+        assert_eq!("Eleve", strip_accents("Élève"));
+        assert_eq!("smørrebrød", strip_accents("smørrebrød"));
+        assert_eq!("plain", strip_accents("plain"));
+        // Letters typed with a combining accent lose it too.
+        assert_eq!("cafe", strip_accents("cafe\u{301}"));
+    }
+}
@@ -2,17 +2,19 @@ use anyhow::{bail, Result};
 use fnv::FnvHashMap;
 use itertools::Itertools;
 use std::num::ParseIntError;
-use unicode_titlecase::StrTitleCase;
 
 use crate::affix_trie::{PrefixTrie, SuffixTrie};
+use crate::hunspell::casing::Casing;
 use crate::hunspell::compoundrule::CompoundRule;
 use crate::hunspell::condition::AffixCondition;
+use crate::hunspell::numbers::NumberFormat;
 use crate::hunspell::replacements::Replacements;
 use crate::hunspell::wordflags::WordFlags;
 use crate::hunspell::{CapStyle, Compound, SpellerHunspellDict, WordInfo};
 
 /// Represents the format of the flags after words in the dictionary file.
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FlagMode {
     /// Single-character flags
     #[default]
@@ -26,6 +28,7 @@ pub enum FlagMode {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecialFlags {
     inner: FnvHashMap<WordFlags, AffixFlag>,
     all: WordFlags,
@@ -58,6 +61,7 @@ impl SpecialFlags {
 pub type AffixFlag = u32;
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AffixData {
     /// Affixes that can be applied to the front of a word
     pub prefixes: Vec<AffixEntry>,
@@ -79,6 +83,12 @@ pub struct AffixData {
     pub compound_min: u8,
     /// Limit to ngram suggestions in suggestion list
     pub max_ngram_suggestions: u8,
+    /// How different ngram suggestions may be from the word, from 0 (not
+    /// at all) to 10 (anything goes), from MAXDIFF.
+    pub max_diff: Option<u8>,
+    /// Drop all ngram suggestions that are too different, instead of
+    /// keeping the best of them when there's nothing better.
+    pub only_max_diff: bool,
     /// Characters that should be converted before matching.
     pub iconv: Replacements,
     /// Characters that should be converted after matching.
@@ -96,6 +106,14 @@ pub struct AffixData {
     /// Any errors reported by the .aff file parser
     pub errors: Vec<String>,
 
+    /// The language of the dictionary, such as "tr_TR", from the LANG
+    /// directive.
+    pub lang: Option<String>,
+    /// How the language changes the case of letters.
+    pub casing: Casing,
+    /// How the language writes numbers.
+    pub number_format: NumberFormat,
+
     /// Is this guessed to be a language where words are combined with dashes?
     pub dash_word_heuristic: bool,
 
@@ -180,16 +198,24 @@ impl AffixData {
     }
 
     pub fn finalize(&mut self) {
+        self.casing = self
+            .lang
+            .as_deref()
+            .map_or(Casing::Default, Casing::from_lang);
+        self.number_format = self
+            .lang
+            .as_deref()
+            .map_or(NumberFormat::Any, NumberFormat::from_lang);
         self.dash_word_heuristic = if let Some(try_string) = &self.try_string {
             try_string.contains('_') || try_string.contains(|c: char| c.is_ascii_alphabetic())
         } else {
             false
         };
         for pfx in &mut self.prefixes {
-            pfx.finalize(&self.special_flags);
+            pfx.finalize(&self.special_flags, self.casing);
         }
         for sfx in &mut self.suffixes {
-            sfx.finalize(&self.special_flags);
+            sfx.finalize(&self.special_flags, self.casing);
         }
         self.recalc_rev_cont();
         self.recalc_rev_suffix();
@@ -244,32 +270,45 @@ impl AffixData {
         mut suggest: impl FnMut(&str),
     ) {
         for winfo in dict.word_iter(root) {
-            // First try the root itself.
-            if !winfo.word_flags.intersects(
-                WordFlags::Forbidden
-                    | WordFlags::NoSuggest
-                    | WordFlags::OnlyInCompound
-                    | WordFlags::NeedAffix,
-            ) {
-                suggest(root);
-            }
+            self.generate_words_from_winfo(root, winfo, dict, &mut suggest);
+        }
+    }
 
-            for pfx in &self.prefixes {
-                if winfo.has_affix_flag(pfx.flag) {
-                    pfx.try_prefix(root, winfo, dict, &mut suggest);
-                }
+    /// Like `generate_words_from_root`, but with the flags in `winfo`
+    /// instead of the root's own entries in the dictionary.
+    pub fn generate_words_from_winfo(
+        &self,
+        root: &str,
+        winfo: &WordInfo,
+        dict: &SpellerHunspellDict,
+        mut suggest: impl FnMut(&str),
+    ) {
+        // First try the root itself.
+        if !winfo.word_flags.intersects(
+            WordFlags::Forbidden
+                | WordFlags::NoSuggest
+                | WordFlags::OnlyInCompound
+                | WordFlags::NeedAffix,
+        ) {
+            suggest(root);
+        }
+
+        for pfx in &self.prefixes {
+            if winfo.has_affix_flag(pfx.flag) {
+                pfx.try_prefix(root, winfo, dict, &mut suggest);
             }
+        }
 
-            for sfx in &self.suffixes {
-                if winfo.has_affix_flag(sfx.flag) {
-                    sfx.try_suffix(root, dict, &mut suggest, false);
-                }
+        for sfx in &self.suffixes {
+            if winfo.has_affix_flag(sfx.flag) {
+                sfx.try_suffix(root, dict, &mut suggest, false);
             }
         }
     }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AffixEntry {
     allow_cross: bool,
     flag: AffixFlag,
@@ -316,15 +355,18 @@ impl AffixEntry {
             condition,
             contflags: WordInfo::new(WordFlags::empty(), cflags),
 
-            capsed_affix: affix.to_uppercase(),
-            titled_affix: affix.to_titlecase(),
+            // The language isn't known yet, so these are filled in later.
+            capsed_affix: String::new(),
+            titled_affix: String::new(),
 
             pruned_condition: pruned,
         }
     }
 
-    pub fn finalize(&mut self, sf: &SpecialFlags) {
+    pub fn finalize(&mut self, sf: &SpecialFlags, casing: Casing) {
         self.contflags.word_flags = sf.word_flags(&self.contflags.affix_flags);
+        self.capsed_affix = casing.uppercase(&self.affix);
+        self.titled_affix = casing.titlecase(&self.affix);
     }
 
     fn _deprefixed_word(
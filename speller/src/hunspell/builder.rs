@@ -0,0 +1,182 @@
+use anyhow::Result;
+#[cfg(feature = "std-fs")]
+use anyhow::{anyhow, Context};
+#[cfg(feature = "std-fs")]
+use std::fs::read_to_string;
+#[cfg(feature = "std-fs")]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::hunspell::{BreakPolicy, SpellerConfig, SpellerHunspellDict, SuggestionConfig};
+use crate::Speller;
+
+/// Loads a `SpellerHunspellDict` with options, for when
+/// `SpellerHunspellDict::new` with the setters afterward isn't enough.
+/// Get one from `SpellerHunspellDict::builder`.
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use speller::{SpellerHunspellDict, SuggestionConfig};
+/// let speller = SpellerHunspellDict::builder()
+///     .dictionary(Path::new("en_US.dic"), Path::new("en_US.aff"))
+///     .user_dict(Path::new("personal.dic"))
+///     .suggestion_config(SuggestionConfig::fast())
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SpellerBuilder {
+    #[cfg(feature = "std-fs")]
+    dictionary: Option<(PathBuf, PathBuf)>,
+    #[cfg(feature = "std-fs")]
+    user_dict: Option<PathBuf>,
+    #[cfg(feature = "std-fs")]
+    wordlists: Vec<PathBuf>,
+    config: SpellerConfig,
+    suggestion_config: SuggestionConfig,
+    break_policy: BreakPolicy,
+    normalize_apostrophes: bool,
+    suggestion_time_limit: Option<Duration>,
+    number_suffixes: Option<Vec<String>>,
+    keyboard: Option<String>,
+    layout_pairs: Vec<(String, String)>,
+    build_folded_index: bool,
+}
+
+impl SpellerBuilder {
+    /// The Hunspell-format dictionary and affix file to load.
+    #[cfg(feature = "std-fs")]
+    pub fn dictionary(mut self, dictionary: &Path, affixes: &Path) -> Self {
+        self.dictionary = Some((dictionary.to_path_buf(), affixes.to_path_buf()));
+        self
+    }
+
+    /// Load the words from this user dict, and add accepted words to it.
+    /// See `Speller::set_user_dict`.
+    #[cfg(feature = "std-fs")]
+    pub fn user_dict(mut self, path: &Path) -> Self {
+        self.user_dict = Some(path.to_path_buf());
+        self
+    }
+
+    /// Also load the words from this file, which has the same format as
+    /// a user dict but isn't changed. This can be given more than once.
+    #[cfg(feature = "std-fs")]
+    pub fn wordlist(mut self, path: &Path) -> Self {
+        self.wordlists.push(path.to_path_buf());
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_config`.
+    pub fn config(mut self, config: SpellerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See `Speller::set_suggestion_config`.
+    pub fn suggestion_config(mut self, config: SuggestionConfig) -> Self {
+        self.suggestion_config = config;
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_break_policy`.
+    pub fn break_policy(mut self, policy: BreakPolicy) -> Self {
+        self.break_policy = policy;
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_normalize_apostrophes`.
+    pub fn normalize_apostrophes(mut self, normalize: bool) -> Self {
+        self.normalize_apostrophes = normalize;
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_suggestion_time_limit`.
+    pub fn suggestion_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.suggestion_time_limit = limit;
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_number_suffixes`.
+    pub fn number_suffixes(mut self, suffixes: &[&str]) -> Self {
+        self.number_suffixes = Some(suffixes.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    /// See `SpellerHunspellDict::set_keyboard`.
+    pub fn keyboard(mut self, keys: &str) -> Self {
+        self.keyboard = Some(keys.to_string());
+        self
+    }
+
+    /// Add a pair of keyboard layouts that words may have been typed in
+    /// by mistake. This can be given more than once.
+    /// See `SpellerHunspellDict::set_layout_pairs`.
+    pub fn layout_pair(mut self, layout: &str, other: &str) -> Self {
+        self.layout_pairs
+            .push((layout.to_string(), other.to_string()));
+        self
+    }
+
+    /// Build the index of case-folded words while loading, instead of
+    /// when it's first needed. See `SpellerHunspellDict::build_folded_index`.
+    /// The default is to build it when it's first needed.
+    pub fn build_folded_index(mut self, now: bool) -> Self {
+        self.build_folded_index = now;
+        self
+    }
+
+    /// Load the dictionary with these options.
+    #[cfg(feature = "std-fs")]
+    pub fn build(self) -> Result<SpellerHunspellDict> {
+        let (dictionary, affixes) = self
+            .dictionary
+            .as_ref()
+            .ok_or_else(|| anyhow!("No dictionary was given to load"))?;
+        let dict = SpellerHunspellDict::load(dictionary, affixes)?;
+        self.finish(dict)
+    }
+
+    /// Make the dictionary from the contents of a dictionary and affix
+    /// file with these options. See `SpellerHunspellDict::from_text`.
+    pub fn build_from_text(self, dictionary: &str, affixes: &str) -> Result<SpellerHunspellDict> {
+        let dict = SpellerHunspellDict::from_text(dictionary, affixes)?;
+        self.finish(dict)
+    }
+
+    fn finish(self, mut dict: SpellerHunspellDict) -> Result<SpellerHunspellDict> {
+        dict.set_config(self.config);
+        dict.set_suggestion_config(self.suggestion_config);
+        dict.set_break_policy(self.break_policy);
+        dict.set_suggestion_time_limit(self.suggestion_time_limit);
+        if let Some(suffixes) = &self.number_suffixes {
+            let suffixes: Vec<&str> = suffixes.iter().map(String::as_str).collect();
+            dict.set_number_suffixes(&suffixes);
+        }
+        dict.set_keyboard(self.keyboard.as_deref());
+        let layout_pairs: Vec<(&str, &str)> = self
+            .layout_pairs
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str()))
+            .collect();
+        dict.set_layout_pairs(&layout_pairs);
+        // Before the extra words, so that they get the same apostrophes
+        // as any other words added later.
+        dict.set_normalize_apostrophes(self.normalize_apostrophes);
+        #[cfg(feature = "std-fs")]
+        {
+            for path in &self.wordlists {
+                let text = read_to_string(path)
+                    .with_context(|| format!("Could not read {}", path.display()))?;
+                dict.add_user_dict_entries(&text);
+            }
+            if let Some(user_dict) = &self.user_dict {
+                dict.set_user_dict(user_dict)?;
+            }
+        }
+        if self.build_folded_index {
+            dict.build_folded_index();
+        }
+        Ok(dict)
+    }
+}
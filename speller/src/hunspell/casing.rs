@@ -0,0 +1,119 @@
+use caseless::default_case_fold_str;
+use std::borrow::Cow;
+use unicode_titlecase::StrTitleCase;
+
+/// How the dictionary's language changes letters between upper and
+/// lower case. This is set by the LANG directive of the affix file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Casing {
+    /// The Unicode default case mappings.
+    #[default]
+    Default,
+    /// Turkish, Azeri, and Crimean Tatar, where the dotted i and the
+    /// dotless ı are different letters. The capital of i is İ, and the
+    /// lowercase of I is ı.
+    Turkic,
+}
+
+impl Casing {
+    /// Returns the casing for a LANG value such as "tr_TR".
+    pub fn from_lang(lang: &str) -> Self {
+        let language = lang.split(['_', '-']).next().unwrap_or(lang);
+        match language {
+            "tr" | "az" | "crh" => Casing::Turkic,
+            _ => Casing::Default,
+        }
+    }
+
+    pub fn lowercase(self, word: &str) -> String {
+        match self {
+            Casing::Default if word.is_ascii() => word.to_ascii_lowercase(),
+            Casing::Default => word.to_lowercase(),
+            Casing::Turkic => lower_turkic_i(word).to_lowercase(),
+        }
+    }
+
+    pub fn uppercase(self, word: &str) -> String {
+        match self {
+            Casing::Default => word.to_uppercase(),
+            Casing::Turkic => word.replace('i', "İ").to_uppercase(),
+        }
+    }
+
+    /// Returns `word` with its first letter in titlecase and the rest
+    /// unchanged.
+    pub fn titlecase(self, word: &str) -> String {
+        match (self, word.strip_prefix('i')) {
+            (Casing::Turkic, Some(rest)) => format!("İ{}", rest),
+            _ => word.to_titlecase(),
+        }
+    }
+
+    /// Returns `word` with its first letter in titlecase and the rest
+    /// in lowercase.
+    pub fn titlecase_lower_rest(self, word: &str) -> String {
+        match self {
+            Casing::Default => word.to_titlecase_lower_rest(),
+            Casing::Turkic => {
+                let split = word.chars().next().map_or(0, char::len_utf8);
+                let (first, rest) = word.split_at(split);
+                self.titlecase(first) + &self.lowercase(rest)
+            }
+        }
+    }
+
+    /// Case-fold a word the way the dictionary's folded index does.
+    /// For ASCII words that's just lowercasing, which is much cheaper.
+    pub fn fold(self, word: &str) -> String {
+        match self {
+            Casing::Default if word.is_ascii() => word.to_ascii_lowercase(),
+            Casing::Default => default_case_fold_str(word),
+            Casing::Turkic => default_case_fold_str(&lower_turkic_i(word)),
+        }
+    }
+}
+
+/// Lowercase only the capital I and İ, the Turkic way.
+fn lower_turkic_i(word: &str) -> Cow<'_, str> {
+    if word.contains(['I', 'İ']) {
+        Cow::Owned(word.replace('I', "ı").replace('İ', "i"))
+    } else {
+        Cow::Borrowed(word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_lang() {
+        assert_eq!(Casing::Turkic, Casing::from_lang("tr_TR"));
+        assert_eq!(Casing::Turkic, Casing::from_lang("az"));
+        assert_eq!(Casing::Turkic, Casing::from_lang("crh-UA"));
+        assert_eq!(Casing::Default, Casing::from_lang("en_US"));
+        assert_eq!(Casing::Default, Casing::from_lang("trv"));
+    }
+
+    #[test]
+    fn test_turkic() {
+        let casing = Casing::Turkic;
+        assert_eq!("ılık iyi", casing.lowercase("ILIK İYİ"));
+        assert_eq!("ILIK İYİ", casing.uppercase("ılık iyi"));
+        assert_eq!("İstanbul", casing.titlecase("istanbul"));
+        assert_eq!("Irak", casing.titlecase_lower_rest("ıRAK"));
+        assert_eq!("İzmir", casing.titlecase_lower_rest("iZMİR"));
+        assert_eq!("istanbul", casing.fold("İSTANBUL"));
+        assert_eq!("ırak", casing.fold("IRAK"));
+    }
+
+    #[test]
+    fn test_default() {
+        let casing = Casing::Default;
+        assert_eq!("ilik", casing.lowercase("ILIK"));
+        assert_eq!("IYI", casing.uppercase("iyi"));
+        assert_eq!("Istanbul", casing.titlecase("istanbul"));
+        assert_eq!("irak", casing.fold("IRAK"));
+    }
+}
@@ -3,11 +3,13 @@ use anyhow::{bail, Result};
 use crate::hunspell::affixdata::{AffixData, AffixFlag};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompoundRule {
     v: Vec<CompoundElement>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CompoundElement {
     Multi(AffixFlag),
     Optional(AffixFlag),
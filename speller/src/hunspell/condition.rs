@@ -4,6 +4,7 @@
 /// prefix rules.
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum AffixCondChar {
     Any,
     Match(char),
@@ -23,6 +24,7 @@ impl AffixCondChar {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AffixCondition {
     /// A processed version of the condition string, suitable for fast matching.
     cond: Vec<AffixCondChar>,
@@ -0,0 +1,219 @@
+/// Spaces that group the digits of a number in many locales.
+const SPACES: [char; 4] = [
+    ' ',        // for when the no-break spaces below can't be typed
+    '\u{a0}',   // no-break space
+    '\u{2009}', // thin space
+    '\u{202f}', // narrow no-break space, used by the French
+];
+
+/// Characters used between groups of digits, or before the decimals,
+/// in the number formats of various locales.
+const ANY_SEPARATORS: [char; 10] = [
+    '.',        // decimal point
+    ',',        // decimal comma
+    '\'',       // Swiss thousands separator
+    '\u{2019}', // right single quotation mark, also used by the Swiss
+    SPACES[0],  // space
+    SPACES[1],  // no-break space
+    SPACES[2],  // thin space
+    SPACES[3],  // narrow no-break space
+    '\u{66b}',  // Arabic decimal separator
+    '\u{66c}',  // Arabic thousands separator
+];
+
+/// How the dictionary's language writes numbers, such as "1,000.50" in
+/// English and "1.000,50" in German. This is set by the LANG directive
+/// of the affix file, or by the name of the affix file if it has none.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NumberFormat {
+    /// The language is not known, so any of the separators may be used
+    /// anywhere between digits.
+    #[default]
+    Any,
+    /// "1,000.50", as in English.
+    Point,
+    /// "1.000,50", as in German.
+    Comma,
+    /// "1 000,50", as in French.
+    Space,
+    /// "1'000.50", as in Swiss German.
+    Swiss,
+    /// "١٬٠٠٠٫٥٠", as in Arabic.
+    Arabic,
+}
+
+impl NumberFormat {
+    /// Returns the number format for a LANG value such as "de_CH".
+    pub fn from_lang(lang: &str) -> Self {
+        let mut parts = lang.split(['_', '-']);
+        let language = parts.next().unwrap_or(lang);
+        let country = parts.next().unwrap_or_default();
+        match (language, country) {
+            ("de" | "it" | "rm", "CH" | "LI") => NumberFormat::Swiss,
+            ("en" | "ja" | "zh" | "ko" | "he" | "th" | "ga" | "hi" | "ms" | "fil" | "sw", _) => {
+                NumberFormat::Point
+            }
+            (
+                "de" | "es" | "pt" | "it" | "nl" | "tr" | "az" | "id" | "da" | "ro" | "el" | "hr"
+                | "sl" | "sr" | "ca" | "is" | "vi",
+                _,
+            ) => NumberFormat::Comma,
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "uk" | "sv" | "fi" | "nb" | "nn" | "no" | "hu"
+                | "bg" | "lt" | "lv" | "et" | "be" | "kk",
+                _,
+            ) => NumberFormat::Space,
+            ("ar" | "fa" | "ur", _) => NumberFormat::Arabic,
+            _ => NumberFormat::Any,
+        }
+    }
+
+    /// The separators between groups of three digits, and the ones
+    /// before the decimals.
+    fn separators(self) -> (&'static [char], &'static [char]) {
+        match self {
+            NumberFormat::Any => (&ANY_SEPARATORS, &ANY_SEPARATORS),
+            NumberFormat::Point => (&[',', ' ', '\u{a0}', '\u{2009}', '\u{202f}'], &['.']),
+            NumberFormat::Comma => (&['.', ' ', '\u{a0}', '\u{2009}', '\u{202f}'], &[',']),
+            NumberFormat::Space => (&SPACES, &[',']),
+            NumberFormat::Swiss => (
+                &['\'', '\u{2019}', ' ', '\u{a0}', '\u{2009}', '\u{202f}'],
+                &['.'],
+            ),
+            NumberFormat::Arabic => (&['\u{66c}', ','], &['\u{66b}', '.']),
+        }
+    }
+
+    /// Returns true if `word` is a number as the language writes them,
+    /// with an optional minus sign in front.
+    pub fn is_numeric(self, word: &str) -> bool {
+        // allow -- instead of the decimals, and - at the front
+        let (word, open_decimals) = match word.strip_suffix("--") {
+            Some(word) => (word, true),
+            None => (word, false),
+        };
+        let word = word.strip_prefix(['-', '\u{2212}']).unwrap_or(word);
+        if self == NumberFormat::Any {
+            return Self::is_any_numeric(word);
+        }
+        let (thousands, decimal) = self.separators();
+        // The number of digits before and after each separator.
+        let mut separators = Vec::new();
+        let mut runs = vec![0];
+        for c in word.chars() {
+            if c.is_numeric() {
+                // This accepts digits from all scripts, such as
+                // Arabic-Indic and Devanagari digits.
+                *runs.last_mut().unwrap() += 1;
+            } else if thousands.contains(&c) || decimal.contains(&c) {
+                separators.push(c);
+                runs.push(0);
+            } else {
+                return false;
+            }
+        }
+        if runs[0] == 0 {
+            return false;
+        }
+        // Each group after a thousands separator has three digits, and
+        // the decimals can only come last.
+        let last = separators.len().saturating_sub(1);
+        separators
+            .iter()
+            .zip(&runs[1..])
+            .enumerate()
+            .all(|(i, (c, run))| {
+                let grouped = thousands.contains(c) && *run == 3 && runs[0] <= 3;
+                grouped || (decimal.contains(c) && i == last && (*run > 0 || open_decimals))
+            })
+    }
+
+    // Any separator may go between any two digits.
+    fn is_any_numeric(word: &str) -> bool {
+        let mut seen_digit = false;
+        for c in word.chars() {
+            if ANY_SEPARATORS.contains(&c) {
+                if !seen_digit {
+                    return false;
+                }
+                seen_digit = false;
+            } else if c.is_numeric() {
+                seen_digit = true;
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_lang() {
+        assert_eq!(NumberFormat::Point, NumberFormat::from_lang("en_US"));
+        assert_eq!(NumberFormat::Comma, NumberFormat::from_lang("de_DE"));
+        assert_eq!(NumberFormat::Swiss, NumberFormat::from_lang("de_CH"));
+        assert_eq!(NumberFormat::Space, NumberFormat::from_lang("fr-FR"));
+        assert_eq!(NumberFormat::Arabic, NumberFormat::from_lang("ar"));
+        assert_eq!(NumberFormat::Any, NumberFormat::from_lang("xx_YY"));
+    }
+
+    #[test]
+    fn test_any() {
+        let any = NumberFormat::Any;
+        assert!(any.is_numeric("54"));
+        assert!(any.is_numeric("-1,000.00"));
+        assert!(any.is_numeric("-1,000.--"));
+        assert!(!any.is_numeric("1,ooo"));
+        assert!(!any.is_numeric("100,,000"));
+        assert!(!any.is_numeric(".."));
+        assert!(!any.is_numeric(".50"));
+        assert!(any.is_numeric("1\u{202f}000"));
+        assert!(any.is_numeric("1'000.50"));
+        assert!(any.is_numeric("\u{2212}1.000,50"));
+        assert!(any.is_numeric("١٢٣٫٤٥"));
+        assert!(any.is_numeric("१२३"));
+        assert!(!any.is_numeric("1\u{202f}\u{202f}000"));
+        assert!(!any.is_numeric("\u{a0}1"));
+        assert!(any.is_numeric("1 000"));
+        assert!(any.is_numeric("3½"));
+    }
+
+    #[test]
+    fn test_point() {
+        let point = NumberFormat::Point;
+        assert!(point.is_numeric("54"));
+        assert!(point.is_numeric("-1,000.00"));
+        assert!(point.is_numeric("-1,000.--"));
+        assert!(point.is_numeric("1 000"));
+        assert!(point.is_numeric("3½"));
+        assert!(point.is_numeric("१२३"));
+        // Separators of other locales
+        assert!(!point.is_numeric("1,5"));
+        assert!(!point.is_numeric("1.000,50"));
+        assert!(!point.is_numeric("1'000"));
+        assert!(!point.is_numeric("1.000.000"));
+        assert!(!point.is_numeric("1000,000"));
+        assert!(!point.is_numeric("1.5."));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        assert!(NumberFormat::Comma.is_numeric("\u{2212}1.000,50"));
+        assert!(NumberFormat::Comma.is_numeric("1,5"));
+        assert!(!NumberFormat::Comma.is_numeric("1.5"));
+        assert!(!NumberFormat::Comma.is_numeric("1'000"));
+        assert!(NumberFormat::Space.is_numeric("1\u{202f}000,50"));
+        assert!(!NumberFormat::Space.is_numeric("1.000"));
+        assert!(NumberFormat::Swiss.is_numeric("1'000.50"));
+        assert!(NumberFormat::Swiss.is_numeric("1\u{2019}000"));
+        assert!(!NumberFormat::Swiss.is_numeric("1,5"));
+        assert!(NumberFormat::Arabic.is_numeric("١٬٠٠٠٫٥٠"));
+        assert!(NumberFormat::Arabic.is_numeric("١٢٣٫٤٥"));
+        assert!(!NumberFormat::Arabic.is_numeric("1'000"));
+    }
+}
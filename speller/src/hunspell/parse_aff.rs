@@ -1,5 +1,6 @@
 /// Parser for hunspell-format .aff files
 use anyhow::{bail, Result};
+#[cfg(feature = "std-fs")]
 use encoding::Encoding;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_till1};
@@ -25,11 +26,14 @@ enum AffixLine<'a> {
     SetEncoding(&'a str),
     SetFlagMode(FlagMode),
     SetKeyboardString(&'a str),
+    SetLanguage(&'a str),
     SetTryString(&'a str),
     SetExtraWordString(&'a str),
     SetFlag(WordFlags, &'a str, &'a str),
     SetCompoundMin(u8),
     SetMaxNGramSuggestions(u8),
+    SetMaxDiff(u8),
+    SetOnlyMaxDiff,
     AddIconv((&'a str, &'a str)),
     AddOconv((&'a str, &'a str)),
     AddCompoundRule(&'a str),
@@ -161,20 +165,22 @@ fn set_flag_mode(s: &str) -> IResult<&str, AffixLine> {
     map(keyword("FLAG", flag_mode), AffixLine::SetFlagMode)(s)
 }
 
-fn maxdiff(s: Input) -> IResult<Input, AffixLine> {
-    // Not implemented, and not relevant for our implementation
-    value(AffixLine::Empty, keyword("MAXDIFF", value_string))(s)
+fn set_max_diff(s: &str) -> IResult<&str, AffixLine> {
+    map(keyword("MAXDIFF", u8), AffixLine::SetMaxDiff)(s)
 }
 
-fn onlymaxdiff(s: Input) -> IResult<Input, AffixLine> {
-    // Not implemented, and not relevant for our implementation
-    value(AffixLine::Empty, tag("ONLYMAXDIFF"))(s)
+fn set_only_max_diff(s: &str) -> IResult<&str, AffixLine> {
+    value(AffixLine::SetOnlyMaxDiff, tag("ONLYMAXDIFF"))(s)
 }
 
 fn set_keyboard_string(s: &str) -> IResult<&str, AffixLine> {
     map(keyword("KEY", value_string), AffixLine::SetKeyboardString)(s)
 }
 
+fn set_language(s: &str) -> IResult<&str, AffixLine> {
+    map(keyword("LANG", value_string), AffixLine::SetLanguage)(s)
+}
+
 fn set_try_string(s: &str) -> IResult<&str, AffixLine> {
     map(keyword("TRY", value_string), AffixLine::SetTryString)(s)
 }
@@ -304,6 +310,7 @@ fn line(s: &str) -> IResult<&str, AffixLine> {
         set_encoding,
         set_flag_mode,
         set_keyboard_string,
+        set_language,
         set_try_string,
         set_extra_word_string,
         assign_flag,
@@ -319,8 +326,7 @@ fn line(s: &str) -> IResult<&str, AffixLine> {
         set_checksharps,
         add_affix("PFX", true),
         add_affix("SFX", false),
-        maxdiff,
-        onlymaxdiff,
+        alt((set_max_diff, set_only_max_diff)),
         success(AffixLine::Empty),
     ))(s)
 }
@@ -353,6 +359,7 @@ pub fn parse_affix_data(s: &str) -> Result<AffixData> {
             AffixLine::SetKeyboardString(k) => {
                 d.keyboard_string = Some(k.to_string());
             }
+            AffixLine::SetLanguage(lang) => d.lang = Some(lang.to_string()),
             AffixLine::SetTryString(t) => d.try_string = Some(t.to_string()),
             AffixLine::SetExtraWordString(t) => {
                 d.extra_word_string = Some(t.to_string());
@@ -366,6 +373,8 @@ pub fn parse_affix_data(s: &str) -> Result<AffixData> {
             }
             AffixLine::SetCompoundMin(v) => d.compound_min = v,
             AffixLine::SetMaxNGramSuggestions(v) => d.max_ngram_suggestions = v,
+            AffixLine::SetMaxDiff(v) => d.max_diff = Some(v.min(10)),
+            AffixLine::SetOnlyMaxDiff => d.only_max_diff = true,
             AffixLine::AddIconv((c1, c2)) => {
                 d.iconv.push(c1, c2);
             }
@@ -422,36 +431,45 @@ pub fn parse_affix_data(s: &str) -> Result<AffixData> {
     Ok(d)
 }
 
+#[cfg(feature = "std-fs")]
 pub fn determine_encoding(bytes: &[u8]) -> &'static dyn Encoding {
     for line in bytes.split(|b| *b == b'\n') {
         if line.starts_with(b"SET ") {
-            return match String::from_utf8_lossy(&line[4..]).trim() {
-                "UTF-8" => encoding::all::UTF_8,
-                "ISO8859-1" => encoding::all::ISO_8859_1,
-                "ISO8859-2" => encoding::all::ISO_8859_2,
-                "ISO8859-3" => encoding::all::ISO_8859_3,
-                "ISO8859-4" => encoding::all::ISO_8859_4,
-                "ISO8859-5" => encoding::all::ISO_8859_5,
-                "ISO8859-6" => encoding::all::ISO_8859_6,
-                "ISO8859-7" => encoding::all::ISO_8859_7,
-                "ISO8859-8" => encoding::all::ISO_8859_8,
-                "ISO8859-10" => encoding::all::ISO_8859_10,
-                "ISO8859-13" => encoding::all::ISO_8859_13,
-                "ISO8859-14" => encoding::all::ISO_8859_14,
-                "ISO8859-15" => encoding::all::ISO_8859_15,
-                "KOI8-R" => encoding::all::KOI8_R,
-                "KOI8-U" => encoding::all::KOI8_U,
-                "cp1251" => encoding::all::WINDOWS_1251,
-                _ => encoding::all::UTF_8,
-            };
+            return encoding_from_name(String::from_utf8_lossy(&line[4..]).trim());
         }
     }
     encoding::all::UTF_8
 }
 
+/// Map an encoding name as used in the SET directive to an `Encoding`.
+/// Unknown encodings are treated as UTF-8.
+#[cfg(feature = "std-fs")]
+pub fn encoding_from_name(name: &str) -> &'static dyn Encoding {
+    match name {
+        "UTF-8" => encoding::all::UTF_8,
+        "ISO8859-1" => encoding::all::ISO_8859_1,
+        "ISO8859-2" => encoding::all::ISO_8859_2,
+        "ISO8859-3" => encoding::all::ISO_8859_3,
+        "ISO8859-4" => encoding::all::ISO_8859_4,
+        "ISO8859-5" => encoding::all::ISO_8859_5,
+        "ISO8859-6" => encoding::all::ISO_8859_6,
+        "ISO8859-7" => encoding::all::ISO_8859_7,
+        "ISO8859-8" => encoding::all::ISO_8859_8,
+        "ISO8859-10" => encoding::all::ISO_8859_10,
+        "ISO8859-13" => encoding::all::ISO_8859_13,
+        "ISO8859-14" => encoding::all::ISO_8859_14,
+        "ISO8859-15" => encoding::all::ISO_8859_15,
+        "KOI8-R" => encoding::all::KOI8_R,
+        "KOI8-U" => encoding::all::KOI8_U,
+        "cp1251" => encoding::all::WINDOWS_1251,
+        _ => encoding::all::UTF_8,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hunspell::casing::Casing;
 
     #[test]
     fn rep_unanchored() {
@@ -481,4 +499,27 @@ mod tests {
         let d = parse_affix_data(s).unwrap();
         assert_eq!("a lot", d.replacements.conv("alot"));
     }
+
+    #[test]
+    fn lang() {
+        let d = parse_affix_data("SET UTF-8\nLANG tr_TR\n").unwrap();
+        assert_eq!(Some("tr_TR"), d.lang.as_deref());
+        assert_eq!(Casing::Turkic, d.casing);
+
+        let d = parse_affix_data("SET UTF-8\n").unwrap();
+        assert_eq!(None, d.lang);
+        assert_eq!(Casing::Default, d.casing);
+    }
+
+    #[test]
+    fn iconv_decomposed() {
+        let s = "ICONV 3\nICONV a b\nICONV e\u{0323}\u{0302} ệ\nICONV \u{0301} \u{0300}";
+        let d = parse_affix_data(s).unwrap();
+        // The a is not taken away from its accent.
+        assert_eq!("ba\u{0300}", d.iconv.conv("aa\u{0300}"));
+        assert_eq!("bệ", d.iconv.conv("ae\u{0323}\u{0302}"));
+        // Unless the rule is about the accent, even if another follows.
+        assert_eq!("ba\u{0300}", d.iconv.conv("aa\u{0301}"));
+        assert_eq!("ba\u{0300}\u{0323}", d.iconv.conv("aa\u{0301}\u{0323}"));
+    }
 }
@@ -1,6 +1,10 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::affix_trie::PrefixTrie;
 use crate::hunspell::suggcollector::SuggCollector;
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Rep {
     anchor_begin: bool,
     anchor_end: bool,
@@ -9,23 +13,52 @@ struct Rep {
 }
 
 impl Rep {
-    fn matches(&self, word: &str, at_start: bool) -> bool {
-        if self.anchor_begin && !at_start {
-            false
-        } else if self.anchor_end {
-            word == self.from
-        } else {
-            word.starts_with(&self.from)
-        }
+    /// Check the anchors of a rep whose `from` is already known to be
+    /// found at `at` in `word`.
+    /// A rep also must not split a letter from the accents after it, as
+    /// in a decomposed "ệ", because then the accents would end up on
+    /// whatever the rep puts there. So a rep that starts where a letter
+    /// starts must end where one ends. Reps that start inside a letter
+    /// are about its accents, and those may end anywhere.
+    fn anchors_match(&self, word: &str, at: usize, letters: &Letters) -> bool {
+        let end = at + self.from.len();
+        (!letters.starts_at(at) || letters.starts_at(end))
+            && (at == 0 || !self.anchor_begin)
+            && (!self.anchor_end || end == word.len())
+    }
+}
+
+/// Where the letters of a word start, as the byte offsets of its
+/// grapheme clusters. The end of the word counts as a start too.
+struct Letters(Vec<usize>);
+
+impl Letters {
+    fn new(word: &str) -> Self {
+        let starts = word.grapheme_indices(true).map(|(i, _)| i);
+        Letters(starts.chain([word.len()]).collect())
+    }
+
+    fn starts_at(&self, i: usize) -> bool {
+        self.0.binary_search(&i).is_ok()
     }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Replacements {
     reps: Vec<Rep>,
+    /// Indexes into `reps`, by their `from` strings. Dictionaries such as
+    /// pt_BR have hundreds of reps, and looking up the ones that match
+    /// at a position in the word this way only costs as much as the
+    /// longest match.
+    by_from: PrefixTrie<usize>,
 }
 
 impl Replacements {
+    pub fn is_empty(&self) -> bool {
+        self.reps.is_empty()
+    }
+
     pub fn push(&mut self, from: &str, to: &str) {
         let mut rep = Rep::default();
         let mut from = from;
@@ -39,30 +72,53 @@ impl Replacements {
         }
         rep.from = from.to_string();
         rep.to = to.to_string();
+        self.by_from.insert(&rep.from, self.reps.len());
         self.reps.push(rep);
     }
 
-    // TODO make this logarithmic instead of linear
-    fn longest_match(&self, word: &str, at_start: bool) -> Option<&Rep> {
-        let mut longest_len = 0;
+    /// Returns the indexes of the reps that match at `at` in `word`,
+    /// in the order they were added.
+    fn matches(&self, word: &str, at: usize, letters: &Letters, found: &mut Vec<usize>) {
+        found.clear();
+        self.by_from.lookup(&word[at..], |i| {
+            if self.reps[i].anchors_match(word, at, letters) {
+                found.push(i);
+            }
+            false
+        });
+        // The trie gives them shortest first.
+        found.sort_unstable();
+    }
+
+    fn longest_match(&self, word: &str, at: usize, letters: &Letters) -> Option<&Rep> {
         let mut longest_rep: Option<&Rep> = None;
-        for rep in &self.reps {
-            if rep.from.len() > longest_len && rep.matches(word, at_start) {
-                longest_len = rep.from.len();
+        // The trie gives shorter matches first, so the last one is the
+        // longest. Of equally long ones the first added wins.
+        self.by_from.lookup(&word[at..], |i| {
+            let rep = &self.reps[i];
+            if !rep.from.is_empty()
+                && longest_rep.is_none_or(|r| rep.from.len() > r.from.len())
+                && rep.anchors_match(word, at, letters)
+            {
                 longest_rep = Some(rep);
             }
-        }
+            false
+        });
         longest_rep
     }
 
     pub fn conv(&self, word: &str) -> String {
+        if self.reps.is_empty() {
+            return word.to_string();
+        }
+        let letters = Letters::new(word);
         let mut output = String::new();
         let mut skip_to = 0;
         for (i, c) in word.char_indices() {
             if i < skip_to {
                 continue;
             }
-            if let Some(rep) = self.longest_match(&word[i..], i == 0) {
+            if let Some(rep) = self.longest_match(word, i, &letters) {
                 output += &rep.to;
                 skip_to = i + rep.from.len();
             } else {
@@ -75,18 +131,25 @@ impl Replacements {
     pub fn suggest(&self, word: &str, collector: &mut SuggCollector) {
         collector.new_source("rep");
         let mut sugg = String::with_capacity(word.len() * 2);
+        let mut found = Vec::new();
+        let letters = Letters::new(word);
         for (i, _) in word.char_indices() {
-            // TODO: optimize by putting start-anchored reps in a separate list
-            for rep in &self.reps {
-                if rep.matches(&word[i..], i == 0) {
-                    sugg.clear();
-                    sugg.push_str(&word[..i]);
-                    sugg.push_str(&rep.to);
-                    sugg.push_str(&word[i + rep.from.len()..]);
+            self.matches(word, i, &letters, &mut found);
+            for &r in &found {
+                let rep = &self.reps[r];
+                sugg.clear();
+                sugg.push_str(&word[..i]);
+                sugg.push_str(&rep.to);
+                sugg.push_str(&word[i + rep.from.len()..]);
+                // A replacement that splits the word is the dictionary's
+                // own answer for a common mistake, as in "alot" -> "a lot".
+                if rep.to.contains(' ') {
+                    collector.suggest_first(&sugg);
+                } else {
                     collector.suggest(&sugg);
-                    if collector.limit() {
-                        return;
-                    }
+                }
+                if collector.limit() {
+                    return;
                 }
             }
         }
@@ -1,10 +1,10 @@
 use std::cmp::min;
-use unicode_titlecase::StrTitleCase;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
+use crate::hunspell::casing::Casing;
 use crate::hunspell::{CapStyle, SpellerHunspellDict};
-
-/// No more than this many suggestion attempts from any one source.
-const MAX_SUGGESTS_PER_SOURCE: usize = 1000;
+use crate::{Suggestion, Suggestions};
 
 #[derive(Clone, Debug)]
 pub struct SuggCollector<'a> {
@@ -13,15 +13,32 @@ pub struct SuggCollector<'a> {
     caps: CapStyle,
     max: usize,
     limit: usize,
-    suggs: Vec<String>,
-
-    current_source: &'a str,
+    suggs: Vec<Suggestion>,
+    // The case-folded forms of `suggs`, so that they don't have to be
+    // folded again for every new suggestion.
+    folded: Vec<String>,
+    // How many suggestions at the start of `suggs` came from
+    // `suggest_first`. They stay ahead of all the others.
+    first: usize,
+
+    current_source: &'static str,
     counter: usize,
     done: bool,
+    deadline: Option<Instant>,
+    // Set by the caller when it no longer wants the suggestions.
+    cancel: Option<&'a AtomicBool>,
+    // Set when a candidate was rejected only because it is forbidden
+    // or marked NOSUGGEST.
+    forbidden: bool,
 }
 
 impl<'a> SuggCollector<'a> {
-    pub fn new(dict: &'a SpellerHunspellDict, word: &'a str, max: usize) -> Self {
+    pub fn new(
+        dict: &'a SpellerHunspellDict,
+        word: &'a str,
+        max: usize,
+        time_limit: Option<Duration>,
+    ) -> Self {
         SuggCollector {
             dict,
             word,
@@ -29,9 +46,14 @@ impl<'a> SuggCollector<'a> {
             max,
             limit: max,
             suggs: Vec::new(),
+            folded: Vec::new(),
+            first: 0,
             current_source: "unknown",
             counter: 0,
             done: false,
+            deadline: time_limit.map(|limit| Instant::now() + limit),
+            cancel: None,
+            forbidden: false,
         }
     }
 
@@ -44,27 +66,83 @@ impl<'a> SuggCollector<'a> {
         self.limit = min(self.suggs.len() + reserve, self.max);
     }
 
-    pub fn new_source(&mut self, name: &'a str) {
+    pub fn new_source(&mut self, name: &'static str) {
         self.current_source = name;
-        self.counter = MAX_SUGGESTS_PER_SOURCE;
+        self.counter = if self.dict.suggestion_config().is_enabled(name) {
+            self.dict.config().max_suggests_per_source
+        } else {
+            0
+        };
     }
 
     pub fn has_suggestions(&self) -> bool {
         !self.suggs.is_empty()
     }
 
+    pub fn set_cancel(&mut self, cancel: &'a AtomicBool) {
+        self.cancel = Some(cancel);
+    }
+
+    /// Returns true if the time is up, or if the search was cancelled.
+    pub fn timed_out(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// How the dictionary's language changes the case of letters.
+    pub fn casing(&self) -> Casing {
+        self.dict.casing()
+    }
+
     /// Return true iff no more suggestions should be submitted
     pub fn limit(&self) -> bool {
-        self.done || self.suggs.len() >= self.limit || self.counter == 0
+        self.done || self.suggs.len() >= self.limit || self.counter == 0 || self.timed_out()
+    }
+
+    /// Suggest a correction that is almost certainly what was meant,
+    /// such as a REP entry that splits "alot" into "a lot", or a word
+    /// that was typed in the wrong keyboard layout. These go before all
+    /// other suggestions, even ones that were made earlier, and are
+    /// kept by `suggest_priority`.
+    pub fn suggest_first(&mut self, sugg: &str) {
+        // Make room if the list is full, and put the last suggestion
+        // back if this one is rejected after all.
+        let mut bumped = None;
+        if self.suggs.len() >= self.limit && self.suggs.len() > self.first {
+            bumped = self.suggs.pop().zip(self.folded.pop());
+        }
+        let len = self.suggs.len();
+        self.suggest(sugg);
+        if self.suggs.len() > len {
+            let sugg = self.suggs.remove(len);
+            let folded = self.folded.remove(len);
+            self.suggs.insert(self.first, sugg);
+            self.folded.insert(self.first, folded);
+            self.first += 1;
+        } else if let Some((sugg, folded)) = bumped {
+            self.suggs.push(sugg);
+            self.folded.push(folded);
+        }
     }
 
     pub fn suggest_priority(&mut self, sugg: &str) {
         // If the suggestion is in the dictionary as a single entry
         // (so no space or break checking), then it overrides all other
-        // suggestions.
+        // suggestions except those from `suggest_first`.
         if sugg != self.word && self.dict.check_suggestion_priority(sugg, self.caps) {
-            self.suggs.clear();
-            self.suggs.push(sugg.to_string());
+            self.suggs.truncate(self.first);
+            self.folded.truncate(self.first);
+            if !self.suggs.iter().any(|s| s.word == sugg) {
+                self.suggs.push(Suggestion {
+                    word: sugg.to_string(),
+                    source: self.current_source,
+                    score: None,
+                });
+                self.folded.push(self.casing().fold(sugg));
+            }
             self.done = true;
         } else {
             self.suggest(sugg);
@@ -72,7 +150,16 @@ impl<'a> SuggCollector<'a> {
     }
 
     pub fn suggest(&mut self, sugg: &str) {
-        if self.limit() || sugg == self.word || self.suggs.iter().any(|s| s == sugg) {
+        self.suggest_scored(sugg, None);
+    }
+
+    /// Like `suggest`, for sources that rank their suggestions by score.
+    pub fn suggest_with_score(&mut self, sugg: &str, score: usize) {
+        self.suggest_scored(sugg, Some(score));
+    }
+
+    fn suggest_scored(&mut self, sugg: &str, score: Option<usize>) {
+        if self.limit() || sugg == self.word || self.suggs.iter().any(|s| s.word == sugg) {
             return;
         }
         self.counter -= 1;
@@ -81,30 +168,76 @@ impl<'a> SuggCollector<'a> {
         // capitalized or uppercased suggestions. Do fall back on the
         // unmodified suggestion in case the capitalized forms are rejected.
         if matches!(self.caps, CapStyle::Capitalized) {
-            let cap = sugg.to_titlecase();
-            if self.dict.check_suggestion(&cap, self.caps) {
-                self.suggs.push(cap);
+            let cap = self.casing().titlecase(sugg);
+            if self.check(&cap) {
+                self.push(cap, score);
                 return;
             }
         } else if matches!(self.caps, CapStyle::AllCaps) {
-            let cap = sugg.to_uppercase();
-            if self.dict.check_suggestion(&cap, self.caps) {
-                self.suggs.push(cap);
+            let cap = self.casing().uppercase(sugg);
+            if self.check(&cap) {
+                self.push(cap, score);
                 return;
             }
         }
 
+        if self.check(sugg) {
+            self.push(sugg.to_string(), score);
+        }
+    }
+
+    // Check a suggestion and remember if it was rejected for being
+    // forbidden, so that the caller can be told why there are no
+    // suggestions.
+    fn check(&mut self, sugg: &str) -> bool {
         if self.dict.check_suggestion(sugg, self.caps) {
-            self.suggs.push(sugg.to_string());
+            return true;
+        }
+        if self.dict.is_forbidden_suggestion(sugg) {
+            self.forbidden = true;
         }
+        false
     }
-}
 
-impl<'a> IntoIterator for SuggCollector<'a> {
-    type Item = String;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    // Add a suggestion unless it only differs in case from one that's
+    // already there. Of the two, keep the one that has the same
+    // capitalization as the misspelled word.
+    fn push(&mut self, sugg: String, score: Option<usize>) {
+        if sugg == self.word {
+            return;
+        }
+        let folded = self.casing().fold(&sugg);
+        let sugg = Suggestion {
+            word: sugg,
+            source: self.current_source,
+            score,
+        };
+        match self.folded.iter().position(|s| *s == folded) {
+            Some(i) => {
+                if CapStyle::from_str(&sugg.word) == self.caps
+                    && CapStyle::from_str(&self.suggs[i].word) != self.caps
+                {
+                    self.suggs[i] = sugg;
+                }
+            }
+            None => {
+                self.suggs.push(sugg);
+                self.folded.push(folded);
+            }
+        }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.suggs.into_iter()
+    /// Returns the suggestions with where they came from, or if there
+    /// are none then the reason why.
+    pub fn into_detailed(self) -> Result<Vec<Suggestion>, Suggestions> {
+        if !self.suggs.is_empty() {
+            Ok(self.suggs)
+        } else if self.timed_out() {
+            Err(Suggestions::TimedOut)
+        } else if self.forbidden {
+            Err(Suggestions::AllForbidden)
+        } else {
+            Err(Suggestions::NoCandidates)
+        }
     }
 }
@@ -3,7 +3,7 @@ use itertools::Itertools;
 use rayon::prelude::*;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
-use std::mem::swap;
+use unicode_casing::CharExt;
 
 use crate::delins::delins;
 use crate::hunspell::suggcollector::SuggCollector;
@@ -11,48 +11,59 @@ use crate::hunspell::wordflags::WordFlags;
 use crate::ngram::ngram;
 use crate::SpellerHunspellDict;
 
-const MAX_NGRAM_ROOTS: usize = 100;
-const MAX_NGRAM_SUGG: usize = 20;
-const MAX_DELINS_ROOTS: usize = 100;
-const MAX_DELINS_SUGG: usize = 20;
 /// This is a heuristic. Suggestions scoring worse than this are not offered.
 const MAX_DELINS_SCORE: usize = 5;
 /// Don't accept too short delins suggestions; they rarely have anything
 /// to do with the original word.
 const MAX_DELINS_SHORTER: usize = 3;
+/// How many characters the related_char suggestions replace at most.
+/// Words that need more, such as long words typed without any of their
+/// accents, are found through the accent index instead.
+const MAX_RELATED_CHANGES: usize = 3;
 
 pub fn related_char_suggestions(related: &[String], word: &str, collector: &mut SuggCollector) {
     collector.new_source("related_char");
-    // Try all possible combinations of replacements of related characters.
-    // This can result in a huge number of candidates for long words.
-    // Rely on the `suggest` callback to limit the time spent here.
-    // When suggest() returns false, we abort.
     let wvec: Vec<char> = word.chars().collect();
-    let mut candidates: Vec<Vec<char>> = vec![wvec.clone()];
-
-    // Process the related classes in order, because the affix file ordered
-    // them starting with the most likely.
-    for rc in related.iter() {
-        for i in 0..wvec.len() {
-            if rc.contains(wvec[i]) {
-                let mut new_candidates: Vec<Vec<char>> = Vec::new();
-                for cnd in candidates.drain(..) {
-                    for newc in rc.chars() {
-                        if newc == wvec[i] {
-                            continue;
-                        }
-                        let mut newcnd: Vec<char> = cnd.clone();
-                        newcnd[i] = newc;
-                        let newword = newcnd.iter().collect::<String>();
-                        collector.suggest(&newword);
-                        if collector.limit() {
-                            return;
-                        }
-                        new_candidates.push(newcnd);
-                    }
-                    new_candidates.push(cnd);
+    // For each place in the word that has related characters, the ones
+    // to try there instead. They are in the order of the related classes,
+    // because the affix file ordered them starting with the most likely.
+    let places: Vec<(usize, Vec<char>)> = wvec
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &c)| {
+            let others: Vec<char> = related
+                .iter()
+                .filter(|rc| rc.contains(c))
+                .flat_map(|rc| rc.chars())
+                .filter(|&newc| newc != c)
+                .unique()
+                .collect();
+            (!others.is_empty()).then_some((i, others))
+        })
+        .collect();
+
+    // Try all the candidates with one replacement before those with two,
+    // and so on. Trying all the combinations in turn would use up the
+    // candidates allowed per source on unlikely ones in long words.
+    // Rely on the `suggest` callback to limit the time spent here.
+    let mut candidate = wvec.clone();
+    for count in 1..=MAX_RELATED_CHANGES.min(places.len()) {
+        for chosen in places.iter().combinations(count) {
+            for newcs in chosen
+                .iter()
+                .map(|(_, others)| others.iter())
+                .multi_cartesian_product()
+            {
+                for ((i, _), &newc) in chosen.iter().zip(newcs) {
+                    candidate[*i] = newc;
+                }
+                collector.suggest(&candidate.iter().collect::<String>());
+                if collector.limit() {
+                    return;
                 }
-                swap(&mut candidates, &mut new_candidates);
+            }
+            for (i, _) in chosen {
+                candidate[*i] = wvec[*i];
             }
         }
     }
@@ -274,6 +285,47 @@ pub fn wrong_key_suggestions(word: &str, keyboard: &str, collector: &mut SuggCol
     }
 }
 
+/// Was the word typed with the keyboard in the wrong layout? Each of
+/// `layout_pairs` maps the characters on the keys of one layout to the
+/// characters on the same keys of the other, and is tried both ways.
+pub fn wrong_layout_suggestions(
+    word: &str,
+    layout_pairs: &[Vec<(char, char)>],
+    collector: &mut SuggCollector,
+) {
+    collector.new_source("wrong_layout");
+    for keys in layout_pairs {
+        let forward = keys.iter().map(|&(a, b)| (a, b));
+        let backward = keys.iter().map(|&(a, b)| (b, a));
+        for sugg in [retype(word, forward), retype(word, backward)]
+            .into_iter()
+            .flatten()
+        {
+            collector.suggest_first(&sugg);
+            if collector.limit() {
+                return;
+            }
+        }
+    }
+}
+
+/// Type `word` again on the other layout, keeping capitals. Returns None
+/// if one of its letters isn't on the first layout. Other characters
+/// that aren't on it stay as they are.
+fn retype(word: &str, keys: impl Iterator<Item = (char, char)> + Clone) -> Option<String> {
+    word.chars()
+        .map(|c| {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            match keys.clone().find(|&(from, _)| from == lower) {
+                Some((_, other)) if c != lower => other.to_uppercase().next(),
+                Some((_, other)) => Some(other),
+                None if c.is_alphabetic() => None,
+                None => Some(c),
+            }
+        })
+        .collect()
+}
+
 pub fn split_word_suggestions(word: &str, collector: &mut SuggCollector) {
     collector.new_source("split_word");
     // Try adding a space between each pair of letters
@@ -290,6 +342,29 @@ pub fn split_word_suggestions(word: &str, collector: &mut SuggCollector) {
     }
 }
 
+/// Did the user type a space inside a compound word?
+/// Try joining the words, with and without lowercasing the second one,
+/// because in German the parts of "Arbeits Computer" are both capitalized
+/// but the compound is "Arbeitscomputer".
+pub fn join_words_suggestions(word: &str, collector: &mut SuggCollector) {
+    collector.new_source("join_words");
+    for (i, _) in word.match_indices(' ') {
+        let (first, second) = (&word[..i], &word[i + 1..]);
+        if first.is_empty() || second.is_empty() {
+            continue;
+        }
+        collector.suggest(&format!("{}{}", first, second));
+        let mut chars = second.chars();
+        if let Some(c) = chars.next().filter(|c| c.is_uppercase()) {
+            let c = collector.casing().lowercase(&second[..c.len_utf8()]);
+            collector.suggest(&format!("{}{}{}", first, c, chars.as_str()));
+        }
+        if collector.limit() {
+            return;
+        }
+    }
+}
+
 pub fn split_word_with_dash_suggestions(word: &str, collector: &mut SuggCollector) {
     collector.new_source("split_word_with_dash");
     let mut sugg = String::with_capacity(word.len() + 1);
@@ -313,18 +388,23 @@ pub fn split_word_with_dash_suggestions(word: &str, collector: &mut SuggCollecto
 /// Did the user forget to hit shift on one letter?
 pub fn capitalize_char_suggestions(word: &str, collector: &mut SuggCollector) {
     collector.new_source("capitalize_char");
+    let casing = collector.casing();
     let mut sugg = String::with_capacity(word.len());
     for (i, c) in word.char_indices() {
-        if c.is_uppercase() {
+        if c.is_uppercase() || c.is_titlecase() {
             continue;
         }
-        sugg.clear();
-        sugg.push_str(&word[..i]);
-        // Uppercasing a char may produce multiple chars
-        for c_up in c.to_uppercase() {
-            sugg.push(c_up);
+        if i == 0 {
+            // At the start of a word, a digraph such as ǉ is capitalized
+            // as the titlecase ǈ rather than the uppercase Ǉ.
+            sugg = casing.titlecase(word);
+        } else {
+            sugg.clear();
+            sugg.push_str(&word[..i]);
+            // Uppercasing a char may produce multiple chars
+            sugg.push_str(&casing.uppercase(&word[i..i + c.len_utf8()]));
+            sugg.push_str(&word[i + c.len_utf8()..]);
         }
-        sugg.push_str(&word[i + c.len_utf8()..]);
         collector.suggest(&sugg);
         if collector.limit() {
             return;
@@ -337,6 +417,10 @@ pub fn ngram_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mut
     if collector.limit() {
         return;
     }
+    let config = dict.suggestion_config();
+    if config.max_ngram_forms == 0 {
+        return;
+    }
 
     #[derive(Eq, PartialEq)]
     struct HeapItem<T> {
@@ -360,34 +444,48 @@ pub fn ngram_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mut
 
     let wvec = word.chars().collect::<Vec<char>>();
 
-    let mut rootscores: Vec<HeapItem<&String>> = dict
+    let mut rootscores: Vec<HeapItem<&str>> = dict
         .words
         .par_iter()
-        .filter_map(|(root, homonyms)| {
+        .map(|(root, homonyms)| {
+            // This scan is the slowest part of the search, so it stops
+            // as soon as the time is up.
+            if collector.timed_out() {
+                return None;
+            }
             for winfo in homonyms.iter() {
                 if winfo.word_flags.intersects(
                     WordFlags::Forbidden | WordFlags::NoSuggest | WordFlags::OnlyInCompound,
                 ) {
-                    return None;
+                    return Some(None);
                 }
             }
 
             let rvec = root.chars().collect::<Vec<char>>();
             if rvec.len() > wvec.len() + 2 {
-                return None;
+                return Some(None);
             }
             let score = ngram(3, &wvec, &rvec);
-            Some(HeapItem { word: root, score })
+            Some(Some(HeapItem { word: root, score }))
         })
+        .while_some()
+        .flatten()
         .collect();
+    if collector.timed_out() {
+        return;
+    }
     rootscores.par_sort_unstable();
-    rootscores.truncate(MAX_NGRAM_ROOTS);
+    rootscores.truncate(config.max_ngram_roots);
 
     // Heuristic minimum score, to discard bad suggestions
     let heuristic = ngram(1, &wvec, &wvec);
-    let mut suggheap: BinaryHeap<HeapItem<String>> = BinaryHeap::with_capacity(MAX_NGRAM_SUGG);
+    let mut suggheap: BinaryHeap<HeapItem<String>> =
+        BinaryHeap::with_capacity(config.max_ngram_forms);
     let mut uniq: FnvHashSet<String> = FnvHashSet::default();
     for HeapItem { word: root, .. } in rootscores {
+        if collector.timed_out() {
+            return;
+        }
         dict.affix_data
             .generate_words_from_root(root, dict, |sugg| {
                 if uniq.contains(sugg) {
@@ -399,10 +497,12 @@ pub fn ngram_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mut
                 if score <= heuristic {
                     return;
                 }
-                if suggheap.len() == MAX_NGRAM_SUGG && score > suggheap.peek().unwrap().score {
+                if suggheap.len() == config.max_ngram_forms
+                    && score > suggheap.peek().unwrap().score
+                {
                     suggheap.pop();
                 }
-                if suggheap.len() < MAX_NGRAM_SUGG {
+                if suggheap.len() < config.max_ngram_forms {
                     suggheap.push(HeapItem {
                         word: sugg.to_string(),
                         score,
@@ -410,20 +510,50 @@ pub fn ngram_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mut
                 }
             });
     }
-    for HeapItem { word: sugg, .. } in suggheap.into_sorted_vec() {
-        collector.suggest(&sugg);
+    let mut suggs = suggheap.into_sorted_vec();
+    let affix_data = &dict.affix_data;
+    if affix_data.max_diff.is_some() || affix_data.only_max_diff {
+        // Hunspell's default for MAXDIFF is 5.
+        let max_diff = affix_data.max_diff.unwrap_or(5);
+        let (good, bad): (Vec<_>, Vec<_>) = suggs
+            .into_iter()
+            .partition(|item| similar_enough(&wvec, &item.word, max_diff));
+        suggs = good;
+        // Without ONLYMAXDIFF, the best suggestion stays even if it's
+        // too different, as long as there is nothing better.
+        if suggs.is_empty() && !affix_data.only_max_diff {
+            suggs.extend(bad.into_iter().next());
+        }
+    }
+    for HeapItem { word: sugg, score } in suggs {
+        collector.suggest_with_score(&sugg, score);
         if collector.limit() {
             return;
         }
     }
 }
 
+/// Check if `sugg` is close enough to the misspelled word for MAXDIFF,
+/// which goes from 0 (only the word itself) to 10 (anything).
+/// The ngram score of the two is compared with what it would be if
+/// they were the same, so that long words don't get an advantage.
+fn similar_enough(wvec: &[char], sugg: &str, max_diff: u8) -> bool {
+    let svec = sugg.chars().collect::<Vec<char>>();
+    let shared = 2 * ngram(3, wvec, &svec);
+    let whole = ngram(3, wvec, wvec) + ngram(3, &svec, &svec);
+    10 * shared >= usize::from(10 - max_diff) * whole
+}
+
 /// Same method as ngram suggestions, but using the delins scoring algorithm.
 pub fn delins_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mut SuggCollector) {
-    collector.new_source("ngram");
+    collector.new_source("delins");
     if collector.limit() {
         return;
     }
+    let config = dict.suggestion_config();
+    if config.max_delins_forms == 0 {
+        return;
+    }
 
     // The logic is reversed compared to ngram because delins scores are
     // lower = better.
@@ -447,32 +577,46 @@ pub fn delins_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mu
 
     let wvec = word.chars().collect::<Vec<char>>();
 
-    let mut rootscores: Vec<HeapItem<&String>> = dict
+    let mut rootscores: Vec<HeapItem<&str>> = dict
         .words
         .par_iter()
-        .filter_map(|(root, homonyms)| {
+        .map(|(root, homonyms)| {
+            // This scan is the slowest part of the search, so it stops
+            // as soon as the time is up.
+            if collector.timed_out() {
+                return None;
+            }
             for winfo in homonyms.iter() {
                 if winfo.word_flags.intersects(
                     WordFlags::Forbidden | WordFlags::NoSuggest | WordFlags::OnlyInCompound,
                 ) {
-                    return None;
+                    return Some(None);
                 }
             }
 
             let rvec = root.chars().collect::<Vec<char>>();
             if rvec.len() > wvec.len() + 2 {
-                return None;
+                return Some(None);
             }
             let score = delins(&wvec, &rvec, wvec.len());
-            Some(HeapItem { word: root, score })
+            Some(Some(HeapItem { word: root, score }))
         })
+        .while_some()
+        .flatten()
         .collect();
+    if collector.timed_out() {
+        return;
+    }
     rootscores.par_sort_unstable();
-    rootscores.truncate(MAX_DELINS_ROOTS);
+    rootscores.truncate(config.max_delins_roots);
 
-    let mut suggheap: BinaryHeap<HeapItem<String>> = BinaryHeap::with_capacity(MAX_DELINS_SUGG);
+    let mut suggheap: BinaryHeap<HeapItem<String>> =
+        BinaryHeap::with_capacity(config.max_delins_forms);
     let mut uniq: FnvHashSet<String> = FnvHashSet::default();
     for HeapItem { word: root, .. } in rootscores {
+        if collector.timed_out() {
+            return;
+        }
         dict.affix_data
             .generate_words_from_root(root, dict, |sugg| {
                 if uniq.contains(sugg) {
@@ -487,10 +631,12 @@ pub fn delins_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mu
                 if score > MAX_DELINS_SCORE {
                     return;
                 }
-                if suggheap.len() == MAX_DELINS_SUGG && score < suggheap.peek().unwrap().score {
+                if suggheap.len() == config.max_delins_forms
+                    && score < suggheap.peek().unwrap().score
+                {
                     suggheap.pop();
                 }
-                if suggheap.len() < MAX_DELINS_SUGG {
+                if suggheap.len() < config.max_delins_forms {
                     suggheap.push(HeapItem {
                         word: sugg.to_string(),
                         score,
@@ -498,8 +644,8 @@ pub fn delins_suggestions(word: &str, dict: &SpellerHunspellDict, collector: &mu
                 }
             });
     }
-    for HeapItem { word: sugg, .. } in suggheap.into_sorted_vec() {
-        collector.suggest(&sugg);
+    for HeapItem { word: sugg, score } in suggheap.into_sorted_vec() {
+        collector.suggest_with_score(&sugg, score);
         if collector.limit() {
             return;
         }
@@ -4,6 +4,7 @@ use bitflags::bitflags;
 
 bitflags! {
     #[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct WordFlags: u16 {
         /// This word must not be accepted as good.
         const Forbidden = 0x0001;
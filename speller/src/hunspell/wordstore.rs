@@ -0,0 +1,197 @@
+//! Storage for the words of a dictionary.
+//!
+//! This is a hash index over one shared string rather than an FST or a
+//! double-array trie. Those would share the common prefixes of words
+//! and so take less memory, but the dictionary gains words all the time,
+//! from the user dict and from accepting words, and an FST can't be
+//! added to without building it again. A trie that can be added to
+//! keeps a node per letter, which costs more than the prefixes save at
+//! the sizes of hunspell dictionaries. Words are also looked up much
+//! more often than they are added, and a hash lookup stays fast for the
+//! long words of compounding languages. Keeping the words together in
+//! one string already saves most of what a map with a `String` per word
+//! spends on allocations.
+
+use anyhow::{anyhow, Result};
+use fnv::FnvHasher;
+use rayon::prelude::*;
+use smallvec::SmallVec;
+use std::hash::Hasher;
+
+/// The words of a dictionary, each with its list of entries.
+///
+/// All the words are kept one after the other in a single string, with
+/// a hash table of their indexes next to it. A map with a `String` key
+/// per word spends more memory on the allocations and pointers of the
+/// keys than on the words themselves, and going through all the words,
+/// as the ngram suggestions do, then jumps all over memory. Here that
+/// is one pass over a few big vectors.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordStore<T> {
+    /// All the words, one after the other.
+    text: String,
+    /// Where each word ends in `text`. A word starts where the one
+    /// before it ends.
+    ends: Vec<u32>,
+    /// The entries of each word. Most words have only one.
+    entries: Vec<SmallVec<[T; 1]>>,
+    /// Hash table of word index + 1, with 0 for an empty bucket. Its
+    /// length is 0 or a power of two.
+    buckets: Vec<u32>,
+    /// For each word, the word index + 1 of the next word in the same
+    /// bucket, or 0 if it's the last one.
+    next: Vec<u32>,
+}
+
+impl<T> Default for WordStore<T> {
+    fn default() -> Self {
+        WordStore {
+            text: String::new(),
+            ends: Vec::new(),
+            entries: Vec::new(),
+            buckets: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+}
+
+impl<T> WordStore<T> {
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Make room for `additional` more words.
+    pub fn reserve(&mut self, additional: usize) {
+        self.ends.reserve(additional);
+        self.entries.reserve(additional);
+        self.next.reserve(additional);
+        let wanted = (self.len() + additional).next_power_of_two();
+        if wanted > self.buckets.len() {
+            self.rehash(wanted);
+        }
+    }
+
+    fn word(&self, idx: usize) -> &str {
+        let start = if idx == 0 { 0 } else { self.ends[idx - 1] };
+        &self.text[start as usize..self.ends[idx] as usize]
+    }
+
+    fn bucket(&self, word: &str) -> usize {
+        let mut hasher = FnvHasher::default();
+        hasher.write(word.as_bytes());
+        // The table length is a power of two, so this is the modulo.
+        hasher.finish() as usize & (self.buckets.len() - 1)
+    }
+
+    fn find(&self, word: &str) -> Option<usize> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let mut link = self.buckets[self.bucket(word)];
+        while link != 0 {
+            let idx = link as usize - 1;
+            if self.word(idx) == word {
+                return Some(idx);
+            }
+            link = self.next[idx];
+        }
+        None
+    }
+
+    // Only called for words that are already stored, and `push` checks
+    // that the index of each of those fits.
+    fn link(&mut self, idx: usize) {
+        let bucket = self.bucket(self.word(idx));
+        self.next[idx] = self.buckets[bucket];
+        self.buckets[bucket] = idx as u32 + 1;
+    }
+
+    fn rehash(&mut self, size: usize) {
+        self.buckets = vec![0; size];
+        for idx in 0..self.len() {
+            self.link(idx);
+        }
+    }
+
+    pub fn get(&self, word: &str) -> Option<&[T]> {
+        self.find(word).map(|idx| &self.entries[idx][..])
+    }
+
+    pub fn contains_key(&self, word: &str) -> bool {
+        self.find(word).is_some()
+    }
+
+    /// Add an entry for `word`, after the entries it already has.
+    /// Returns an error if there is no room for another word, because
+    /// the indexes and the offsets into the text are 32 bits.
+    pub fn push(&mut self, word: &str, entry: T) -> Result<()> {
+        if let Some(idx) = self.find(word) {
+            self.entries[idx].push(entry);
+            return Ok(());
+        }
+        let full = || anyhow!("No room for more words after {}", self.len());
+        // Word index + 1 has to fit in the links.
+        u32::try_from(self.len() + 1).map_err(|_| full())?;
+        let end = u32::try_from(self.text.len() + word.len()).map_err(|_| full())?;
+        // Keep the table at most full, so that the chains stay short.
+        if self.len() >= self.buckets.len() {
+            self.rehash((self.buckets.len() * 2).max(16));
+        }
+        self.text.push_str(word);
+        self.ends.push(end);
+        self.entries.push(SmallVec::from_buf([entry]));
+        self.next.push(0);
+        self.link(self.len() - 1);
+        Ok(())
+    }
+
+    /// Returns the words with their entries, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[T])> {
+        (0..self.len()).map(|idx| (self.word(idx), &self.entries[idx][..]))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        (0..self.len()).map(|idx| self.word(idx))
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&str, &[T])>
+    where
+        T: Sync,
+    {
+        (0..self.len())
+            .into_par_iter()
+            .map(|idx| (self.word(idx), &self.entries[idx][..]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_word_store() {
+        let mut store: WordStore<i8> = WordStore::default();
+        assert_eq!(None, store.get("foo"));
+
+        store.push("foo", 1).unwrap();
+        store.push("bar", 2).unwrap();
+        store.push("foo", 3).unwrap();
+        store.push("", 4).unwrap();
+        assert_eq!(Some(&[1, 3][..]), store.get("foo"));
+        assert_eq!(Some(&[2][..]), store.get("bar"));
+        assert_eq!(Some(&[4][..]), store.get(""));
+        assert!(!store.contains_key("fo"));
+        assert!(!store.contains_key("foobar"));
+        assert_eq!(vec!["foo", "bar", ""], store.keys().collect::<Vec<_>>());
+
+        // Growing the table keeps the words findable.
+        for i in 0..1000 {
+            store.push(&format!("word{i}"), 0).unwrap();
+        }
+        assert_eq!(1003, store.len());
+        assert_eq!(Some(&[1, 3][..]), store.get("foo"));
+        assert_eq!(Some(&[0][..]), store.get("word999"));
+        assert_eq!(1003, store.par_iter().count());
+    }
+}
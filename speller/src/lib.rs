@@ -1,15 +1,68 @@
 #![warn(missing_debug_implementations)]
 #![allow(clippy::similar_names)]
 
+#[cfg(feature = "std-fs")]
 use anyhow::Result;
+#[cfg(feature = "std-fs")]
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 mod affix_trie;
 mod delins;
 mod hunspell;
 pub mod ngram;
+#[cfg(feature = "system-hunspell")]
+pub mod system_hunspell;
 
-pub use crate::hunspell::SpellerHunspellDict;
+pub use crate::hunspell::{
+    BreakPolicy, SpellerBuilder, SpellerConfig, SpellerHunspellDict, SuggestionConfig,
+};
+#[cfg(feature = "system-hunspell")]
+pub use crate::system_hunspell::SpellerHunspellLib;
+
+/// The result of a search for suggestions, which says why there are
+/// no suggestions if none were found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Suggestions {
+    /// At least one suggestion was found.
+    Found(Vec<String>),
+    /// Nothing similar enough to the word was found.
+    NoCandidates,
+    /// Similar words were found, but they are all forbidden or marked
+    /// as words that should not be suggested.
+    AllForbidden,
+    /// The search was stopped by its time limit before it found anything.
+    TimedOut,
+    /// The word is too long to be a word, so no suggestions were looked
+    /// for. See `SpellerConfig::max_word_length`.
+    TooLong,
+}
+
+/// A suggested correction together with how it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    pub word: String,
+    /// The name of the method that found it, such as "swap_char", "rep",
+    /// or "ngram".
+    pub source: &'static str,
+    /// How close the word is to the misspelled word, for the methods
+    /// that search the whole dictionary. For "ngram" a higher score is
+    /// closer; for "delins" it's the number of letters deleted and
+    /// inserted, so lower is closer. The other methods make one small
+    /// change to the word and have no score.
+    pub score: Option<usize>,
+}
+
+impl Suggestions {
+    /// Returns the suggestions that were found, which may be none.
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            Suggestions::Found(suggs) => suggs,
+            _ => Vec::new(),
+        }
+    }
+}
 
 pub trait Speller {
     /// Returns true if the word is in the dictionary, otherwise false.
@@ -19,19 +72,138 @@ pub trait Speller {
     /// The list may be empty.
     fn suggestions(&self, word: &str, max: usize) -> Vec<String>;
 
+    /// Like `suggestions`, but if there are no suggestions it says why.
+    /// Spellers that can't tell always report `NoCandidates`.
+    fn suggestions_with_reason(&self, word: &str, max: usize) -> Suggestions {
+        let suggs = self.suggestions(word, max);
+        if suggs.is_empty() {
+            Suggestions::NoCandidates
+        } else {
+            Suggestions::Found(suggs)
+        }
+    }
+
+    /// Like `suggestions_with_reason`, but stops looking after `budget`
+    /// has passed and returns whatever was found by then. This is for
+    /// callers that must stay responsive, such as an interactive editor.
+    /// Spellers that can't stop early ignore the budget.
+    fn suggestions_with_budget(&self, word: &str, max: usize, _budget: Duration) -> Suggestions {
+        self.suggestions_with_reason(word, max)
+    }
+
+    /// Like `suggestions_with_budget`, but also stops looking as soon as
+    /// `cancel` is set, for example by another thread when the
+    /// suggestions are no longer wanted. What was found before that is
+    /// returned, but it may be far from the best suggestions.
+    /// Spellers that can't stop early ignore `cancel`.
+    fn suggestions_cancellable(
+        &self,
+        word: &str,
+        max: usize,
+        budget: Duration,
+        _cancel: &AtomicBool,
+    ) -> Suggestions {
+        self.suggestions_with_budget(word, max, budget)
+    }
+
+    /// Like `suggestions`, but says for each suggestion which method
+    /// found it. Spellers that can't tell give "unknown" as the source.
+    fn suggestions_detailed(&self, word: &str, max: usize) -> Vec<Suggestion> {
+        self.suggestions(word, max)
+            .into_iter()
+            .map(|word| Suggestion {
+                word,
+                source: "unknown",
+                score: None,
+            })
+            .collect()
+    }
+
+    /// Change which sources of suggestions are tried and how much work
+    /// they may do. Spellers that can't be tuned ignore this.
+    fn set_suggestion_config(&mut self, _config: SuggestionConfig) {}
+
     /// Accept `word` into the dictionary.
     /// Returns false if the word could not be accepted (for example
     /// if it contained characters the dictionary can't handle),
     /// otherwise returns true.
     fn add_word(&mut self, word: &str) -> bool;
 
-    /// Load words from `path` (one word per line), and in the future
+    /// Accept all of `words` into the dictionary, as with `add_word`.
+    /// This is for long lists such as a project's accepted words, which
+    /// some spellers can add faster than one word at a time.
+    /// Returns how many of the words were accepted.
+    fn add_words(&mut self, words: &[&str]) -> usize {
+        words.iter().filter(|word| self.add_word(word)).count()
+    }
+
+    /// Accept `word` into the dictionary with the same affixes as the
+    /// existing word `model`, so that for example "Valyrian" accepted
+    /// like "Victorian" also accepts "Valyrians".
+    /// Returns false if `model` is not in the dictionary or `word` could
+    /// not be accepted.
+    fn add_word_like(&mut self, word: &str, model: &str) -> bool;
+
+    /// Returns the forms of `word`, including `word` itself, that would
+    /// be accepted after `add_word_like(word, model)`. This does not
+    /// change the dictionary. The list may be empty if the speller can't
+    /// tell, or if `model` is not in the dictionary.
+    fn words_like(&self, word: &str, model: &str) -> Vec<String>;
+
+    /// Returns a dictionary word that would make a good model for
+    /// `add_word_like`, because it has affixes and ends the same way as
+    /// `word`. Returns None if no such word was found.
+    fn suggest_model(&self, word: &str) -> Option<String>;
+
+    /// Like `suggest_model`, but gives up and returns None once `budget`
+    /// has passed or as soon as `cancel` is set, because finding the
+    /// model may mean going through the whole dictionary.
+    /// Spellers that can't stop early ignore both.
+    fn suggest_model_cancellable(
+        &self,
+        word: &str,
+        _budget: Duration,
+        _cancel: &AtomicBool,
+    ) -> Option<String> {
+        self.suggest_model(word)
+    }
+
+    /// Returns true if the dictionary marks `word` as a word to avoid,
+    /// such as an obscenity or a known bad form, with hunspell's
+    /// FORBIDDENWORD or NOSUGGEST flag. Accepting such a word deserves a
+    /// second thought. Spellers that can't tell return false.
+    fn is_discouraged(&self, _word: &str) -> bool {
+        false
+    }
+
+    /// Returns the words that were accepted into the dictionary after it
+    /// was loaded, with `add_word`, `add_word_like`, or from the user dict,
+    /// in no particular order. Words that the dictionary already had are
+    /// included if they were accepted again.
+    /// Spellers that can't tell return no words.
+    fn user_words(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Load words from `path` (one word per line, with lines starting
+    /// with `#` being comments), and in the future
     /// append words to that file when `add_word_to_user_dict` is called.
+    /// Words may have affix flags as `word/FLAGS`, and the first line
+    /// may be a word count, as in a hunspell personal dictionary.
+    /// Words added with `add_word_like_to_user_dict` are stored as
+    /// `word<TAB>like:model`.
     /// The file is created if it does not exist yet.
     /// Returns the number of words loaded from the file.
+    #[cfg(feature = "std-fs")]
     fn set_user_dict(&mut self, path: &Path) -> Result<i32>;
 
     /// Accept `word` into the dictionary and add it to the user dict file
     /// that was set with `set_user_dict`.
+    #[cfg(feature = "std-fs")]
     fn add_word_to_user_dict(&mut self, word: &str) -> Result<bool>;
+
+    /// Accept `word` like `model`, as with `add_word_like`, and add it to
+    /// the user dict file together with the model.
+    #[cfg(feature = "std-fs")]
+    fn add_word_like_to_user_dict(&mut self, word: &str, model: &str) -> Result<bool>;
 }
@@ -0,0 +1,248 @@
+//! A Speller that calls the hunspell C library, enabled with the
+//! `system-hunspell` feature. It is meant for comparing this crate's
+//! behavior against the reference implementation.
+//!
+//! The library is linked as `hunspell-1.7` unless the `HUNSPELL_LIB`
+//! environment variable names another one at build time.
+
+use anyhow::{bail, Context, Result};
+use encoding::{DecoderTrap, EncoderTrap, Encoding};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::fs::{read_to_string, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::hunspell::encoding_from_name;
+use crate::{Speller, SpellerHunspellDict};
+
+#[repr(C)]
+struct Hunhandle {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn Hunspell_create(affpath: *const c_char, dpath: *const c_char) -> *mut Hunhandle;
+    fn Hunspell_destroy(handle: *mut Hunhandle);
+    fn Hunspell_spell(handle: *mut Hunhandle, word: *const c_char) -> c_int;
+    fn Hunspell_get_dic_encoding(handle: *mut Hunhandle) -> *const c_char;
+    fn Hunspell_suggest(
+        handle: *mut Hunhandle,
+        slst: *mut *mut *mut c_char,
+        word: *const c_char,
+    ) -> c_int;
+    fn Hunspell_free_list(handle: *mut Hunhandle, slst: *mut *mut *mut c_char, n: c_int);
+    fn Hunspell_add(handle: *mut Hunhandle, word: *const c_char) -> c_int;
+    fn Hunspell_add_with_affix(
+        handle: *mut Hunhandle,
+        word: *const c_char,
+        example: *const c_char,
+    ) -> c_int;
+}
+
+/// The hunspell object. It may be used from any thread, but only from
+/// one at a time, because hunspell changes its own state while checking
+/// a word. That is what the Mutex around it is for.
+struct Handle(*mut Hunhandle);
+
+unsafe impl Send for Handle {}
+
+/// A speller that uses the system's hunspell library
+pub struct SpellerHunspellLib {
+    handle: Mutex<Handle>,
+    /// The name of the dictionary's encoding. The encoding itself can't
+    /// be kept here, because it can't be shared between threads.
+    encoding_name: String,
+    user_dict: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for SpellerHunspellLib {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpellerHunspellLib")
+            .field("encoding", &self.encoding_name)
+            .field("user_dict", &self.user_dict)
+            .finish_non_exhaustive()
+    }
+}
+
+fn path_cstring(path: &Path) -> Result<CString> {
+    CString::new(path.to_string_lossy().as_bytes())
+        .with_context(|| format!("Invalid path {}", path.display()))
+}
+
+impl SpellerHunspellLib {
+    /// Returns a Speller that uses the hunspell library with a
+    /// Hunspell-format dictionary and affix file.
+    pub fn new(dictionary: &Path, affixes: &Path) -> Result<Self> {
+        // Hunspell_create does not report missing files, so check them here.
+        for path in [dictionary, affixes] {
+            if !path.exists() {
+                bail!("Could not find {}", path.display());
+            }
+        }
+        let dpath = path_cstring(dictionary)?;
+        let affpath = path_cstring(affixes)?;
+        let handle = unsafe { Hunspell_create(affpath.as_ptr(), dpath.as_ptr()) };
+        if handle.is_null() {
+            bail!("hunspell could not load {}", dictionary.display());
+        }
+        let encoding_name = unsafe { CStr::from_ptr(Hunspell_get_dic_encoding(handle)) };
+        let encoding_name = encoding_name.to_string_lossy().into_owned();
+        Ok(SpellerHunspellLib {
+            handle: Mutex::new(Handle(handle)),
+            encoding_name,
+            user_dict: None,
+        })
+    }
+
+    /// Call `f` with the hunspell object, while no other thread uses it.
+    fn with_handle<R>(&self, f: impl FnOnce(*mut Hunhandle) -> R) -> R {
+        let handle = self.handle.lock().unwrap_or_else(|e| e.into_inner());
+        f(handle.0)
+    }
+
+    fn encoding(&self) -> &'static dyn Encoding {
+        encoding_from_name(&self.encoding_name)
+    }
+
+    /// Convert a word to the dictionary's encoding.
+    /// Returns None if the word can't be represented in it.
+    fn encode(&self, word: &str) -> Option<CString> {
+        let bytes = self.encoding().encode(word, EncoderTrap::Strict).ok()?;
+        CString::new(bytes).ok()
+    }
+
+    fn append_to_user_dict(&self, word: &str, model: Option<&str>) -> Result<()> {
+        if let Some(user_dict) = &self.user_dict {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .open(user_dict)
+                .with_context(|| format!("Could not append to {}", user_dict.display()))?;
+            file.write_all(SpellerHunspellDict::user_dict_line(word, model).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn decode(&self, word: &CStr) -> String {
+        self.encoding()
+            .decode(word.to_bytes(), DecoderTrap::Replace)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for SpellerHunspellLib {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut().unwrap_or_else(|e| e.into_inner());
+        unsafe { Hunspell_destroy(handle.0) };
+    }
+}
+
+impl Speller for SpellerHunspellLib {
+    fn spellcheck(&self, word: &str) -> bool {
+        match self.encode(word.trim()) {
+            Some(word) => self.with_handle(|h| unsafe { Hunspell_spell(h, word.as_ptr()) != 0 }),
+            None => false,
+        }
+    }
+
+    fn suggestions(&self, word: &str, max: usize) -> Vec<String> {
+        let Some(word) = self.encode(word.trim()) else {
+            return Vec::new();
+        };
+        self.with_handle(|h| {
+            let mut slst: *mut *mut c_char = std::ptr::null_mut();
+            let n = unsafe { Hunspell_suggest(h, &mut slst, word.as_ptr()) };
+            let mut suggs = Vec::new();
+            for i in 0..usize::try_from(n).unwrap_or(0) {
+                let sugg = unsafe { CStr::from_ptr(*slst.add(i)) };
+                if suggs.len() < max {
+                    suggs.push(self.decode(sugg));
+                }
+            }
+            if n > 0 {
+                unsafe { Hunspell_free_list(h, &mut slst, n) };
+            }
+            suggs
+        })
+    }
+
+    fn add_word(&mut self, word: &str) -> bool {
+        let word = word.trim();
+        if word.is_empty() {
+            return false;
+        }
+        match self.encode(word) {
+            Some(word) => self.with_handle(|h| unsafe { Hunspell_add(h, word.as_ptr()) == 0 }),
+            None => false,
+        }
+    }
+
+    fn add_word_like(&mut self, word: &str, model: &str) -> bool {
+        let word = word.trim();
+        // hunspell adds the word even if the model is unknown,
+        // so check the model first.
+        if word.is_empty() || !self.spellcheck(model) {
+            return false;
+        }
+        match (self.encode(word), self.encode(model.trim())) {
+            (Some(word), Some(model)) => self.with_handle(|h| unsafe {
+                Hunspell_add_with_affix(h, word.as_ptr(), model.as_ptr()) == 0
+            }),
+            _ => false,
+        }
+    }
+
+    // The C API can't list the forms a word would get,
+    // or search the dictionary for a model.
+    fn words_like(&self, _word: &str, _model: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn suggest_model(&self, _word: &str) -> Option<String> {
+        None
+    }
+
+    fn set_user_dict(&mut self, path: &Path) -> Result<i32> {
+        if !path.exists() {
+            File::create(path).with_context(|| format!("Could not create {}", path.display()))?;
+        }
+        let dict =
+            read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+
+        self.user_dict = Some(path.to_path_buf());
+
+        let mut added = 0;
+        // The C API has no way to add a word with flags, so they are ignored.
+        for entry in SpellerHunspellDict::user_dict_entries(&dict) {
+            let success = match entry.model {
+                Some(model) => self.add_word_like(&entry.word, model) || self.add_word(&entry.word),
+                None => self.add_word(&entry.word),
+            };
+            if success {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    fn add_word_to_user_dict(&mut self, word: &str) -> Result<bool> {
+        if word.contains(['\n', '\r']) || !self.add_word(word) {
+            return Ok(false);
+        }
+
+        self.append_to_user_dict(word, None)?;
+        Ok(true)
+    }
+
+    fn add_word_like_to_user_dict(&mut self, word: &str, model: &str) -> Result<bool> {
+        if word.contains(['\n', '\r', '\t'])
+            || model.contains(['\n', '\r', '\t'])
+            || !self.add_word_like(word, model)
+        {
+            return Ok(false);
+        }
+
+        self.append_to_user_dict(word, Some(model.trim()))?;
+        Ok(true)
+    }
+}
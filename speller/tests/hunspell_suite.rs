@@ -0,0 +1,140 @@
+//! Run test cases in the format of hunspell's own test suite.
+//!
+//! A case is a `NAME.aff` and `NAME.dic` with at least one of:
+//! * `NAME.good`: words that must be accepted, one per line
+//! * `NAME.wrong`: words that must be rejected, one per line
+//! * `NAME.sug`: the suggestions for the words in `NAME.wrong`, one
+//!   line per word that has any, separated by ", "
+//!
+//! The word lists are in the encoding given by the `.aff` file, as in
+//! hunspell. The cases in `tests/files` are run as a normal test. To
+//! see how a whole directory of upstream cases fares, such as the
+//! `tests` directory of a hunspell checkout, run
+//! `HUNSPELL_TESTS=path/to/hunspell/tests cargo test --test hunspell_suite -- --ignored --nocapture`
+
+use encoding::label::encoding_from_whatwg_label;
+use encoding::DecoderTrap;
+use std::fs::{read, read_dir};
+use std::path::Path;
+
+use speller::{Speller, SpellerHunspellDict};
+
+/// Number of suggestions to ask for. Hunspell gives at most 15.
+const MAX_SUGGESTIONS: usize = 15;
+
+/// Read a word list of case `name`, if it has one with extension `ext`.
+/// The words are decoded with the encoding of the case's `.aff` file.
+fn read_list(dir: &Path, name: &str, ext: &str) -> Option<Vec<String>> {
+    let bytes = read(dir.join(format!("{}.{}", name, ext))).ok()?;
+    let aff = read(dir.join(format!("{}.aff", name))).ok()?;
+    let encoding = aff
+        .split(|b| *b == b'\n')
+        .find_map(|line| line.strip_prefix(b"SET "))
+        .and_then(|label| encoding_from_whatwg_label(String::from_utf8_lossy(label).trim()))
+        .unwrap_or(encoding::all::UTF_8);
+    let text = encoding.decode(&bytes, DecoderTrap::Replace).unwrap();
+    Some(
+        text.lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+    )
+}
+
+/// Run case `name`, and return what went wrong with it.
+fn run_case(dir: &Path, name: &str) -> Vec<String> {
+    let dicpath = dir.join(format!("{}.dic", name));
+    let affpath = dir.join(format!("{}.aff", name));
+    let speller = match SpellerHunspellDict::new(&dicpath, &affpath) {
+        Ok(speller) => speller,
+        Err(e) => return vec![format!("could not load: {:#}", e)],
+    };
+
+    let mut failures = Vec::new();
+    for word in read_list(dir, name, "good").unwrap_or_default() {
+        if !speller.spellcheck(&word) {
+            failures.push(format!("rejected good word {}", word));
+        }
+    }
+    let wrong = read_list(dir, name, "wrong").unwrap_or_default();
+    for word in &wrong {
+        if speller.spellcheck(word) {
+            failures.push(format!("accepted wrong word {}", word));
+        }
+    }
+    if let Some(expected) = read_list(dir, name, "sug") {
+        // Like hunspell's test script, leave out the words that get no
+        // suggestions.
+        let found: Vec<String> = wrong
+            .iter()
+            .map(|word| speller.suggestions(word, MAX_SUGGESTIONS).join(", "))
+            .filter(|suggs| !suggs.is_empty())
+            .collect();
+        if found != expected {
+            failures.push(format!("suggested {:?} instead of {:?}", found, expected));
+        }
+    }
+    failures
+}
+
+/// The names of the cases in `dir`, in order.
+fn case_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = read_dir(dir)
+        .unwrap()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "aff" {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let has_lists = ["good", "wrong", "sug"]
+                .iter()
+                .any(|ext| dir.join(format!("{}.{}", name, ext)).exists());
+            (has_lists && dir.join(format!("{}.dic", name)).exists()).then_some(name)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Run all the cases in `dir`, print how each went, and return the
+/// names of the ones that failed.
+fn run_suite(dir: &Path) -> Vec<String> {
+    let names = case_names(dir);
+    let mut failed = Vec::new();
+    for name in &names {
+        let failures = run_case(dir, name);
+        if failures.is_empty() {
+            println!("PASS {}", name);
+        } else {
+            println!("FAIL {}", name);
+            for failure in failures {
+                println!("    {}", failure);
+            }
+            failed.push(name.clone());
+        }
+    }
+    println!(
+        "{} of {} cases passed",
+        names.len() - failed.len(),
+        names.len()
+    );
+    failed
+}
+
+#[test]
+fn bundled_cases() {
+    let failed = run_suite(Path::new("tests/files"));
+    assert!(failed.is_empty(), "failed: {}", failed.join(", "));
+}
+
+#[test]
+#[ignore]
+fn upstream_cases() {
+    let Some(dir) = std::env::var_os("HUNSPELL_TESTS") else {
+        println!("Set HUNSPELL_TESTS to a directory of hunspell test cases");
+        return;
+    };
+    // Not all of hunspell's features are supported, so this only reports.
+    run_suite(Path::new(&dir));
+}
@@ -0,0 +1,112 @@
+//! Measure the quality of suggestions against a corpus of misspellings
+//! and their expected corrections, in `tests/files/suggest-corpus`.
+//!
+//! This uses the full dictionaries in `benches/files`, so it is slow and
+//! ignored by default. Run it with
+//! `cargo test --release --test suggest_quality -- --ignored --nocapture`
+//! to see the top-1 and top-3 accuracy for each language.
+
+use std::fs::read_to_string;
+use std::path::Path;
+
+use speller::{Speller, SpellerHunspellDict};
+
+/// Number of suggestions to ask for, same as the ck3spell GUI.
+const MAX_SUGGESTIONS: usize = 9;
+
+struct Score {
+    total: usize,
+    top1: usize,
+    top3: usize,
+    found: usize,
+}
+
+impl Score {
+    fn percent(&self, n: usize) -> f64 {
+        100.0 * n as f64 / self.total as f64
+    }
+}
+
+fn score_corpus(locale: &str) -> Score {
+    let dictpath = format!("benches/files/{}.dic", locale);
+    let affpath = format!("benches/files/{}.aff", locale);
+    let speller = SpellerHunspellDict::new(Path::new(&dictpath), Path::new(&affpath)).unwrap();
+    let corpus = read_to_string(format!("tests/files/suggest-corpus/{}.tsv", locale)).unwrap();
+
+    let mut score = Score {
+        total: 0,
+        top1: 0,
+        top3: 0,
+        found: 0,
+    };
+    for line in corpus.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let (misspelled, expected) = line.split_once('\t').unwrap();
+        // Catch mistakes in the corpus itself.
+        assert!(
+            !speller.spellcheck(misspelled),
+            "{}: {} is not misspelled",
+            locale,
+            misspelled
+        );
+        assert!(
+            expected.split(' ').all(|word| speller.spellcheck(word)),
+            "{}: {} is not in the dictionary",
+            locale,
+            expected
+        );
+
+        let suggs = speller.suggestions(misspelled, MAX_SUGGESTIONS);
+        let rank = suggs.iter().position(|sugg| sugg == expected);
+        score.total += 1;
+        match rank {
+            Some(0) => score.top1 += 1,
+            Some(1..=2) => score.top3 += 1,
+            Some(_) => score.found += 1,
+            None => eprintln!("{}: {} -> {:?}", locale, misspelled, suggs),
+        }
+    }
+    // Make the counts cumulative
+    score.top3 += score.top1;
+    score.found += score.top3;
+    score
+}
+
+fn report(locale: &str) {
+    let score = score_corpus(locale);
+    println!(
+        "{}: {} words, top-1 {:.1}%, top-3 {:.1}%, top-{} {:.1}%",
+        locale,
+        score.total,
+        score.percent(score.top1),
+        score.percent(score.top3),
+        MAX_SUGGESTIONS,
+        score.percent(score.found),
+    );
+}
+
+#[test]
+#[ignore]
+fn suggest_quality_en() {
+    report("en_US");
+}
+
+#[test]
+#[ignore]
+fn suggest_quality_de() {
+    report("de_DE");
+}
+
+#[test]
+#[ignore]
+fn suggest_quality_fr() {
+    report("fr_FR");
+}
+
+#[test]
+#[ignore]
+fn suggest_quality_es() {
+    report("es_ES");
+}
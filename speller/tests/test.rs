@@ -1,6 +1,11 @@
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
 
-use speller::{Speller, SpellerHunspellDict};
+use speller::{
+    BreakPolicy, Speller, SpellerConfig, SpellerHunspellDict, Suggestion, SuggestionConfig,
+    Suggestions,
+};
 
 fn load_speller(name: &str) -> impl Speller {
     let dictpath = format!("tests/files/{}.dic", name);
@@ -12,6 +17,22 @@ fn load_speller(name: &str) -> impl Speller {
     speller
 }
 
+fn load_speller_with_breaks(name: &str, policy: BreakPolicy) -> impl Speller {
+    let dictpath = format!("tests/files/{}.dic", name);
+    let affpath = format!("tests/files/{}.aff", name);
+    let mut speller = SpellerHunspellDict::new(Path::new(&dictpath), Path::new(&affpath)).unwrap();
+    speller.set_break_policy(policy);
+    speller
+}
+
+fn load_speller_normalized(name: &str) -> impl Speller {
+    let dictpath = format!("tests/files/{}.dic", name);
+    let affpath = format!("tests/files/{}.aff", name);
+    let mut speller = SpellerHunspellDict::new(Path::new(&dictpath), Path::new(&affpath)).unwrap();
+    speller.set_normalize_apostrophes(true);
+    speller
+}
+
 #[test]
 fn match_root_words() {
     let speller = load_speller("en_US");
@@ -357,6 +378,55 @@ fn mixed_case_all_caps2() {
     assert!(!sugg(&speller, "iPodos", "iPodos", 3));
 }
 
+#[test]
+fn all_caps_added_words() {
+    // Words added before and after the case-folded index is built
+    // should both be found in all caps.
+    let mut speller = load_speller("en_US");
+    assert!(speller.add_word("Valyrian"));
+    assert!(speller.spellcheck("VALYRIAN"));
+    assert!(speller.add_word("zot"));
+    assert!(speller.spellcheck("ZOT"));
+
+    let dictpath = Path::new("tests/files/en_US.dic");
+    let affpath = Path::new("tests/files/en_US.aff");
+    let speller = SpellerHunspellDict::new(dictpath, affpath).unwrap();
+    speller.build_folded_index();
+    assert!(speller.spellcheck("REAPPEAR"));
+}
+
+#[test]
+fn add_words() {
+    let mut speller = load_speller("en_US");
+    assert_eq!(2, speller.add_words(&["Valyrian", "", "zot"]));
+    assert!(speller.spellcheck("Valyrian"));
+    assert!(speller.spellcheck("VALYRIAN"));
+    assert!(speller.spellcheck("zot"));
+    assert!(!speller.spellcheck("Zots"));
+    let mut user_words: Vec<&str> = speller.user_words().collect();
+    user_words.sort_unstable();
+    assert_eq!(vec!["Valyrian", "zot"], user_words);
+}
+
+#[test]
+fn words_with_prefix() {
+    let dictpath = Path::new("tests/files/en_US.dic");
+    let affpath = Path::new("tests/files/en_US.aff");
+    let mut speller = SpellerHunspellDict::new(dictpath, affpath).unwrap();
+    let words: Vec<String> = speller.words_with_prefix("ang").collect();
+    assert_eq!(vec!["angle", "anglicism"], words);
+    speller.add_word("angstrom");
+    assert_eq!(3, speller.words_with_prefix("ang").count());
+    assert_eq!(0, speller.words_with_prefix("angles").count());
+
+    let words: Vec<String> = speller.words_with_flag("G").unwrap().collect();
+    assert_eq!(
+        vec!["angle", "anoint", "appear", "apply", "blood", "have"],
+        words
+    );
+    assert!(speller.words_with_flag("GD").is_err());
+}
+
 #[test]
 fn forbidden_break() {
     let speller = load_speller("forbidden-break");
@@ -370,6 +440,126 @@ fn forbidden_break() {
     assert!(!speller.spellcheck("foo-bar")); // This one is marked forbidden
 }
 
+#[test]
+fn break_policy_lenient() {
+    let speller = load_speller_with_breaks("en_US", BreakPolicy::Lenient);
+
+    assert!(speller.spellcheck("angle-appear"));
+    assert!(speller.spellcheck("-angle-"));
+    assert!(speller.spellcheck("----angle---"));
+    assert!(speller.spellcheck("angle--appear"));
+}
+
+#[test]
+fn break_policy_strict() {
+    let speller = load_speller_with_breaks("en_US", BreakPolicy::Strict);
+
+    assert!(speller.spellcheck("angle-appear"));
+    assert!(speller.spellcheck("angle-appear-apply"));
+    assert!(speller.spellcheck("-angle-"));
+    assert!(speller.spellcheck("Blood-Brothers"));
+    assert!(!speller.spellcheck("----angle---"));
+    assert!(!speller.spellcheck("--angle"));
+    assert!(!speller.spellcheck("angle--"));
+    assert!(!speller.spellcheck("angle--appear"));
+    assert!(!speller.spellcheck("angle-apear"));
+}
+
+#[test]
+fn break_policy_strict_forbidden() {
+    let speller = load_speller_with_breaks("forbidden-break", BreakPolicy::Strict);
+
+    assert!(speller.spellcheck("foo-gnu"));
+    assert!(speller.spellcheck("gnu-bar-foo"));
+    assert!(!speller.spellcheck("foo-bar"));
+    assert!(!speller.spellcheck("foo-bar-gnu"));
+    assert!(!speller.spellcheck("gnu-foo-bar"));
+}
+
+#[test]
+fn break_policy_flag_all() {
+    let speller = load_speller_with_breaks("en_US", BreakPolicy::FlagAll);
+
+    assert!(speller.spellcheck("angle"));
+    assert!(!speller.spellcheck("angle-appear"));
+    assert!(!speller.spellcheck("-angle-"));
+}
+
+#[test]
+fn break_table() {
+    // From the hunspell break test
+    let speller = load_speller("break");
+
+    assert!(speller.spellcheck("foo"));
+    assert!(speller.spellcheck("fox-bax"));
+    assert!(speller.spellcheck("foo-bar"));
+    assert!(speller.spellcheck("foo–bar"));
+    assert!(speller.spellcheck("foo-bar-foo-bar"));
+    assert!(speller.spellcheck("foo-bar–foo-bar"));
+    assert!(speller.spellcheck("bar-baz"));
+    assert!(speller.spellcheck("e-mail"));
+    assert!(speller.spellcheck("e-mail-foo"));
+    assert!(speller.spellcheck("foo-e-mail"));
+    assert!(speller.spellcheck("e-mail-e-mail"));
+
+    assert!(!speller.spellcheck("fox"));
+    assert!(!speller.spellcheck("-foo"));
+    assert!(!speller.spellcheck("bar-"));
+    assert!(!speller.spellcheck("fox-bar"));
+    assert!(!speller.spellcheck("foo–bax"));
+    assert!(!speller.spellcheck("foo-bax-foo-bar"));
+    assert!(!speller.spellcheck("foo-bar–fox-bar"));
+    assert!(!speller.spellcheck("foo-baz"));
+}
+
+#[test]
+fn break_table_strict() {
+    let speller = load_speller_with_breaks("break", BreakPolicy::Strict);
+
+    assert!(speller.spellcheck("foo-bar–foo-bar"));
+    assert!(!speller.spellcheck("foo-baz-foo"));
+    assert!(!speller.spellcheck("foo--bar"));
+}
+
+#[test]
+fn break_table_off() {
+    // From the hunspell breakoff test
+    let speller = load_speller("breakoff");
+
+    assert!(speller.spellcheck("foo"));
+    assert!(speller.spellcheck("e-mail"));
+    assert!(!speller.spellcheck("foo-bar"));
+    assert!(!speller.spellcheck("-foo"));
+}
+
+#[test]
+fn break_compound() {
+    let speller = load_speller("break-compound");
+
+    assert!(speller.spellcheck("firewood"));
+    assert!(speller.spellcheck("l'firewood"));
+    assert!(speller.spellcheck("firewood’s"));
+    assert!(speller.spellcheck("l'firewood’s"));
+    assert!(speller.spellcheck("fire—wood"));
+    assert!(speller.spellcheck("l'fire—woodfire’s"));
+    assert!(speller.spellcheck("l'l'fire"));
+
+    assert!(!speller.spellcheck("l'"));
+    assert!(!speller.spellcheck("’s"));
+    assert!(!speller.spellcheck("l'firewod’s"));
+    assert!(!speller.spellcheck("fire—"));
+    assert!(!speller.spellcheck("—wood"));
+}
+
+#[test]
+fn break_compound_strict() {
+    let speller = load_speller_with_breaks("break-compound", BreakPolicy::Strict);
+
+    assert!(speller.spellcheck("l'firewood’s"));
+    assert!(!speller.spellcheck("l'l'fire"));
+    assert!(!speller.spellcheck("firewood’s’s"));
+}
+
 #[test]
 fn titlecase_break() {
     let speller = load_speller("en_US");
@@ -388,6 +578,47 @@ fn numeric_break() {
     assert!(speller.spellcheck("15-foot"));
 }
 
+#[test]
+fn locale_numbers() {
+    // The language comes from the affix file's name, as it has no LANG.
+    let speller = load_speller("en_US");
+    for word in ["1,000", "1,000.50", "-1,000.--", "1 000", "3.5"] {
+        assert!(speller.spellcheck(word), "{}", word);
+    }
+    for word in ["1'000", "1,5", "1.000,50", "1.000.000"] {
+        assert!(!speller.spellcheck(word), "{}", word);
+    }
+
+    let speller = load_speller("de_DE");
+    for word in ["1.000", "1.000,50", "3,5"] {
+        assert!(speller.spellcheck(word), "{}", word);
+    }
+    for word in ["1'000", "1,000.50", "3.5"] {
+        assert!(!speller.spellcheck(word), "{}", word);
+    }
+}
+
+#[test]
+fn number_suffixes() {
+    let dictpath = "tests/files/en_US.dic";
+    let affpath = "tests/files/en_US.aff";
+    let mut speller = SpellerHunspellDict::new(Path::new(dictpath), Path::new(affpath)).unwrap();
+
+    for word in ["50%", "12°", "12°C", "-40°F", "3½", "1 000", "1 000%", "2‰"] {
+        assert!(speller.spellcheck(word), "{}", word);
+    }
+    assert!(!speller.spellcheck("%"));
+    assert!(!speller.spellcheck("°C"));
+    assert!(!speller.spellcheck("50%%"));
+    assert!(!speller.spellcheck("1,%"));
+    assert!(!speller.spellcheck("50K"));
+
+    speller.set_number_suffixes(&["K"]);
+    assert!(speller.spellcheck("50K"));
+    assert!(!speller.spellcheck("50%"));
+    assert!(speller.spellcheck("50"));
+}
+
 #[test]
 fn needaffix_continuation() {
     // test "needaffix5" from hunspell
@@ -429,6 +660,20 @@ fn suggest_a_lot() {
     assert!(sugg(&speller, "alot", "a lot", 3));
 }
 
+#[test]
+fn suggest_priority_order() {
+    let speller = load_speller("priority");
+
+    // A REP entry with a space goes before the other suggestions, even
+    // ones found before it such as a change of case.
+    assert_eq!(vec!["a lot", "Alot"], speller.suggestions("alot", 9)[..2]);
+    assert_eq!(vec!["a lot"], speller.suggestions("alot", 1));
+
+    // A word pair in the dictionary replaces the other suggestions, but
+    // not the REP entries with a space.
+    assert_eq!(vec!["ab cd", "abc d"], speller.suggestions("abcd", 9));
+}
+
 #[test]
 fn suggest_related_chars() {
     let speller = load_speller("fr_FR");
@@ -579,6 +824,66 @@ fn test_compounding() {
     assert!(!speller.spellcheck("-Abdeck")); // only in compound
 }
 
+#[test]
+fn test_compounding_blob() {
+    let speller = load_speller("compoundblob");
+
+    assert!(speller.spellcheck("aaaaa"));
+    // Every way of dividing the a's into pieces fails at the b, and
+    // trying them all one by one would take practically forever.
+    let blob = "a".repeat(80) + "b";
+    assert!(!speller.spellcheck(&blob));
+}
+
+#[test]
+fn test_config() {
+    let mut speller = SpellerHunspellDict::new(
+        Path::new("tests/files/de_DE.dic"),
+        Path::new("tests/files/de_DE.aff"),
+    )
+    .unwrap();
+    assert_eq!(SpellerConfig::default(), speller.config());
+    assert!(speller.spellcheck("Abdeckzirkular"));
+
+    speller.set_config(SpellerConfig {
+        max_compound_attempts: 3,
+        ..SpellerConfig::default()
+    });
+    assert!(!speller.spellcheck("Abdeckzirkular"));
+}
+
+#[test]
+fn test_max_word_length() {
+    let mut speller = SpellerHunspellDict::new(
+        Path::new("tests/files/en_US.dic"),
+        Path::new("tests/files/en_US.aff"),
+    )
+    .unwrap();
+    // A pasted blob of base64 isn't a misspelled word.
+    let blob = "aGVsbG8gd29ybGQgaGVsbG8gd29ybGQ".repeat(4);
+    assert!(speller.spellcheck(&blob));
+    assert_eq!(
+        Suggestions::TooLong,
+        speller.suggestions_with_reason(&blob, 5)
+    );
+    assert!(!speller.spellcheck("xyzzy"));
+
+    speller.set_config(SpellerConfig {
+        max_word_length: 4,
+        ..SpellerConfig::default()
+    });
+    assert!(speller.spellcheck("xyzzy"));
+    assert!(!speller.spellcheck("xyzy"));
+
+    // 0 means no limit.
+    speller.set_config(SpellerConfig {
+        max_word_length: 0,
+        ..SpellerConfig::default()
+    });
+    assert!(!speller.spellcheck("xyzzy"));
+    assert!(!speller.spellcheck(&"xyzzy".repeat(40)));
+}
+
 #[test]
 fn test_iconv() {
     // From hunspell iconv test
@@ -593,6 +898,26 @@ fn test_iconv() {
     assert!(speller.spellcheck("ţ")); // t-cedilla
 }
 
+#[test]
+fn test_composed_accents() {
+    let speller = load_speller("fr_FR");
+    assert!(speller.spellcheck("Néréide"));
+    assert!(speller.spellcheck("Ne\u{0301}re\u{0301}ide"));
+    assert!(sugg(&speller, "Ne\u{0301}reide", "Néréide", 3));
+
+    // The dictionary has its word decomposed.
+    let speller = load_speller("decomposed");
+    assert!(speller.spellcheck("café"));
+    assert!(speller.spellcheck("cafe\u{0301}"));
+    assert!(sugg(&speller, "caffé", "café", 3));
+
+    // Also when the dictionary has ICONV rules.
+    let speller = load_speller("decomposed-iconv");
+    assert!(speller.spellcheck("café"));
+    assert!(speller.spellcheck("cafe\u{0301}"));
+    assert!(speller.spellcheck("l’été"));
+}
+
 #[test]
 fn test_iconv_longest() {
     // From hunspell iconv2 test
@@ -650,3 +975,634 @@ fn test_keepcase() {
     assert!(!speller.spellcheck("Nato-word"));
     assert!(!speller.spellcheck("word-nato"));
 }
+
+#[test]
+fn test_user_dict_roundtrip() {
+    let path = std::env::temp_dir().join(format!("speller-user-dict-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut speller = load_speller("suggest");
+    assert_eq!(0, speller.set_user_dict(&path).unwrap());
+    for word in ["and/or", "#hashtag", "back\\slash"] {
+        assert!(speller.add_word_to_user_dict(word).unwrap());
+    }
+    assert!(!speller.add_word_to_user_dict("two\nlines").unwrap());
+
+    let mut speller = load_speller("suggest");
+    assert_eq!(3, speller.set_user_dict(&path).unwrap());
+    assert!(speller.spellcheck("and/or"));
+    assert!(speller.spellcheck("#hashtag"));
+    assert!(speller.spellcheck("back\\slash"));
+    assert!(!speller.spellcheck("and"));
+    assert!(!speller.spellcheck("two"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_user_dict_with_flags() {
+    let mut speller = load_speller("2sfx");
+    assert_eq!(
+        2,
+        speller
+            .set_user_dict(Path::new("tests/files/user_flags.dic"))
+            .unwrap()
+    );
+    assert!(speller.spellcheck("bar"));
+    assert!(speller.spellcheck("bars"));
+    assert!(speller.spellcheck("unbar"));
+    assert!(speller.spellcheck("quux"));
+    assert!(!speller.spellcheck("quuxs"));
+    let mut words: Vec<&str> = speller.user_words().collect();
+    words.sort_unstable();
+    assert_eq!(vec!["bar", "quux"], words);
+}
+
+#[test]
+fn test_add_word_like() {
+    let mut speller = load_speller("en_US");
+    assert!(!speller.spellcheck("Valyria's"));
+    assert!(speller.add_word_like("Valyria", "Alberta"));
+    assert!(speller.spellcheck("Valyria"));
+    assert!(speller.spellcheck("Valyria's"));
+
+    assert!(speller.add_word_like("zot", "lot"));
+    assert!(speller.spellcheck("zots"));
+    assert!(speller.spellcheck("zot's"));
+
+    assert!(!speller.add_word_like("quux", "nosuchword"));
+    assert!(!speller.spellcheck("quux"));
+}
+
+#[test]
+fn test_user_words() {
+    let mut speller = load_speller("en_US");
+    assert_eq!(0, speller.user_words().count());
+
+    assert!(speller.add_word("Valyrian"));
+    assert!(speller.add_word_like("zot", "lot"));
+    assert!(!speller.add_word_like("quux", "nosuchword"));
+    let mut words: Vec<&str> = speller.user_words().collect();
+    words.sort_unstable();
+    assert_eq!(vec!["Valyrian", "zot"], words);
+}
+
+#[test]
+fn test_words_like() {
+    let speller = load_speller("en_US");
+    assert_eq!(
+        vec!["zot", "zot's", "zots"],
+        speller.words_like("zot", "lot")
+    );
+    assert!(speller.words_like("zot", "nosuchword").is_empty());
+    assert!(!speller.spellcheck("zots"));
+}
+
+#[test]
+fn test_suggest_model() {
+    let speller = load_speller("en_US");
+    assert_eq!(
+        Some("Alberta".to_string()),
+        speller.suggest_model("Roberta")
+    );
+    assert_eq!(Some("blood".to_string()), speller.suggest_model("flood"));
+    assert_eq!(None, speller.suggest_model("xyzzy"));
+
+    let budget = Duration::from_secs(60);
+    let cancel = AtomicBool::new(false);
+    assert_eq!(
+        Some("blood".to_string()),
+        speller.suggest_model_cancellable("flood", budget, &cancel)
+    );
+    let cancel = AtomicBool::new(true);
+    assert_eq!(
+        None,
+        speller.suggest_model_cancellable("flood", budget, &cancel)
+    );
+    assert_eq!(
+        None,
+        speller.suggest_model_cancellable("flood", Duration::ZERO, &AtomicBool::new(false))
+    );
+}
+
+#[test]
+fn test_user_dict_add_like() {
+    let path = std::env::temp_dir().join(format!("speller-user-like-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut speller = load_speller("en_US");
+    assert_eq!(0, speller.set_user_dict(&path).unwrap());
+    assert!(speller.add_word_like_to_user_dict("zot", "lot").unwrap());
+    assert!(!speller
+        .add_word_like_to_user_dict("quux", "nosuchword")
+        .unwrap());
+
+    let mut speller = load_speller("en_US");
+    assert_eq!(1, speller.set_user_dict(&path).unwrap());
+    assert!(speller.spellcheck("zots"));
+    assert!(!speller.spellcheck("quux"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn load_from_text() {
+    let dic = std::fs::read_to_string("tests/files/en_US.dic").unwrap();
+    let aff = std::fs::read_to_string("tests/files/en_US.aff").unwrap();
+    let speller = SpellerHunspellDict::from_text(&dic, &aff).unwrap();
+
+    assert!(speller.spellcheck("anglers"));
+    assert!(speller.spellcheck("reappear"));
+    assert!(!speller.spellcheck("apear"));
+}
+
+#[test]
+fn load_from_strings() {
+    let speller = SpellerHunspellDict::from_strings(
+        "SET UTF-8\nSFX S Y 1\nSFX S 0 s .\n",
+        "2\nangler/S\nappear\n",
+    )
+    .unwrap();
+
+    assert!(speller.spellcheck("anglers"));
+    assert!(speller.spellcheck("appear"));
+    assert!(!speller.spellcheck("appears"));
+}
+
+#[test]
+fn builder_options() {
+    let speller = SpellerHunspellDict::builder()
+        .dictionary(
+            Path::new("tests/files/en_US.dic"),
+            Path::new("tests/files/en_US.aff"),
+        )
+        .wordlist(Path::new("tests/files/wordlist.txt"))
+        .suggestion_config(SuggestionConfig::fast())
+        .build_folded_index(true)
+        .build()
+        .unwrap();
+    assert!(speller.spellcheck("Valyrian"));
+    assert!(speller.spellcheck("VALYRIAN"));
+    assert_eq!(&SuggestionConfig::fast(), speller.suggestion_config());
+
+    assert!(SpellerHunspellDict::builder().build().is_err());
+
+    let dic = std::fs::read_to_string("tests/files/apostrophe.dic").unwrap();
+    let aff = std::fs::read_to_string("tests/files/apostrophe.aff").unwrap();
+    let speller = SpellerHunspellDict::builder()
+        .normalize_apostrophes(true)
+        .build_from_text(&dic, &aff)
+        .unwrap();
+    assert!(speller.spellcheck("don\u{2019}t"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() {
+    let speller = SpellerHunspellDict::new(
+        Path::new("tests/files/en_US.dic"),
+        Path::new("tests/files/en_US.aff"),
+    )
+    .unwrap();
+    let json = serde_json::to_string(&speller).unwrap();
+    let speller: SpellerHunspellDict = serde_json::from_str(&json).unwrap();
+
+    assert!(speller.spellcheck("anglers"));
+    assert!(speller.spellcheck("REAPPEAR"));
+    assert!(!speller.spellcheck("apear"));
+    assert_eq!(vec!["appear"], speller.suggestions("apear", 1));
+}
+
+#[test]
+fn apostrophes_not_normalized() {
+    let speller = load_speller("apostrophe");
+
+    assert!(speller.spellcheck("don't"));
+    assert!(!speller.spellcheck("don\u{2019}t"));
+}
+
+#[test]
+fn apostrophes_normalized() {
+    let speller = load_speller_normalized("apostrophe");
+
+    assert!(speller.spellcheck("don't"));
+    assert!(speller.spellcheck("don\u{2019}t"));
+    assert!(speller.spellcheck("l\u{2018}homme"));
+    assert!(speller.spellcheck("l\u{2bc}homme"));
+    assert!(!speller.spellcheck("l\u{2019}homm"));
+
+    let suggs = speller.suggestions("l\u{2019}homm", 5);
+    assert_eq!(Some(&"l\u{2019}homme".to_string()), suggs.first());
+    let suggs = speller.suggestions("l'homm", 5);
+    assert_eq!(Some(&"l'homme".to_string()), suggs.first());
+}
+
+#[test]
+fn apostrophes_normalized_curly_dict() {
+    let speller = load_speller_normalized("apostrophe-curly");
+
+    assert!(speller.spellcheck("don't"));
+    assert!(speller.spellcheck("don\u{2019}t"));
+
+    let suggs = speller.suggestions("l'homm", 5);
+    assert_eq!(Some(&"l'homme".to_string()), suggs.first());
+}
+
+#[test]
+fn apostrophes_normalized_add_word() {
+    let mut speller = load_speller_normalized("apostrophe");
+
+    assert!(!speller.spellcheck("o'clock"));
+    speller.add_word("o\u{2019}clock");
+    assert!(speller.spellcheck("o'clock"));
+    assert!(speller.spellcheck("o\u{2019}clock"));
+}
+
+#[test]
+fn suggest_dedup_case() {
+    let speller = load_speller("suggest-case");
+
+    let suggs = speller.suggestions("parris", 5);
+    assert_eq!(
+        1,
+        suggs.iter().filter(|s| s.to_lowercase() == "paris").count()
+    );
+    assert!(suggs.contains(&"paris".to_string()));
+
+    let suggs = speller.suggestions("Parris", 5);
+    assert_eq!(
+        1,
+        suggs.iter().filter(|s| s.to_lowercase() == "paris").count()
+    );
+    assert!(suggs.contains(&"Paris".to_string()));
+
+    // Both paris and Paris become PARIS, which is the word itself.
+    let suggs = speller.suggestions("PARIS", 5);
+    assert_eq!(vec!["PARISH".to_string()], suggs);
+
+    let suggs = speller.suggestions("PAARIS", 5);
+    assert_eq!(vec!["PARIS".to_string()], suggs);
+}
+
+#[test]
+fn suggest_reason() {
+    let speller = load_speller("suggest-forbidden");
+
+    assert_eq!(
+        Suggestions::Found(vec!["foo".to_string()]),
+        speller.suggestions_with_reason("fooo", 5)
+    );
+    assert_eq!(
+        Suggestions::AllForbidden,
+        speller.suggestions_with_reason("qwerti", 5)
+    );
+    assert_eq!(
+        Suggestions::NoCandidates,
+        speller.suggestions_with_reason("zzzzzzzz", 5)
+    );
+}
+
+#[test]
+fn discouraged_words() {
+    let speller = load_speller("suggest-forbidden");
+    assert!(speller.is_discouraged("qwerty"));
+    assert!(speller.is_discouraged("Qwerty"));
+    assert!(!speller.is_discouraged("foo"));
+    assert!(!speller.is_discouraged("qwerti"));
+
+    let speller = load_speller("forbidden-break");
+    assert!(speller.is_discouraged("foo-bar"));
+    assert!(!speller.is_discouraged("foo"));
+}
+
+#[test]
+fn suggest_time_limit() {
+    let dictpath = "tests/files/suggest-forbidden.dic";
+    let affpath = "tests/files/suggest-forbidden.aff";
+    let mut speller = SpellerHunspellDict::new(Path::new(dictpath), Path::new(affpath)).unwrap();
+
+    speller.set_suggestion_time_limit(Some(Duration::ZERO));
+    assert_eq!(
+        Suggestions::TimedOut,
+        speller.suggestions_with_reason("fooo", 5)
+    );
+    assert!(speller.suggestions("fooo", 5).is_empty());
+
+    speller.set_suggestion_time_limit(None);
+    assert_eq!(vec!["foo".to_string()], speller.suggestions("fooo", 5));
+}
+
+#[test]
+fn suggest_budget() {
+    let speller = load_speller("suggest-forbidden");
+
+    assert_eq!(
+        Suggestions::TimedOut,
+        speller.suggestions_with_budget("fooo", 5, Duration::ZERO)
+    );
+    assert_eq!(
+        Suggestions::Found(vec!["foo".to_string()]),
+        speller.suggestions_with_budget("fooo", 5, Duration::from_secs(60))
+    );
+}
+
+#[test]
+fn suggest_budget_large_dictionary() {
+    // Enough long words that scanning them all for ngram and delins
+    // suggestions takes much longer than the budget.
+    const WORDS: usize = 300_000;
+    let mut seed: u32 = 1;
+    let mut dic = format!("{}\n", WORDS);
+    for _ in 0..WORDS {
+        for _ in 0..30 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            dic.push(char::from(b'a' + (seed >> 16) as u8 % 16));
+        }
+        dic.push('\n');
+    }
+    let speller = SpellerHunspellDict::from_strings("SET UTF-8\n", &dic).unwrap();
+
+    let word = "zyxwvutsrqzyxwvutsrqzyxwvutsrq";
+    let budget = Duration::from_millis(50);
+    let start = Instant::now();
+    assert_eq!(
+        Suggestions::TimedOut,
+        speller.suggestions_with_budget(word, 5, budget)
+    );
+    let elapsed = start.elapsed();
+    assert!(elapsed < budget * 10, "took {:?}", elapsed);
+}
+
+#[test]
+fn suggest_cancellable() {
+    let speller = load_speller("suggest-forbidden");
+    let budget = Duration::from_secs(60);
+
+    let cancel = AtomicBool::new(true);
+    assert_eq!(
+        Suggestions::TimedOut,
+        speller.suggestions_cancellable("fooo", 5, budget, &cancel)
+    );
+    let cancel = AtomicBool::new(false);
+    assert_eq!(
+        Suggestions::Found(vec!["foo".to_string()]),
+        speller.suggestions_cancellable("fooo", 5, budget, &cancel)
+    );
+}
+
+#[test]
+fn suggest_detailed() {
+    let speller = load_speller("en_US");
+
+    assert_eq!(
+        Some(&Suggestion {
+            word: "appear".to_string(),
+            source: "swap_char",
+            score: None,
+        }),
+        speller.suggestions_detailed("appaer", 3).first()
+    );
+    assert_eq!(
+        Some(&Suggestion {
+            word: "a lot".to_string(),
+            source: "rep",
+            score: None,
+        }),
+        speller.suggestions_detailed("alot", 3).first()
+    );
+
+    // Nothing close enough, so only the ngram search finds something.
+    let suggs = speller.suggestions_detailed("dinosour", 3);
+    assert!(!suggs.is_empty());
+    for sugg in &suggs {
+        assert_eq!("ngram", sugg.source);
+        assert!(sugg.score.is_some());
+    }
+    assert_eq!(
+        suggs.into_iter().map(|s| s.word).collect::<Vec<_>>(),
+        speller.suggestions("dinosour", 3)
+    );
+}
+
+#[test]
+fn suggest_config() {
+    let mut speller = load_speller("en_US");
+    assert!(!speller.suggestions("dinosour", 3).is_empty());
+
+    speller.set_suggestion_config(SuggestionConfig::fast());
+    assert!(speller.suggestions("dinosour", 3).is_empty());
+    assert!(sugg(&speller, "appaer", "appear", 3));
+
+    speller.set_suggestion_config(SuggestionConfig {
+        disabled_sources: vec!["swap_char".to_string()],
+        ..SuggestionConfig::default()
+    });
+    assert!(speller
+        .suggestions_detailed("appaer", 3)
+        .iter()
+        .all(|s| s.source != "swap_char"));
+
+    speller.set_suggestion_config(SuggestionConfig {
+        max_ngram_forms: 1,
+        ..SuggestionConfig::default()
+    });
+    assert_eq!(1, speller.suggestions("dinosour", 3).len());
+
+    // No forms at all is the same as skipping those searches.
+    speller.set_suggestion_config(SuggestionConfig {
+        max_ngram_forms: 0,
+        max_delins_forms: 0,
+        ..SuggestionConfig::default()
+    });
+    assert!(speller.suggestions("dinosour", 3).is_empty());
+}
+
+#[test]
+fn suggest_join_words() {
+    let speller = load_speller("de_DE");
+
+    let suggs = speller.suggestions("Abdeck zirkular", 5);
+    assert_eq!(Some(&"Abdeckzirkular".to_string()), suggs.first());
+
+    let suggs = speller.suggestions("Abdeck Zirkular", 5);
+    assert_eq!(Some(&"Abdeckzirkular".to_string()), suggs.first());
+}
+
+#[test]
+fn dictionary_file_quirks() {
+    for name in ["dic-crlf", "dic-comments", "dic-bom", "dic-nocount"] {
+        let speller = load_speller(name);
+        for word in ["castle", "castles", "keep", "keeps", "moat"] {
+            assert!(speller.spellcheck(word), "{name}: {word}");
+        }
+        assert!(!speller.spellcheck("moats"), "{name}");
+        assert!(!speller.spellcheck("building"), "{name}");
+        assert!(!speller.spellcheck("#"), "{name}");
+    }
+}
+
+#[test]
+fn titlecase_digraphs() {
+    let speller = load_speller("digraph");
+
+    assert!(speller.spellcheck("ǉudima"));
+    assert!(speller.spellcheck("ǈudima"));
+    assert!(speller.spellcheck("ǇUDIMA"));
+    // All caps with a titlecase digraph at the start.
+    assert!(speller.spellcheck("ǈUDIMA"));
+    assert!(speller.spellcheck("ǈubljana"));
+    assert!(speller.spellcheck("Ǉubljana"));
+    assert!(speller.spellcheck("ǇUBLJANA"));
+    assert!(!speller.spellcheck("ǉubljana"));
+    assert!(!speller.spellcheck("ǉUDI"));
+
+    assert!(sugg(&speller, "ǈudii", "ǈudi", 1));
+    assert!(sugg(&speller, "ǇUDII", "ǇUDI", 1));
+    assert!(sugg(&speller, "ǉubljana", "ǈubljana", 1));
+    assert!(sugg(&speller, "ǌivaa", "ǌiva", 1));
+    assert!(sugg(&speller, "ǋivaa", "ǋiva", 1));
+}
+
+#[test]
+fn turkic_casing() {
+    // With LANG tr_TR, i and ı are different letters in every case.
+    let speller = load_speller("turkic");
+
+    for word in ["İyi", "İYİ", "İYİLİ", "ILIK", "İSTANBUL", "IRAK", "İÇIRAK"] {
+        assert!(speller.spellcheck(word), "{}", word);
+    }
+    for word in ["Iyi", "IYI", "İLIK", "ISTANBUL", "istanbul", "İRAK"] {
+        assert!(!speller.spellcheck(word), "{}", word);
+    }
+
+    assert!(sugg(&speller, "İyii", "İyi", 3));
+    assert!(sugg(&speller, "İYİİ", "İYİ", 3));
+    assert!(sugg(&speller, "istanbul", "İstanbul", 3));
+}
+
+#[test]
+fn dictionary_language() {
+    let dictpath = "tests/files/turkic.dic";
+    let affpath = "tests/files/turkic.aff";
+    let speller = SpellerHunspellDict::new(Path::new(dictpath), Path::new(affpath)).unwrap();
+    assert_eq!(Some("tr_TR"), speller.language());
+
+    let dictpath = "tests/files/2sfx.dic";
+    let affpath = "tests/files/2sfx.aff";
+    let speller = SpellerHunspellDict::new(Path::new(dictpath), Path::new(affpath)).unwrap();
+    assert_eq!(None, speller.language());
+}
+
+#[test]
+fn suggest_restored_accents() {
+    let speller = load_speller("accents");
+
+    assert!(sugg(&speller, "interet", "intérêt", 3));
+    assert!(sugg(&speller, "Eleve", "Élève", 3));
+    // The stem's accents are restored in its forms with a suffix too.
+    assert!(sugg(&speller, "interets", "intérêts", 3));
+    // Accents that are only partly there, or in the wrong direction.
+    assert!(sugg(&speller, "intéret", "intérêt", 3));
+    assert!(sugg(&speller, "élêve", "élève", 3));
+    // Long words are found without trying every accent in every place.
+    assert_eq!(
+        Some(&Suggestion {
+            word: "caractéristiquement".to_string(),
+            source: "accents",
+            score: None,
+        }),
+        speller
+            .suggestions_detailed("caracteristiquement", 3)
+            .first()
+    );
+}
+
+#[test]
+fn suggest_related_chars_fewest_first() {
+    // Each s could be any of four other letters, so trying all the
+    // combinations in turn runs out of candidates before it gets to
+    // changing only the last one.
+    let speller = load_speller("related");
+
+    assert_eq!(
+        Some(&Suggestion {
+            word: "sasasasasaz".to_string(),
+            source: "related_char",
+            score: None,
+        }),
+        speller.suggestions_detailed("sasasasasas", 3).first()
+    );
+}
+
+#[test]
+fn suggest_keyboard_override() {
+    // The affix file has a QWERTY keyboard, where "a" isn't next to "z".
+    let mut speller = SpellerHunspellDict::new(
+        Path::new("tests/files/keyboard.dic"),
+        Path::new("tests/files/keyboard.aff"),
+    )
+    .unwrap();
+    assert!(!sugg(&speller, "aone", "zone", 5));
+
+    speller.set_keyboard(Some("azertyuiop|qsdfghjklm|wxcvbn"));
+    assert_eq!(Some("azertyuiop|qsdfghjklm|wxcvbn"), speller.keyboard());
+    assert!(sugg(&speller, "aone", "zone", 5));
+
+    speller.set_keyboard(None);
+    assert_eq!(Some("qwertyuiop|asdfghjkl|zxcvbnm"), speller.keyboard());
+    assert!(!sugg(&speller, "aone", "zone", 5));
+
+    let dic = std::fs::read_to_string("tests/files/keyboard.dic").unwrap();
+    let aff = std::fs::read_to_string("tests/files/keyboard.aff").unwrap();
+    let speller = SpellerHunspellDict::builder()
+        .keyboard("azertyuiop|qsdfghjklm|wxcvbn")
+        .build_from_text(&dic, &aff)
+        .unwrap();
+    assert!(sugg(&speller, "aone", "zone", 5));
+}
+
+#[test]
+fn suggest_ngram_max_diff() {
+    let dic = std::fs::read_to_string("tests/files/maxdiff.dic").unwrap();
+    let aff = std::fs::read_to_string("tests/files/maxdiff.aff").unwrap();
+
+    let speller = SpellerHunspellDict::from_text(&dic, &aff).unwrap();
+    assert_eq!(vec!["elegant"], speller.suggestions("elgnat", 9));
+    // Nothing is close enough, so the best ngram suggestion stays.
+    assert!(sugg(&speller, "phelant", "elephant", 9));
+
+    let speller = SpellerHunspellDict::from_text(&dic, &aff.replace("MAXDIFF 3", "")).unwrap();
+    assert!(sugg(&speller, "elgnat", "relevant", 9));
+
+    let aff = format!("{}ONLYMAXDIFF\n", aff);
+    let speller = SpellerHunspellDict::from_text(&dic, &aff).unwrap();
+    assert!(!sugg(&speller, "phelant", "elephant", 9));
+}
+
+#[test]
+fn suggest_wrong_layout() {
+    let mut speller = SpellerHunspellDict::new(
+        Path::new("tests/files/layout.dic"),
+        Path::new("tests/files/layout.aff"),
+    )
+    .unwrap();
+    assert!(!sugg(&speller, "ghbdtn", "привет", 5));
+
+    speller.set_layout_pairs(&[(
+        "qwertyuiop[]asdfghjkl;'zxcvbnm,.`",
+        "йцукенгшщзхъфывапролджэячсмитьбюё",
+    )]);
+    assert_eq!(
+        Some(&Suggestion {
+            word: "привет".to_string(),
+            source: "wrong_layout",
+            score: None,
+        }),
+        speller.suggestions_detailed("ghbdtn", 5).first()
+    );
+    assert_eq!(vec!["Москва"], speller.suggestions("Vjcrdf", 5));
+    // The other way around.
+    assert_eq!(vec!["hello"], speller.suggestions("руддщ", 5));
+    // Letters that aren't on the layout aren't changed.
+    assert!(!sugg(&speller, "ghbdtnü", "приветü", 5));
+}